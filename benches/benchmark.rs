@@ -1,6 +1,8 @@
+// Explicit returns are the house style.
+#![allow(clippy::needless_return)]
+
 #[macro_use]
 extern crate criterion;
-use tallystick;
 
 use criterion::Benchmark;
 use criterion::Criterion;
@@ -71,6 +73,26 @@ fn condorcet_benchmark(c: &mut Criterion) {
             .sample_size(50)
             .throughput(Throughput::Elements(10_000)),
     );
+
+    // 10K from random with 50 candidates
+    c.bench(
+        "condorcet",
+        Benchmark::new("random-50/10K", |b| {
+            b.iter(|| condorcet(random_transitive_votes_from(10_000, 50, 50), 50))
+        })
+        .sample_size(20)
+        .throughput(Throughput::Elements(10_000)),
+    );
+
+    // 1M pre-aggregated votes from predefined list of candidates and candidate ratios
+    c.bench(
+        "condorcet",
+        Benchmark::new("aggregated/1M", |b| {
+            b.iter(|| condorcet_aggregated(static_aggregated_votes(1_000_000), 10))
+        })
+        .sample_size(50)
+        .throughput(Throughput::Elements(1_000_000)),
+    );
 }
 
 fn stv_benchmark(c: &mut Criterion) {
@@ -126,31 +148,35 @@ fn condorcet<T: Eq + Clone + Hash>(mut votes: Vec<Vec<T>>, num_candidates: usize
     let mut tally = tallystick::condorcet::DefaultCondorcetTally::with_capacity(1, num_candidates);
 
     for vote in votes.drain(0..) {
-        let res = tally.add(vote);
-        match res {
-            Err(_) => panic!("Error adding vote to condorcet tally"),
-            Ok(_) => (),
-        }
+        tally.add(vote).expect("Error adding vote to condorcet tally");
     }
 
     tally.winners();
 }
 
+fn condorcet_aggregated<T: Eq + Clone + Hash>(votes: Vec<(Vec<T>, u64)>, num_candidates: usize) {
+    let mut tally = tallystick::condorcet::DefaultCondorcetTally::with_capacity(1, num_candidates);
+
+    tally.add_aggregated(&votes).expect("Error adding votes to condorcet tally");
+
+    tally.winners();
+}
+
 fn stv<T: Eq + Clone + Hash>(mut votes: Vec<Vec<T>>, num_candidates: usize) {
     let mut tally = tallystick::stv::DefaultTally::with_capacity(1, tallystick::Quota::Droop, num_candidates, votes.len());
 
     for vote in votes.drain(0..) {
-        tally.add(vote);
+        tally.add(vote).unwrap();
     }
 
     tally.winners();
 }
 
 fn schulze<T: Eq + Clone + Hash>(mut votes: Vec<Vec<T>>, num_candidates: usize) {
-    let mut tally = tallystick::schulze::DefaultSchulzeTally::with_capacity(1, tallystick::schulze::Variant::Winning, votes.len());
+    let mut tally = tallystick::schulze::DefaultSchulzeTally::with_capacity(1, tallystick::schulze::Variant::Winning, num_candidates);
 
     for vote in votes.drain(0..) {
-        tally.add(vote);
+        tally.add(vote).unwrap();
     }
 
     tally.winners();
@@ -160,7 +186,7 @@ fn plurality<T: Eq + Clone + Hash>(mut votes: Vec<T>, num_candidates: usize) {
     let mut tally = tallystick::plurality::DefaultPluralityTally::with_capacity(1, num_candidates);
 
     for vote in votes.drain(0..) {
-        tally.add(vote);
+        tally.add(vote).unwrap();
     }
 
     tally.winners();
@@ -170,7 +196,7 @@ fn approval<T: Eq + Clone + Hash>(mut votes: Vec<Vec<T>>, num_candidates: usize)
     let mut tally = tallystick::approval::DefaultApprovalTally::with_capacity(1, num_candidates);
 
     for vote in votes.drain(0..) {
-        tally.add(vote);
+        tally.add(vote).unwrap();
     }
 
     tally.winners();
@@ -190,19 +216,23 @@ fn score<T: Eq + Clone + Hash>(mut votes: Vec<Vec<(T, u64)>>, num_candidates: us
     let mut tally = tallystick::score::DefaultScoreTally::with_capacity(1, num_candidates);
 
     for vote in votes.drain(0..) {
-        tally.add(vote);
+        tally.add(vote).unwrap();
     }
 
     tally.winners();
 }
 
 fn random_transitive_votes(n: u32) -> Vec<Vec<u8>> {
+    random_transitive_votes_from(n, 10, 10)
+}
+
+fn random_transitive_votes_from(n: u32, num_candidates: u8, max_marked: u8) -> Vec<Vec<u8>> {
     let mut rng = thread_rng();
     let mut all_votes = Vec::new();
     for _ in 0..n {
         let mut vote = Vec::<u8>::new();
-        for _ in 0..rng.gen_range(0, 10) {
-            let candidate = rng.gen_range(0, 10);
+        for _ in 0..rng.gen_range(0, max_marked) {
+            let candidate = rng.gen_range(0, num_candidates);
             if !vote.contains(&candidate) {
                 vote.push(candidate);
             }
@@ -255,9 +285,19 @@ fn static_transitive_votes(n: u32) -> Vec<Vec<&'static str>> {
     for _ in 0..(2 * n) {
         all_votes.push(vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"]);
     }
-    for _ in 0..(1 * n) {
+    for _ in 0..n {
         all_votes.push(vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"]);
     }
 
     return all_votes;
 }
+
+fn static_aggregated_votes(n: u64) -> Vec<(Vec<&'static str>, u64)> {
+    let n = n / 10;
+    vec![
+        (vec!["Memphis", "Nashville", "Chattanooga", "Knoxville"], 4 * n),
+        (vec!["Nashville", "Chattanooga", "Knoxville", "Memphis"], 3 * n),
+        (vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"], 2 * n),
+        (vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"], n),
+    ]
+}
@@ -1,10 +1,20 @@
-use num_traits::cast::NumCast;
-use num_traits::Num;
-use std::hash::Hash;
-use std::ops::AddAssign;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::check_duplicate;
 use super::plurality::PluralityTally;
 use super::result::RankedWinners;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
+use super::TallyError;
 
 /// An approval tally using `u64` integers to count votes.
 /// `DefaultApprovalTally` is generally preferred over `ApprovalTally`.
@@ -46,18 +56,19 @@ pub type DefaultApprovalTally<T> = ApprovalTally<T, u64>;
 ///    let winners = tally.winners().into_unranked();
 ///    assert!(winners[0] == "Notorious RBG");
 /// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ApprovalTally<T, C = u64>
 where
-    T: Eq + Clone + Hash,                             // Candidate
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
     plurality: PluralityTally<T, C>,
 }
 
 impl<T, C> ApprovalTally<T, C>
 where
-    T: Eq + Clone + Hash,                             // Candidate
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
     /// Create a new `ApprovalTally` with the given number of winners.
     ///
@@ -76,42 +87,97 @@ where
         };
     }
 
+    /// Create a new `ApprovalTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// See [`PluralityTally::with_candidates()`](../plurality/struct.PluralityTally.html#method.with_candidates) for details.
+    pub fn with_candidates(num_winners: u32, candidates: Candidates<T>) -> Self {
+        return ApprovalTally {
+            plurality: PluralityTally::with_candidates(num_winners, candidates),
+        };
+    }
+
     /// Add a new vote
-    pub fn add(&mut self, mut selection: Vec<T>) {
-        for vote in selection.drain(0..) {
-            self.plurality.add(vote);
-        }
+    ///
+    /// An error is returned if `selection` contains duplicate candidates, or if the tally was created with
+    /// [`with_candidates()`](#method.with_candidates) and `selection` contains an unknown candidate.
+    pub fn add(&mut self, selection: Vec<T>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, C::one())
     }
 
     /// Add a vote by reference.
-    pub fn add_ref(&mut self, selection: &[T]) {
-        for vote in selection {
-            self.plurality.add_ref(vote);
-        }
+    pub fn add_ref(&mut self, selection: &[T]) -> Result<(), TallyError> {
+        self.add_weighted_ref(selection, C::one())
     }
 
     /// Add a weighted vote.
     /// By default takes a weight as a `usize` integer, but can be customized by using `ApprovalTally` with a custom vote type.
-    pub fn add_weighted(&mut self, mut selection: Vec<T>, weight: C) {
-        for vote in selection.drain(0..) {
-            self.plurality.add_weighted(vote, weight);
-        }
+    pub fn add_weighted(&mut self, selection: Vec<T>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, weight)
     }
 
     /// Add a weighted vote by reference.
-    pub fn add_weighted_ref(&mut self, selection: &[T], weight: C) {
+    pub fn add_weighted_ref(&mut self, selection: &[T], weight: C) -> Result<(), TallyError> {
+        check_duplicate(selection)?;
+        if let Some(registry) = &self.plurality.registry {
+            registry.filter(selection)?;
+        }
         for vote in selection {
-            self.plurality.add_weighted_ref(vote, weight);
+            self.plurality.add_weighted_ref(vote, weight)?;
+        }
+        Ok(())
+    }
+
+    /// Add all votes in a `BallotBox`. Every candidate marked on a vote is approved, regardless of rank.
+    ///
+    /// An error will only be returned if the tally was created with [`with_candidates()`](#method.with_candidates)
+    /// and the ballot box contains an unknown candidate.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+        let candidates = ballot_box.candidate_ids();
+        if let Some(registry) = &self.plurality.registry {
+            registry.filter(candidates)?;
+        }
+        for (ballot, weight) in ballot_box.interned() {
+            for id in ballot.candidates() {
+                self.plurality.add_weighted_ref(&candidates[*id], *weight)?;
+            }
         }
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Tallies can be merged in any order. See [`PluralityTally::merge()`](../plurality/struct.PluralityTally.html#method.merge) for details.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.plurality.merge(other.plurality);
+    }
+
+    // Create an empty tally that checks votes against the same registry, to count part of the votes separately.
+    #[cfg(feature = "parallel")]
+    fn fork(&self) -> Self {
+        return ApprovalTally {
+            plurality: self.plurality.fork(),
+        };
     }
 
     /// Get a list of all candidates seen by this tally.
-    /// Candidates are returned in no particular order.
+    /// Candidates are returned in the order they were first seen.
     pub fn candidates(&self) -> Vec<T> {
         return self.plurality.candidates();
     }
 
-    /// Get a ranked list of winners. Winners with the same rank are tied.
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        self.plurality.sort_candidates();
+    }
+
+    /// Get a ranked list of winners. Winners with the same rank are tied, and are listed in the order they were first seen.
     /// The number of winners might be greater than the requested `num_winners` if there is a tie.
     /// In approval voting, the winning candidate(s) is the one most approved by all voters.
     pub fn winners(&self) -> RankedWinners<T> {
@@ -144,7 +210,7 @@ where
         return self.plurality.totals();
     }
 
-    /// Get a ranked list of all candidates. Candidates with the same rank are tied.
+    /// Get a ranked list of all candidates. Candidates with the same rank are tied, and are listed in the order they were first seen.
     /// Candidates are ranked in ascending order. The highest ranked candidate has a rank of `0`.
     ///
     /// # Example
@@ -168,6 +234,55 @@ where
     }
 }
 
+#[cfg(feature = "parallel")]
+impl<T, C> ApprovalTally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send + Sync, // Vote count type
+{
+    /// Create a new `ApprovalTally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and counted into per-thread tallies, which are then merged.
+    /// Requires the `parallel` feature.
+    pub fn from_par_iter<I>(num_winners: u32, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let mut tally = Self::new(num_winners);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// Votes are split across threads and counted into per-thread tallies, which are then merged into this tally.
+    /// If any vote contains duplicate or unknown candidates, an error is returned and this tally is left unchanged.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let tally = votes
+            .into_par_iter()
+            .try_fold(
+                || self.fork(),
+                |mut tally, vote| {
+                    tally.add(vote)?;
+                    Ok(tally)
+                },
+            )
+            .try_reduce(
+                || self.fork(),
+                |mut tally, other| {
+                    tally.plurality.merge_counts(other.plurality);
+                    Ok(tally)
+                },
+            )?;
+        self.plurality.merge_counts(tally.plurality);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,8 +290,8 @@ mod tests {
     #[test]
     fn approval_basic() {
         let mut tally = DefaultApprovalTally::new(1);
-        tally.add_ref(&vec!["Alice"]);
-        tally.add_weighted_ref(&vec!["Alice", "Bob"], 2);
+        tally.add_ref(&["Alice"]).unwrap();
+        tally.add_weighted_ref(&["Alice", "Bob"], 2).unwrap();
 
         let winners = tally.winners().into_unranked();
         assert_eq!(winners, vec!["Alice"]);
@@ -191,13 +306,13 @@ mod tests {
         let titanic = "Titanic";
 
         let mut tally = DefaultApprovalTally::with_capacity(1, 3);
-        tally.add_weighted(vec![scream, matrix], 3);
-        tally.add_weighted(vec![titanic, matrix], 2);
-        tally.add(vec![titanic, scream, matrix]);
-        tally.add(vec![matrix]);
-        tally.add(vec![titanic, scream]);
-        tally.add(vec![titanic]);
-        tally.add(vec![scream]);
+        tally.add_weighted(vec![scream, matrix], 3).unwrap();
+        tally.add_weighted(vec![titanic, matrix], 2).unwrap();
+        tally.add(vec![titanic, scream, matrix]).unwrap();
+        tally.add(vec![matrix]).unwrap();
+        tally.add(vec![titanic, scream]).unwrap();
+        tally.add(vec![titanic]).unwrap();
+        tally.add(vec![scream]).unwrap();
 
         let candidates = tally.candidates();
         assert_eq!(candidates.len(), 3);
@@ -213,4 +328,19 @@ mod tests {
         assert_eq!(winners.contains(&scream), false);
         assert_eq!(winners.contains(&titanic), false);
     }
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn approval_parallel() {
+        let mut votes = Vec::new();
+        for i in 0..5_000 {
+            match i % 3 {
+                0 => votes.push(vec!["Alice", "Bob"]),
+                1 => votes.push(vec!["Bob"]),
+                _ => votes.push(vec!["Carlos", "Bob", "Alice"]),
+            }
+        }
+
+        let tally = DefaultApprovalTally::from_par_iter(1, votes).unwrap();
+        assert_eq!(tally.totals(), vec![("Bob", 5_000), ("Alice", 3_333), ("Carlos", 1_666)]);
+    }
 }
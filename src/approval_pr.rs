@@ -0,0 +1,583 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::check_duplicate;
+use super::result::CountedCandidates;
+use super::result::RankedWinners;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
+use super::TallyError;
+use super::{sorted_order, COUNT_OVERFLOW_PANIC, C_FROM_PANIC};
+
+/// Specifies the rule used to elect a committee from approval votes.
+///
+/// Each rule gives every voter a satisfaction with a committee that depends on how many of the candidates they approve
+/// are elected. The score of a committee is the weighted sum of every voter's satisfaction.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Variant {
+    /// Proportional Approval Voting (PAV). A voter with `n` approved candidates elected has a satisfaction of
+    ///
+    /// ```1 + 1/2 + 1/3 + ... + 1/n```
+    ///
+    /// The committee with the highest score is elected. Every possible committee is scored, so this is only
+    /// practical for small elections: there are `c! / (w! * (c - w)!)` committees of `w` winners from `c` candidates.
+    Proportional,
+
+    /// Sequential Proportional Approval Voting, also known as Reweighted Approval Voting. Winners are elected one at a time,
+    /// each time electing the candidate that increases the PAV score of the committee the most.
+    Sequential,
+
+    /// Reverse Sequential Proportional Approval Voting. Starting with every candidate, candidates are removed one at a time,
+    /// each time removing the candidate whose removal decreases the PAV score of the committee the least.
+    ReverseSequential,
+
+    /// Satisfaction Approval Voting (SAV). A voter who approves `m` candidates, `n` of which are elected, has a satisfaction of `n / m`.
+    /// Each voter splits a single vote evenly between the candidates they approve, and the candidates with the most votes are elected.
+    Satisfaction,
+
+    /// Chamberlin–Courant. A voter has a satisfaction of `1` if at least one candidate they approve is elected, and `0` otherwise.
+    /// Like [`Proportional`](#variant.Proportional), every possible committee is scored.
+    ChamberlinCourant,
+}
+
+impl Variant {
+    /// Get the satisfaction of a voter who approves `num_approved` candidates, `num_elected` of which are on the committee.
+    ///
+    /// This method will panic if using a variant other than [`Variant::ChamberlinCourant`](#variant.ChamberlinCourant)
+    /// with an integer based vote-count type.
+    pub fn satisfaction<C: Numeric + FromPrimitive>(&self, num_elected: usize, num_approved: usize) -> C {
+        if !self.fractional() || num_elected == 0 {
+            return if num_elected == 0 { C::zero() } else { C::one() };
+        }
+        if !C::fraction() {
+            panic!("tallystick::approval_pr::Variant::{:?} cannot be used with an integer count type. Please use a float or a rational.", self)
+        }
+        match self {
+            Variant::Satisfaction => {
+                return C::from_usize(num_elected).expect(C_FROM_PANIC) / C::from_usize(num_approved).expect(C_FROM_PANIC);
+            }
+            _ => {
+                let mut harmonic = C::zero();
+                for i in 1..=num_elected {
+                    harmonic = harmonic + C::one() / C::from_usize(i).expect(C_FROM_PANIC);
+                }
+                return harmonic;
+            }
+        }
+    }
+
+    // Check if voter satisfaction can be fractional, which requires a fractional count type.
+    fn fractional(&self) -> bool {
+        return *self != Variant::ChamberlinCourant;
+    }
+}
+
+/// A proportional approval tally using `f64` floats to count votes.
+/// `DefaultApprovalPrTally` is generally preferred over `ApprovalPrTally`.
+/// Since this is an alias, refer to [`ApprovalPrTally`](struct.ApprovalPrTally.html) for method documentation.
+///
+/// # Example
+/// ```
+///    use tallystick::approval_pr::DefaultApprovalPrTally;
+///    use tallystick::approval_pr::Variant;
+///
+///    // Electing two board members. Most members approve of Alice and Bob, but a sizeable minority only approves of Carlos.
+///    let mut tally = DefaultApprovalPrTally::new(2, Variant::Proportional);
+///    tally.add_weighted(vec!["Alice", "Bob"], 6.0).unwrap();
+///    tally.add_weighted(vec!["Carlos"], 4.0).unwrap();
+///
+///    let winners = tally.winners().into_unranked();
+///    assert_eq!(winners, vec!["Alice", "Carlos"]);
+///    assert_eq!(tally.committee_score(&winners), 10.0);
+/// ```
+pub type DefaultApprovalPrTally<T> = ApprovalPrTally<T, f64>;
+
+/// A generic proportional approval tally, which elects a committee that represents voters in proportion to their approvals.
+///
+/// Unlike [`ApprovalTally`](../approval/struct.ApprovalTally.html), which elects the most approved candidates, a candidate
+/// adds less to the score of a committee the more of a voter's approved candidates are already elected.
+///
+/// Generics:
+/// - `T`: The candidate type.
+/// - `C`: The count type. `f64` is recommended. Every variant except [`Variant::ChamberlinCourant`](enum.Variant.html#variant.ChamberlinCourant)
+///   gives voters a fractional satisfaction, so a float, a [`rational`](https://rust-num.github.io/num/num_rational/index.html)
+///   (with the `rational` feature) or a [`FixedDecimal`](../struct.FixedDecimal.html) must be used.
+///
+/// # Example
+/// ```
+///    use tallystick::approval_pr::ApprovalPrTally;
+///    use tallystick::approval_pr::Variant;
+///
+///    // Chamberlin-Courant scores can be counted with an integer count type.
+///    let mut tally = ApprovalPrTally::<&str, u64>::new(2, Variant::ChamberlinCourant);
+///    tally.add_weighted(vec!["Alice", "Bob"], 6).unwrap();
+///    tally.add_weighted(vec!["Carlos"], 4).unwrap();
+///
+///    let winners = tally.winners().into_unranked();
+///    assert_eq!(winners, vec!["Alice", "Carlos"]);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ApprovalPrTally<T, C = f64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    ballots: BallotBox<T, C>, // Identical votes are stored once, with their total weight
+    num_winners: u32,
+    variant: Variant,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    registry: Option<Candidates<T>>, // The declared candidates, if any
+}
+
+impl<T, C> ApprovalPrTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new `ApprovalPrTally` with the given number of winners, using the given variant.
+    ///
+    /// This method will panic if using a variant other than [`Variant::ChamberlinCourant`](enum.Variant.html#variant.ChamberlinCourant)
+    /// with an integer based vote-count type.
+    pub fn new(num_winners: u32, variant: Variant) -> Self {
+        return Self::with_capacity(num_winners, variant, 0, 0);
+    }
+
+    /// Create a new `ApprovalPrTally` with the given number of winners, number of expected candidates, and number of expected unique votes.
+    pub fn with_capacity(num_winners: u32, variant: Variant, expected_candidates: usize, expected_votes: usize) -> Self {
+        if variant.fractional() && !C::fraction() {
+            panic!("tallystick::approval_pr::Variant::{:?} cannot be used with an integer count type. Please use a float or a rational.", variant)
+        }
+        return ApprovalPrTally {
+            ballots: BallotBox::with_capacity(expected_candidates, expected_votes),
+            num_winners: num_winners,
+            variant: variant,
+            registry: None,
+        };
+    }
+
+    /// Create a new `ApprovalPrTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// Every standing candidate can be elected, even if no votes approve of them.
+    /// See [`Candidates`](../struct.Candidates.html) for details.
+    pub fn with_candidates(num_winners: u32, variant: Variant, candidates: Candidates<T>) -> Self {
+        let mut tally = Self::with_capacity(num_winners, variant, candidates.standing().len(), 0);
+        tally.ballots.declare(candidates.standing());
+        tally.registry = Some(candidates);
+        return tally;
+    }
+
+    /// Add a new vote, approving of every candidate in `selection`.
+    ///
+    /// An error is returned if `selection` contains duplicate candidates, or if the tally was created with
+    /// [`with_candidates()`](#method.with_candidates) and `selection` contains an unknown candidate.
+    pub fn add(&mut self, selection: Vec<T>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, C::one())
+    }
+
+    /// Add a vote by reference.
+    pub fn add_ref(&mut self, selection: &[T]) -> Result<(), TallyError> {
+        self.add_weighted_ref(selection, C::one())
+    }
+
+    /// Add a weighted vote.
+    pub fn add_weighted(&mut self, selection: Vec<T>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, weight)
+    }
+
+    /// Add a weighted vote by reference.
+    pub fn add_weighted_ref(&mut self, selection: &[T], weight: C) -> Result<(), TallyError> {
+        match &self.registry {
+            Some(registry) => {
+                check_duplicate(selection)?;
+                self.ballots.add_weighted_ref(&registry.filter(selection)?, weight)
+            }
+            None => self.ballots.add_weighted_ref(selection, weight),
+        }
+    }
+
+    /// Add all votes in a `BallotBox`. Every candidate marked on a vote is approved, regardless of rank.
+    ///
+    /// An error will only be returned if the tally was created with [`with_candidates()`](#method.with_candidates)
+    /// and the ballot box contains an unknown candidate.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+        if let Some(registry) = &self.registry {
+            registry.check_all(ballot_box.candidate_ids())?;
+        }
+        for (vote, weight) in ballot_box.iter() {
+            let approved: Vec<T> = vote.candidates().cloned().collect();
+            self.add_weighted_ref(&approved, weight)?;
+        }
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Tallies can be merged in any order to get the same result. After merging, candidates are sorted as if by
+    /// [`sort_candidates()`](#method.sort_candidates), so their order doesn't depend on merge order either.
+    /// The variant and number of winners for `other` are ignored.
+    ///
+    /// # Panics
+    /// This method will panic if the merged total weight of all votes overflows the count type.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.ballots.merge(other.ballots);
+        self.sort_candidates();
+    }
+
+    /// Get a list of all candidates seen by this tally.
+    /// Candidates are returned in the order they were first seen.
+    pub fn candidates(&self) -> Vec<T> {
+        return self.ballots.candidates();
+    }
+
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+        let order = sorted_order(self.ballots.candidate_ids(), declared);
+        self.ballots.reorder(&order);
+    }
+
+    /// Get the elected committee as a ranked list of winners.
+    ///
+    /// For [`Variant::Sequential`](enum.Variant.html#variant.Sequential), winners are ranked in the order they were elected.
+    /// For [`Variant::Satisfaction`](enum.Variant.html#variant.Satisfaction), winners are ranked by their share of the votes.
+    /// The other variants elect the committee as a whole, so every winner has a rank of `0`.
+    ///
+    /// If several candidates tie for the last seat of a sequential or satisfaction count, they are all returned with the
+    /// same rank, and the number of winners will be greater than `num_winners`. Other ties are broken in favour of the
+    /// candidates that were seen first. In particular, if several committees have the highest score, the committee
+    /// that includes the candidates seen first is elected.
+    pub fn winners(&self) -> RankedWinners<T> {
+        let candidates = self.ballots.candidate_ids();
+        let num_winners = self.num_winners as usize;
+        let mut winners = RankedWinners::new(self.num_winners);
+        if num_winners == 0 {
+            return winners;
+        }
+        if candidates.len() <= num_winners {
+            for candidate in candidates.iter() {
+                winners.push(candidate.clone(), 0);
+            }
+            return winners;
+        }
+
+        match self.variant {
+            Variant::Proportional | Variant::ChamberlinCourant => {
+                for candidate in self.best_committee() {
+                    winners.push(candidates[candidate].clone(), 0);
+                }
+            }
+            Variant::Sequential => {
+                let mut committee = vec![false; candidates.len()];
+                for rank in 0..num_winners {
+                    // Find the candidates that increase the committee score the most.
+                    let mut best = Vec::new();
+                    let mut best_score = C::zero();
+                    for candidate in 0..candidates.len() {
+                        if committee[candidate] {
+                            continue;
+                        }
+                        committee[candidate] = true;
+                        let score = self.score(&committee);
+                        committee[candidate] = false;
+                        if best.is_empty() || score > best_score {
+                            best = vec![candidate];
+                            best_score = score;
+                        } else if score == best_score {
+                            best.push(candidate);
+                        }
+                    }
+
+                    // Candidates tied for the last seat are all winners.
+                    if rank == num_winners - 1 {
+                        for candidate in best {
+                            winners.push(candidates[candidate].clone(), rank as u32);
+                        }
+                    } else {
+                        committee[best[0]] = true;
+                        winners.push(candidates[best[0]].clone(), rank as u32);
+                    }
+                }
+            }
+            Variant::ReverseSequential => {
+                let mut committee = vec![true; candidates.len()];
+                for _ in num_winners..candidates.len() {
+                    // Remove the candidate that decreases the committee score the least, preferring to keep candidates seen first.
+                    let mut worst = None;
+                    let mut worst_score = C::zero();
+                    for candidate in (0..candidates.len()).rev() {
+                        if !committee[candidate] {
+                            continue;
+                        }
+                        committee[candidate] = false;
+                        let score = self.score(&committee);
+                        committee[candidate] = true;
+                        if worst.is_none() || score > worst_score {
+                            worst = Some(candidate);
+                            worst_score = score;
+                        }
+                    }
+                    committee[worst.unwrap()] = false;
+                }
+                for (candidate, elected) in candidates.iter().zip(committee) {
+                    if elected {
+                        winners.push(candidate.clone(), 0);
+                    }
+                }
+            }
+            Variant::Satisfaction => {
+                let mut shares = vec![C::zero(); candidates.len()];
+                for (vote, weight) in self.ballots.interned() {
+                    let share = *weight / C::from_usize(vote.num_candidates()).expect(C_FROM_PANIC);
+                    for candidate in vote.candidates() {
+                        shares[*candidate] += share;
+                    }
+                }
+                let mut counted = CountedCandidates::new();
+                for (candidate, share) in candidates.iter().zip(shares) {
+                    counted.push(candidate.clone(), share);
+                }
+                return counted.into_ranked(self.num_winners);
+            }
+        }
+        return winners;
+    }
+
+    /// Get the score of a committee. The score is the weighted sum of every voter's satisfaction with the committee,
+    /// as defined by the variant of this tally. See [`Variant::satisfaction()`](enum.Variant.html#method.satisfaction) for details.
+    ///
+    /// Candidates in `committee` that haven't been seen by this tally are ignored.
+    ///
+    /// # Example
+    /// ```
+    ///    use tallystick::approval_pr::DefaultApprovalPrTally;
+    ///    use tallystick::approval_pr::Variant;
+    ///
+    ///    let mut tally = DefaultApprovalPrTally::new(2, Variant::Sequential);
+    ///    tally.add_weighted(vec!["Alice", "Bob"], 6.0).unwrap();
+    ///    tally.add_weighted(vec!["Carlos"], 4.0).unwrap();
+    ///
+    ///    assert_eq!(tally.committee_score(&["Alice", "Bob"]), 9.0);
+    ///    assert_eq!(tally.committee_score(&["Alice", "Carlos"]), 10.0);
+    /// ```
+    ///
+    /// # Panics
+    /// This method will panic if the score overflows the count type.
+    pub fn committee_score(&self, committee: &[T]) -> C {
+        let candidates = self.ballots.candidate_ids();
+        let elected: Vec<bool> = candidates.iter().map(|candidate| committee.contains(candidate)).collect();
+        return self.score(&elected);
+    }
+
+    // Score a committee, given as a flag for each candidate that is set if the candidate is elected.
+    fn score(&self, committee: &[bool]) -> C {
+        let mut score = C::zero();
+        for (vote, weight) in self.ballots.interned() {
+            let num_elected = vote.candidates().filter(|candidate| committee[**candidate]).count();
+            if num_elected > 0 {
+                let satisfaction = self.variant.satisfaction::<C>(num_elected, vote.num_candidates());
+                let weighted = weight.checked_mul(satisfaction).expect(COUNT_OVERFLOW_PANIC);
+                score = score.checked_add(weighted).expect(COUNT_OVERFLOW_PANIC);
+            }
+        }
+        return score;
+    }
+
+    // Score every committee with `num_winners` candidates, returning the first committee with the highest score.
+    // Committees are visited in lexicographic order, so ties are broken in favour of the candidates seen first.
+    fn best_committee(&self) -> Vec<usize> {
+        let num_candidates = self.ballots.candidate_ids().len();
+        let mut committee = vec![false; num_candidates];
+        let mut best = Vec::new();
+        let mut best_score = None;
+        for members in Committees::new(num_candidates, self.num_winners as usize) {
+            for candidate in members.iter() {
+                committee[*candidate] = true;
+            }
+            let score = self.score(&committee);
+            for candidate in members.iter() {
+                committee[*candidate] = false;
+            }
+            if best_score.is_none() || Some(score) > best_score {
+                best = members;
+                best_score = Some(score);
+            }
+        }
+        return best;
+    }
+}
+
+// Iterates over every committee of `size` candidates out of `num_candidates`, as lists of candidate ids in lexicographic order.
+pub(crate) struct Committees {
+    members: Vec<usize>,
+    num_candidates: usize,
+    done: bool,
+}
+
+impl Committees {
+    pub(crate) fn new(num_candidates: usize, size: usize) -> Self {
+        return Committees {
+            members: (0..size).collect(),
+            num_candidates: num_candidates,
+            done: size > num_candidates,
+        };
+    }
+}
+
+impl Iterator for Committees {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        if self.done {
+            return None;
+        }
+        let committee = self.members.clone();
+
+        // Move on to the next committee, by advancing the last member that can still be advanced.
+        let size = self.members.len();
+        match (0..size).rev().find(|i| self.members[*i] < self.num_candidates - size + i) {
+            Some(i) => {
+                self.members[i] += 1;
+                for j in (i + 1)..size {
+                    self.members[j] = self.members[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+        return Some(committee);
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T, C> ApprovalPrTally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send + Sync, // Vote count type
+{
+    /// Create a new `ApprovalPrTally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and collected into per-thread ballot boxes, which are then merged.
+    /// Requires the `parallel` feature.
+    pub fn from_par_iter<I>(num_winners: u32, variant: Variant, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let mut tally = Self::new(num_winners, variant);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// See [`BallotBox::par_add_all()`](../struct.BallotBox.html#method.par_add_all) for details.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        self.ballots.par_add_filtered(votes, self.registry.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three voters approve of Alice and Bob, four approve of Alice and Carlos, three approve of Bob and two approve of Carlos.
+    // Sequential PAV elects Alice first, but the best committee leaves her out.
+    fn tally(variant: Variant) -> ApprovalPrTally<&'static str, f64> {
+        let mut tally = ApprovalPrTally::new(2, variant);
+        tally.add_weighted(vec!["Alice", "Bob"], 3.0).unwrap();
+        tally.add_weighted(vec!["Alice", "Carlos"], 4.0).unwrap();
+        tally.add_weighted(vec!["Bob"], 3.0).unwrap();
+        tally.add_weighted(vec!["Carlos"], 2.0).unwrap();
+        return tally;
+    }
+
+    #[test]
+    fn approval_pr_variants() {
+        let pav = tally(Variant::Proportional);
+        assert_eq!(pav.committee_score(&["Alice", "Bob"]), 11.5);
+        assert_eq!(pav.committee_score(&["Alice", "Carlos"]), 11.0);
+        assert_eq!(pav.committee_score(&["Bob", "Carlos"]), 12.0);
+        assert_eq!(pav.winners().into_vec(), vec![("Bob", 0), ("Carlos", 0)]);
+
+        let sequential = tally(Variant::Sequential);
+        assert_eq!(sequential.winners().into_vec(), vec![("Alice", 0), ("Bob", 1)]);
+
+        let reverse = tally(Variant::ReverseSequential);
+        assert_eq!(reverse.committee_score(&["Alice", "Bob", "Carlos"]), 15.5);
+        assert_eq!(reverse.winners().into_vec(), vec![("Bob", 0), ("Carlos", 0)]);
+
+        let satisfaction = tally(Variant::Satisfaction);
+        assert_eq!(satisfaction.committee_score(&["Bob", "Carlos"]), 8.5);
+        assert_eq!(satisfaction.winners().into_vec(), vec![("Bob", 0), ("Carlos", 1)]);
+
+        let cc = tally(Variant::ChamberlinCourant);
+        assert_eq!(cc.committee_score(&["Alice", "Bob"]), 10.0);
+        assert_eq!(cc.committee_score(&["Bob", "Carlos"]), 12.0);
+        assert_eq!(cc.winners().into_vec(), vec![("Bob", 0), ("Carlos", 0)]);
+    }
+
+    #[test]
+    fn approval_pr_ties() {
+        // Six voters approve of Alice and Bob, and four approve of Carlos.
+        // Block approval voting would elect Alice and Bob.
+        let mut tally = DefaultApprovalPrTally::new(2, Variant::Satisfaction);
+        tally.add_weighted(vec!["Alice", "Bob"], 6.0).unwrap();
+        tally.add_weighted(vec!["Carlos"], 4.0).unwrap();
+
+        // Alice and Bob tie for the second seat.
+        let winners = tally.winners();
+        assert_eq!(winners.all(), vec!["Carlos", "Alice", "Bob"]);
+        assert_eq!(winners.overflow(), Some(vec!["Alice", "Bob"]));
+
+        // Alice and Bob tie for the first seat of a sequential count, so Alice is elected since she was seen first.
+        let mut tally = DefaultApprovalPrTally::new(2, Variant::Sequential);
+        tally.add_weighted(vec!["Alice", "Bob"], 6.0).unwrap();
+        tally.add_weighted(vec!["Carlos"], 4.0).unwrap();
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Carlos", 1)]);
+    }
+
+    #[test]
+    fn approval_pr_candidates() -> Result<(), TallyError> {
+        // Declared candidates can be elected without any approvals.
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos"]);
+        let mut tally = ApprovalPrTally::<&str, u64>::with_candidates(2, Variant::ChamberlinCourant, candidates);
+        tally.add(vec!["Alice", "Bob"])?;
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Bob", 0)]);
+        assert!(tally.add(vec!["Dave"]).is_err());
+
+        // Ranked ballots approve every marked candidate.
+        let mut ballot_box = BallotBox::new();
+        ballot_box.add(vec!["Carlos", "Bob"])?;
+        ballot_box.add_weighted(vec!["Carlos"], 2)?;
+        tally.add_ballot_box(&ballot_box)?;
+        assert_eq!(tally.committee_score(&["Alice", "Carlos"]), 4);
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Carlos", 0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn approval_pr_integer_pav() {
+        ApprovalPrTally::<&str, u64>::new(2, Variant::Proportional);
+    }
+}
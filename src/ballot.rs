@@ -0,0 +1,667 @@
+use super::Numeric;
+use super::TallyError;
+use super::{add_count, COUNT_OVERFLOW_PANIC};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use hashbrown::HashMap;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "parallel")]
+use super::Candidates;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use core::convert::TryFrom;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A `RankedBallot` is a ranked vote that allows a voter to mark two or more candidates as equally preferred.
+///
+/// It is made up of an ordered list of ranks, starting with the most preferred rank. Each rank contains
+/// one or more candidates that are tied with each other. Candidates that do not appear on the ballot are
+/// considered unranked and are less preferred than every ranked candidate.
+///
+/// Ranked tallies define how tied candidates are counted:
+///
+/// - [`CondorcetTally`](condorcet/struct.CondorcetTally.html) and [`SchulzeTally`](schulze/struct.SchulzeTally.html) give
+///   each of two tied candidates half a point in their pairwise comparison.
+/// - [`BordaTally`](borda/struct.BordaTally.html) gives each tied candidate the average of the points for the positions they jointly occupy.
+/// - [`stv::Tally`](stv/struct.Tally.html) splits the vote evenly between the tied candidates.
+///
+/// Since tied candidates are given fractional points, a float or rational count type must be used when adding ballots that contain ties.
+///
+/// # Example
+/// ```
+///    use tallystick::RankedBallot;
+///
+///    // Alice is preferred, followed by Bob and Carlos who are tied, followed by Dave.
+///    let mut ballot = RankedBallot::new();
+///    ballot.push("Alice");
+///    ballot.push_tied(vec!["Bob", "Carlos"]);
+///    ballot.push("Dave");
+///
+///    assert_eq!(ballot.len(), 3);
+///    assert_eq!(ballot.num_candidates(), 4);
+///    assert!(ballot.has_ties());
+///
+///    // A ballot without ties can be created from a list of candidates.
+///    let ballot = RankedBallot::from(vec!["Alice", "Bob", "Carlos"]);
+///    assert!(!ballot.has_ties());
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub struct RankedBallot<T> {
+    ranks: Vec<Vec<T>>,
+}
+
+impl<T> RankedBallot<T> {
+    /// Create a new empty ballot.
+    pub fn new() -> Self {
+        return RankedBallot { ranks: Vec::new() };
+    }
+
+    /// Add a single candidate as the next (less preferred) rank.
+    pub fn push(&mut self, candidate: T) {
+        self.ranks.push(vec![candidate]);
+    }
+
+    /// Add a group of tied candidates as the next (less preferred) rank.
+    /// An empty group is ignored.
+    pub fn push_tied(&mut self, candidates: Vec<T>) {
+        if !candidates.is_empty() {
+            self.ranks.push(candidates);
+        }
+    }
+
+    /// Get the number of ranks on this ballot.
+    pub fn len(&self) -> usize {
+        return self.ranks.len();
+    }
+
+    /// Check if the ballot is empty.
+    pub fn is_empty(&self) -> bool {
+        return self.ranks.is_empty();
+    }
+
+    /// Get the total number of candidates marked on this ballot.
+    pub fn num_candidates(&self) -> usize {
+        return self.ranks.iter().map(|rank| rank.len()).sum();
+    }
+
+    /// Check if the ballot contains any tied candidates.
+    pub fn has_ties(&self) -> bool {
+        return self.ranks.iter().any(|rank| rank.len() > 1);
+    }
+
+    /// Get the ranks on this ballot, ordered from most preferred to least preferred.
+    pub fn ranks(&self) -> &[Vec<T>] {
+        return &self.ranks;
+    }
+
+    /// Iterate over all candidates marked on this ballot, from most preferred to least preferred.
+    /// Tied candidates are returned in the order they were added.
+    pub fn candidates(&self) -> impl Iterator<Item = &T> {
+        self.ranks.iter().flatten()
+    }
+
+    /// Transform the ballot into a vector of ranks.
+    pub fn into_vec(self) -> Vec<Vec<T>> {
+        return self.ranks;
+    }
+}
+
+impl<T: PartialEq> RankedBallot<T> {
+    // Check if the same candidate appears more than once on the ballot, either in the same rank or across ranks.
+    pub(crate) fn check_duplicate(&self) -> Result<(), TallyError> {
+        let candidates: Vec<&T> = self.candidates().collect();
+        for i in 1..candidates.len() {
+            if candidates[i..].contains(&candidates[i - 1]) {
+                return Err(TallyError::VoteHasDuplicateCandidates);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> From<Vec<T>> for RankedBallot<T> {
+    /// Create a ballot without ties from a list of candidates ordered by preference.
+    fn from(candidates: Vec<T>) -> Self {
+        return RankedBallot {
+            ranks: candidates.into_iter().map(|candidate| vec![candidate]).collect(),
+        };
+    }
+}
+
+impl<T> From<Vec<Vec<T>>> for RankedBallot<T> {
+    /// Create a ballot from a list of ranks ordered by preference. Empty ranks are removed.
+    fn from(ranks: Vec<Vec<T>>) -> Self {
+        return RankedBallot {
+            ranks: ranks.into_iter().filter(|rank| !rank.is_empty()).collect(),
+        };
+    }
+}
+
+// A ballot is serialized as its list of ranks, eg `[["Alice"], ["Bob", "Carlos"]]`.
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for RankedBallot<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.ranks.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for RankedBallot<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ranks: Vec<Vec<T>> = Vec::deserialize(deserializer)?;
+        Ok(RankedBallot::from(ranks))
+    }
+}
+
+/// A `BallotBox` collects votes and collapses identical votes into a single vote with a count.
+///
+/// Real elections have heavy duplication: with 10 candidates, a million votes will often collapse into a few thousand unique votes.
+/// A `BallotBox` interns candidates and stores each unique vote only once along with the total weight of all identical votes,
+/// which can save a great deal of memory and time when the votes are then fed into a tally.
+///
+/// A filled `BallotBox` can be added to a tally using `add_ballot_box()`, which is supported by
+/// [`PluralityTally`](plurality/struct.PluralityTally.html) (counting each vote for its first choice),
+/// [`ApprovalTally`](approval/struct.ApprovalTally.html) (approving every candidate on the vote),
+/// [`BordaTally`](borda/struct.BordaTally.html), [`CondorcetTally`](condorcet/struct.CondorcetTally.html),
+/// [`SchulzeTally`](schulze/struct.SchulzeTally.html) and [`stv::Tally`](stv/struct.Tally.html).
+/// Score votes carry a score for each candidate and cannot be stored in a `BallotBox`.
+///
+/// Generics:
+/// - `T`: The candidate type.
+/// - `C`: The count type. It should be the same as the count type of the tally the votes will be added to.
+///
+/// # Example
+/// ```
+///    use tallystick::BallotBox;
+///    use tallystick::condorcet::DefaultCondorcetTally;
+///
+///    let mut ballot_box = BallotBox::<&str, u64>::new();
+///    for _ in 0..500 {
+///        ballot_box.add(vec!["Alice", "Bob", "Carlos"]).unwrap();
+///        ballot_box.add(vec!["Bob", "Alice", "Carlos"]).unwrap();
+///    }
+///    ballot_box.add_weighted(vec!["Alice", "Bob", "Carlos"], 20).unwrap();
+///
+///    // 1020 votes are stored as 2 unique votes.
+///    assert_eq!(ballot_box.len(), 2);
+///    assert_eq!(ballot_box.total(), 1020);
+///
+///    let mut tally = DefaultCondorcetTally::new(1);
+///    tally.add_ballot_box(&ballot_box).unwrap();
+///    assert_eq!(tally.winners().into_unranked(), vec!["Alice"]);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "BallotBoxState<T, C>"))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "T: Deserialize<'de>, C: Deserialize<'de>")))]
+pub struct BallotBox<T, C = u64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    #[cfg_attr(feature = "serde", serde(skip))]
+    candidates: HashMap<T, usize>, // Map candidates to unique integer identifiers
+    candidate_ids: Vec<T>, // Map unique integer identifiers back to candidates
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ballot_ids: HashMap<RankedBallot<usize>, usize>,
+    ballots: Vec<(RankedBallot<usize>, C)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    total: C, // Total weight of all votes, so that tallies summing any of the votes can't overflow
+}
+
+// The serialized state of a `BallotBox`. The candidate and vote indexes are rebuilt when it is deserialized.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct BallotBoxState<T, C> {
+    candidate_ids: Vec<T>,
+    ballots: Vec<(RankedBallot<usize>, C)>,
+}
+
+#[cfg(feature = "serde")]
+impl<T, C> TryFrom<BallotBoxState<T, C>> for BallotBox<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    type Error = TallyError;
+
+    fn try_from(state: BallotBoxState<T, C>) -> Result<Self, TallyError> {
+        let mut ballot_box = BallotBox::with_capacity(state.candidate_ids.len(), state.ballots.len());
+        for candidate in state.candidate_ids {
+            if ballot_box.candidates.contains_key(&candidate) {
+                return Err(TallyError::InvalidState);
+            }
+            ballot_box.intern(&candidate);
+        }
+        for (ballot, weight) in state.ballots {
+            if ballot.is_empty() || ballot.candidates().any(|id| *id >= ballot_box.candidate_ids.len()) {
+                return Err(TallyError::InvalidState);
+            }
+            if ballot.check_duplicate().is_err() || ballot_box.ballot_ids.contains_key(&ballot) {
+                return Err(TallyError::InvalidState);
+            }
+            ballot_box.push(ballot, weight)?;
+        }
+        return Ok(ballot_box);
+    }
+}
+
+impl<T, C> BallotBox<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new empty `BallotBox`.
+    pub fn new() -> Self {
+        return BallotBox {
+            candidates: HashMap::new(),
+            candidate_ids: Vec::new(),
+            ballot_ids: HashMap::new(),
+            ballots: Vec::new(),
+            total: C::zero(),
+        };
+    }
+
+    /// Create a new empty `BallotBox` with the given number of expected candidates and expected unique votes.
+    pub fn with_capacity(expected_candidates: usize, expected_unique_votes: usize) -> Self {
+        return BallotBox {
+            candidates: HashMap::with_capacity(expected_candidates),
+            candidate_ids: Vec::with_capacity(expected_candidates),
+            ballot_ids: HashMap::with_capacity(expected_unique_votes),
+            ballots: Vec::with_capacity(expected_unique_votes),
+            total: C::zero(),
+        };
+    }
+
+    /// Add a new vote.
+    ///
+    /// Votes are represented as a vector of candidates, ordered by preference.
+    /// An error will only be returned if `vote` contains duplicate candidates.
+    pub fn add(&mut self, vote: Vec<T>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&vote, C::one())
+    }
+
+    /// Add a new vote by reference.
+    pub fn add_ref(&mut self, vote: &[T]) -> Result<(), TallyError> {
+        self.add_weighted_ref(vote, C::one())
+    }
+
+    /// Add a weighted vote.
+    pub fn add_weighted(&mut self, vote: Vec<T>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&vote, weight)
+    }
+
+    /// Add a weighted vote by reference.
+    pub fn add_weighted_ref(&mut self, vote: &[T], weight: C) -> Result<(), TallyError> {
+        super::check_duplicate(vote)?;
+        self.push_unchecked(vote, weight)
+    }
+
+    /// Add a new ranked vote that may contain tied candidates.
+    pub fn add_ranked(&mut self, vote: RankedBallot<T>) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(&vote, C::one())
+    }
+
+    /// Add a weighted ranked vote that may contain tied candidates.
+    pub fn add_ranked_weighted(&mut self, vote: RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(&vote, weight)
+    }
+
+    /// Add a weighted ranked vote by reference.
+    pub fn add_ranked_weighted_ref(&mut self, vote: &RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+        vote.check_duplicate()?;
+        let mut interned = RankedBallot::new();
+        for rank in vote.ranks() {
+            interned.push_tied(rank.iter().map(|candidate| self.intern(candidate)).collect());
+        }
+        self.push(interned, weight)
+    }
+
+    /// Merge another ballot box into this one, as if all of its votes had been added to this ballot box.
+    ///
+    /// Votes from `other` that are already in this ballot box are collapsed as usual.
+    /// Votes that are new to this ballot box are added after all existing votes.
+    ///
+    /// # Panics
+    /// This method will panic if the merged total weight of all votes overflows the count type.
+    pub fn merge(&mut self, other: Self) {
+        let ids: Vec<usize> = other.candidate_ids.iter().map(|candidate| self.intern(candidate)).collect();
+        for (ballot, weight) in other.ballots {
+            let mut interned = RankedBallot::new();
+            for rank in ballot.ranks() {
+                interned.push_tied(rank.iter().map(|id| ids[*id]).collect());
+            }
+            self.push(interned, weight).expect(COUNT_OVERFLOW_PANIC);
+        }
+    }
+
+    /// Get the number of unique votes.
+    pub fn len(&self) -> usize {
+        return self.ballots.len();
+    }
+
+    /// Check if the ballot box is empty.
+    pub fn is_empty(&self) -> bool {
+        return self.ballots.is_empty();
+    }
+
+    /// Get the total weight of all votes. If no weights were used, this is the number of votes cast.
+    pub fn total(&self) -> C {
+        return self.total;
+    }
+
+    /// Get a list of all candidates seen in this ballot box, in the order they were first seen.
+    pub fn candidates(&self) -> Vec<T> {
+        return self.candidate_ids.clone();
+    }
+
+    /// Sort candidates by their `Ord` order, so that `candidates()` and the order of tied candidates in any tally counting
+    /// this ballot box don't depend on the order votes were added. Candidates first seen afterwards are listed after them.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let order = super::sorted_order(&self.candidate_ids, 0);
+        self.reorder(&order);
+    }
+
+    /// Iterate over all unique votes paired with their total weight, in the order they were first seen.
+    pub fn iter(&self) -> impl Iterator<Item = (RankedBallot<T>, C)> + '_ {
+        self.ballots.iter().map(move |(ballot, count)| {
+            let mut vote = RankedBallot::new();
+            for rank in ballot.ranks() {
+                vote.push_tied(rank.iter().map(|id| self.candidate_ids[*id].clone()).collect());
+            }
+            (vote, *count)
+        })
+    }
+
+    /// Check if any vote contains tied candidates.
+    pub fn has_ties(&self) -> bool {
+        return self.ballots.iter().any(|(ballot, _)| ballot.has_ties());
+    }
+
+    // Get all unique votes, with candidates as indexes into `candidate_ids()`.
+    pub(crate) fn interned(&self) -> &[(RankedBallot<usize>, C)] {
+        return &self.ballots;
+    }
+
+    // Get all candidates, indexed by their interned identifier.
+    pub(crate) fn candidate_ids(&self) -> &[T] {
+        return &self.candidate_ids;
+    }
+
+    // Declare candidates without adding a vote, so that they are listed by `candidates()` even if no vote marks them.
+    pub(crate) fn declare(&mut self, candidates: &[T]) {
+        for candidate in candidates {
+            self.intern(candidate);
+        }
+    }
+
+    // Move candidates to new identifiers, where `order[i]` is the current identifier of the candidate given identifier `i`.
+    pub(crate) fn reorder(&mut self, order: &[usize]) {
+        let mut ids = vec![0; order.len()];
+        for (id, old) in order.iter().enumerate() {
+            ids[*old] = id;
+        }
+        self.candidate_ids = order.iter().map(|old| self.candidate_ids[*old].clone()).collect();
+        for (id, candidate) in self.candidate_ids.iter().enumerate() {
+            self.candidates.insert(candidate.clone(), id);
+        }
+        self.ballot_ids.clear();
+        for (index, (ballot, _)) in self.ballots.iter_mut().enumerate() {
+            for rank in ballot.ranks.iter_mut() {
+                for candidate in rank.iter_mut() {
+                    *candidate = ids[*candidate];
+                }
+            }
+            self.ballot_ids.insert(ballot.clone(), index);
+        }
+    }
+
+    // Add a weighted vote without checking it for duplicate candidates.
+    pub(crate) fn push_unchecked(&mut self, vote: &[T], weight: C) -> Result<(), TallyError> {
+        let mut interned = RankedBallot::new();
+        for candidate in vote.iter() {
+            interned.push(self.intern(candidate));
+        }
+        return self.push(interned, weight);
+    }
+
+    fn intern(&mut self, candidate: &T) -> usize {
+        if let Some(id) = self.candidates.get(candidate) {
+            return *id;
+        }
+        let id = self.candidate_ids.len();
+        self.candidates.insert(candidate.clone(), id);
+        self.candidate_ids.push(candidate.clone());
+        return id;
+    }
+
+    fn push(&mut self, ballot: RankedBallot<usize>, weight: C) -> Result<(), TallyError> {
+        if ballot.is_empty() {
+            return Ok(());
+        }
+        let total = self.total.checked_add(weight).ok_or(TallyError::CountOverflow)?;
+        match self.ballot_ids.get(&ballot) {
+            Some(id) => add_count(&mut self.ballots[*id].1, weight)?,
+            None => {
+                self.ballot_ids.insert(ballot.clone(), self.ballots.len());
+                self.ballots.push((ballot, weight));
+            }
+        }
+        self.total = total;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T, C> BallotBox<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send, // Vote count type
+{
+    /// Add many votes in parallel.
+    ///
+    /// Votes are split across threads and collected into per-thread ballot boxes, which are then merged into this one.
+    /// Tallies that count votes from a `BallotBox` add votes in parallel the same way. If the tally was created with
+    /// `with_candidates()`, withdrawn candidates are removed from each vote and votes with unknown candidates are rejected.
+    /// If any vote is rejected, an error is returned and nothing is added.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        self.par_add_filtered(votes, None)
+    }
+
+    // Add many votes in parallel, filtering each vote through the registry if there is one.
+    pub(crate) fn par_add_filtered<I>(&mut self, votes: I, registry: Option<&Candidates<T>>) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let ballots = votes
+            .into_par_iter()
+            .try_fold(BallotBox::new, |mut ballots, vote| {
+                match registry {
+                    Some(registry) => {
+                        super::check_duplicate(&vote)?;
+                        ballots.add(registry.filter(&vote)?)?;
+                    }
+                    None => ballots.add(vote)?,
+                }
+                Ok(ballots)
+            })
+            .try_reduce(BallotBox::new, |mut ballots, other| {
+                ballots.merge(other);
+                Ok(ballots)
+            })?;
+        self.merge(ballots);
+        Ok(())
+    }
+}
+
+impl<T, C> Default for BallotBox<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranked_ballot_test() {
+        let mut ballot = RankedBallot::new();
+        assert!(ballot.is_empty());
+        ballot.push("Alice");
+        ballot.push_tied(vec![]);
+        ballot.push_tied(vec!["Bob", "Carlos"]);
+        assert_eq!(ballot.len(), 2);
+        assert_eq!(ballot.num_candidates(), 3);
+        assert!(ballot.has_ties());
+        assert_eq!(ballot.candidates().cloned().collect::<Vec<_>>(), vec!["Alice", "Bob", "Carlos"]);
+        assert!(ballot.check_duplicate().is_ok());
+
+        let ballot = RankedBallot::<&str>::from(vec![vec!["Alice", "Bob"], vec![], vec!["Alice"]]);
+        assert_eq!(ballot.len(), 2);
+        assert!(ballot.check_duplicate().is_err());
+
+        let ballot = RankedBallot::from(vec!["Alice", "Bob"]);
+        assert_eq!(ballot.ranks(), &[vec!["Alice"], vec!["Bob"]][..]);
+        assert!(!ballot.has_ties());
+    }
+
+    #[test]
+    fn ballot_box_test() -> Result<(), TallyError> {
+        let mut ballot_box = BallotBox::<&str, u64>::new();
+        ballot_box.add(vec!["Alice", "Bob"])?;
+        ballot_box.add_ref(&["Bob", "Alice"])?;
+        ballot_box.add_weighted(vec!["Alice", "Bob"], 3)?;
+        ballot_box.add_ranked(RankedBallot::from(vec!["Alice", "Bob"]))?;
+        ballot_box.add_ranked(RankedBallot::from(vec![vec!["Alice", "Bob"]]))?;
+        ballot_box.add(vec![])?;
+        assert!(ballot_box.add(vec!["Carlos", "Carlos"]).is_err());
+
+        let mut other = BallotBox::new();
+        other.add(vec!["Carlos"])?;
+        other.add(vec!["Bob", "Alice"])?;
+        ballot_box.merge(other);
+
+        assert_eq!(ballot_box.len(), 4);
+        assert_eq!(ballot_box.total(), 9);
+        assert_eq!(ballot_box.candidates(), vec!["Alice", "Bob", "Carlos"]);
+        assert!(ballot_box.has_ties());
+        assert_eq!(
+            ballot_box.iter().collect::<Vec<_>>(),
+            vec![
+                (RankedBallot::from(vec!["Alice", "Bob"]), 5),
+                (RankedBallot::from(vec!["Bob", "Alice"]), 2),
+                (RankedBallot::from(vec![vec!["Alice", "Bob"]]), 1),
+                (RankedBallot::from(vec!["Carlos"]), 1),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ballot_box_sort_candidates() -> Result<(), TallyError> {
+        let mut ballot_box = BallotBox::<&str, u64>::new();
+        ballot_box.add(vec!["Carlos", "Alice"])?;
+        ballot_box.add(vec!["Bob"])?;
+        ballot_box.sort_candidates();
+        assert_eq!(ballot_box.candidates(), vec!["Alice", "Bob", "Carlos"]);
+
+        // Votes are unchanged, and are still collapsed with the same votes added later.
+        ballot_box.add(vec!["Carlos", "Alice"])?;
+        ballot_box.add(vec!["Dave"])?;
+        assert_eq!(ballot_box.candidates(), vec!["Alice", "Bob", "Carlos", "Dave"]);
+        assert_eq!(
+            ballot_box.iter().collect::<Vec<_>>(),
+            vec![
+                (RankedBallot::from(vec!["Carlos", "Alice"]), 2),
+                (RankedBallot::from(vec!["Bob"]), 1),
+                (RankedBallot::from(vec!["Dave"]), 1),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ballot_box_serde() -> Result<(), TallyError> {
+        let mut ballot_box = BallotBox::<&str, u64>::new();
+        ballot_box.add_weighted(vec!["Alice", "Bob"], 2)?;
+        ballot_box.add_ranked(RankedBallot::from(vec![vec!["Bob", "Carlos"]]))?;
+
+        let json = serde_json::to_string(&ballot_box).unwrap();
+        assert_eq!(
+            json,
+            r#"{"candidate_ids":["Alice","Bob","Carlos"],"ballots":[[[[0],[1]],2],[[[1,2]],1]]}"#
+        );
+
+        let mut restored: BallotBox<String, u64> = serde_json::from_str(&json).unwrap();
+        restored.add(vec!["Alice".to_string(), "Bob".to_string()])?;
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.total(), 4);
+
+        // Votes that refer to unknown candidates, or that are listed twice, are rejected.
+        let invalid = r#"{"candidate_ids":["Alice"],"ballots":[[[[0],[1]],2]]}"#;
+        assert!(serde_json::from_str::<BallotBox<String, u64>>(invalid).is_err());
+        let invalid = r#"{"candidate_ids":["Alice"],"ballots":[[[[0]],2],[[[0]],1]]}"#;
+        assert!(serde_json::from_str::<BallotBox<String, u64>>(invalid).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn ballot_box_overflow() -> Result<(), TallyError> {
+        let mut ballot_box = BallotBox::<&str, u8>::new();
+        ballot_box.add_weighted(vec!["Alice", "Bob"], 200)?;
+
+        // The total weight of all votes is checked, not just the weight of each unique vote.
+        assert!(matches!(ballot_box.add_weighted(vec!["Bob", "Alice"], 100), Err(TallyError::CountOverflow)));
+        assert!(matches!(ballot_box.add_weighted(vec!["Alice", "Bob"], 100), Err(TallyError::CountOverflow)));
+        assert_eq!(ballot_box.len(), 1);
+        assert_eq!(ballot_box.total(), 200);
+
+        ballot_box.add_weighted(vec!["Bob", "Alice"], 55)?;
+        assert_eq!(ballot_box.total(), 255);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn ballot_box_parallel() -> Result<(), TallyError> {
+        let mut votes = Vec::new();
+        for _ in 0..100 {
+            votes.push(vec!["Alice", "Bob"]);
+            votes.push(vec!["Bob", "Carlos"]);
+        }
+        let mut ballot_box = BallotBox::<&str, u64>::new();
+        ballot_box.par_add_all(votes)?;
+        ballot_box.sort_candidates();
+        assert_eq!(ballot_box.len(), 2);
+        assert_eq!(ballot_box.total(), 200);
+        assert_eq!(ballot_box.candidates(), vec!["Alice", "Bob", "Carlos"]);
+
+        // Nothing is added if any vote is rejected.
+        assert!(ballot_box.par_add_all(vec![vec!["Alice"], vec!["Bob", "Bob"]]).is_err());
+        assert_eq!(ballot_box.total(), 200);
+
+        Ok(())
+    }
+}
@@ -1,21 +1,28 @@
 use super::check_duplicate;
-use super::plurality::PluralityTally;
+use super::{add_count, COUNT_OVERFLOW_PANIC, C_FROM_PANIC};
 use super::result::CountedCandidates;
 use super::result::RankedWinners;
+use super::BallotBox;
+use super::Candidates;
 use super::Numeric;
+use super::RankedBallot;
+use super::sorted_order;
 use super::TallyError;
-use hashbrown::HashMap;
-use hashbrown::HashSet;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::FromPrimitive;
 use num_traits::Num;
-use num_traits::NumCast;
-use std::hash::Hash;
-use std::ops::AddAssign;
 
-// We often convert `C` (vote count types) to and from small integers.
-// If we can't sucessfully convert a small integer into `C`, we panic since converting from a small is trivial.
-const C_FROM_PANIC: &str = "Cannot convert integer to C, this is likely caused by a bug in the ToPrimitive impl for the count type.";
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Specifies method used to assign points to ranked candidates.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Variant<C> {
   /// The standard Borda count where each candidate is assigned a number of points equal to the number of candidates ranked lower than them.
   /// It is known as the "Starting at 0" Borda count since the least-significantly ranked candidate is given zero points.
@@ -106,10 +113,13 @@ pub enum Variant<C> {
   /// });
   /// let mut tally = BordaTally::<&str, usize>::new(1, Variant::Custom(boxed_func));
   /// ```
+  ///
+  /// Custom variants cannot be serialized, since they contain a closure.
+  #[cfg_attr(feature = "serde", serde(skip))]
   Custom(Box<dyn Fn(usize, usize, usize) -> C>),
 }
 
-impl<C: Numeric + Num + NumCast> Variant<C> {
+impl<C: Numeric + Num + FromPrimitive> Variant<C> {
   /// Get the number of points for a candidate at a certain position on a ballot.
   ///
   /// - `candidate_position` is the position of the candidate on the marked ballot. It is `0` for the 1st candidate, `1` for the second candidate etc.
@@ -121,15 +131,15 @@ impl<C: Numeric + Num + NumCast> Variant<C> {
     // Unwrapping options SHOULD be good here. It's very unlikely that C can't represent a small unsigned integer.
     // If it is the case that a small integer can't be represented in C, that's a bug.
     match self {
-      Variant::Borda => C::from(num_candidates - candidate_position - 1).expect(C_FROM_PANIC),
-      Variant::ClassicBorda => C::from(num_candidates - candidate_position).expect(C_FROM_PANIC),
+      Variant::Borda => C::from_usize(num_candidates - candidate_position - 1).expect(C_FROM_PANIC),
+      Variant::ClassicBorda => C::from_usize(num_candidates - candidate_position).expect(C_FROM_PANIC),
       Variant::Dowdall => {
         if !C::fraction() {
           panic!("tallystick::borda::Variant::Dowdall cannot be used with an integer count type. Please use a float or a rational.")
         }
-        C::one() / C::from(candidate_position + 1).expect(C_FROM_PANIC)
+        C::one() / C::from_usize(candidate_position + 1).expect(C_FROM_PANIC)
       }
-      Variant::ModifiedClassicBorda => C::from(num_marked - candidate_position).expect(C_FROM_PANIC),
+      Variant::ModifiedClassicBorda => C::from_usize(num_marked - candidate_position).expect(C_FROM_PANIC),
       Variant::Custom(boxed_func) => boxed_func(candidate_position, num_candidates, num_marked),
     }
   }
@@ -167,7 +177,7 @@ pub type DefaultBordaTally<T> = BordaTally<T, u64>;
 ///
 /// Generics:
 /// - `T`: The candidate type.
-/// - `C`: The count type. `u64` is recommended, but can be modified to use a different type for counting votes (eg `f64` for fractional vote weights). If using [`Variant::Dowdall`](enum.Variant.html#variant.Dowdall) then a float, a [`rational`](https://rust-num.github.io/num/num_rational/index.html), or anyting that implements [`Real`](https://docs.rs/num-traits/0.2.6/num_traits/real/trait.Real.html) must be used.
+/// - `C`: The count type. `u64` is recommended, but can be modified to use a different type for counting votes (eg `f64` for fractional vote weights). If using [`Variant::Dowdall`](enum.Variant.html#variant.Dowdall) then a float or a [`rational`](https://rust-num.github.io/num/num_rational/index.html) (with the `rational` feature) must be used.
 ///
 /// Example:
 /// ```
@@ -183,21 +193,23 @@ pub type DefaultBordaTally<T> = BordaTally<T, u64>;
 ///
 ///    let winners = tally.winners();
 /// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BordaTally<T, C = u64>
 where
-  T: Eq + Clone + Hash,                             // Candidate
-  C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+  T: Eq + Clone + Hash,                                       // Candidate
+  C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
-  running_total: HashMap<Vec<T>, C>,
-  candidates: HashSet<T>,
+  ballots: BallotBox<T, C>, // Identical votes are stored once, with their total weight
   num_winners: u32,
   variant: Variant<C>,
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  registry: Option<Candidates<T>>, // The declared candidates, if any
 }
 
 impl<T, C> BordaTally<T, C>
 where
-  T: Eq + Clone + Hash,                             // Candidate
-  C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+  T: Eq + Clone + Hash,                                       // Candidate
+  C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
   /// Create a new `BordaTally` with the given number of winners.
   ///
@@ -205,23 +217,36 @@ where
   /// (See [`winners()`](#method.winners) for more information on ties.)
   pub fn new(num_winners: u32, variant: Variant<C>) -> Self {
     return BordaTally {
-      running_total: HashMap::new(),
-      candidates: HashSet::new(),
+      ballots: BallotBox::new(),
       num_winners: num_winners,
       variant: variant,
+      registry: None,
     };
   }
 
   /// Create a new `BordaTally` with the given number of winners, and number of expected candidates.
   pub fn with_capacity(num_winners: u32, variant: Variant<C>, expected_candidates: usize) -> Self {
     return BordaTally {
-      running_total: HashMap::with_capacity(expected_candidates),
-      candidates: HashSet::with_capacity(expected_candidates),
+      ballots: BallotBox::with_capacity(expected_candidates, expected_candidates),
       num_winners: num_winners,
       variant: variant,
+      registry: None,
     };
   }
 
+  /// Create a new `BordaTally` with the given number of winners, for an election between the given candidates.
+  ///
+  /// Every standing candidate is reported and counts towards the number of candidates when assigning points,
+  /// even if they are not ranked on any vote. Standing candidates that are not ranked on a vote get no points from it.
+  /// Withdrawn candidates are removed from votes, and votes with unknown candidates are rejected unless write-ins are accepted.
+  /// See [`Candidates`](../struct.Candidates.html) for details.
+  pub fn with_candidates(num_winners: u32, variant: Variant<C>, candidates: Candidates<T>) -> Self {
+    let mut tally = Self::with_capacity(num_winners, variant, candidates.standing().len());
+    tally.ballots.declare(candidates.standing());
+    tally.registry = Some(candidates);
+    return tally;
+  }
+
   /// Add a new vote
   ///
   /// Votes are represented as a vector of ranked candidates, ordered by preference.
@@ -238,59 +263,111 @@ where
   /// Add a weighted vote.
   /// By default takes a weight as a `usize` integer, but can be customized by using `BordaTally` with a custom vote type.
   pub fn add_weighted(&mut self, vote: Vec<T>, weight: C) -> Result<(), TallyError> {
-    check_duplicate(&vote)?;
+    self.add_weighted_ref(&vote, weight)
+  }
 
-    for candidate in vote.iter() {
-      if !self.candidates.contains(candidate) {
-        self.candidates.insert(candidate.clone());
+  /// Add a weighted vote by reference
+  pub fn add_weighted_ref(&mut self, vote: &[T], weight: C) -> Result<(), TallyError> {
+    match &self.registry {
+      Some(registry) => {
+        check_duplicate(vote)?;
+        self.ballots.add_weighted_ref(&registry.filter(vote)?, weight)
       }
+      None => self.ballots.add_weighted_ref(vote, weight),
     }
+  }
 
-    let entry = self.running_total.entry(vote);
-    *entry.or_insert(C::zero()) += weight;
+  /// Add a new ranked vote that may contain tied candidates.
+  ///
+  /// Tied candidates are each given the average of the points for the positions they jointly occupy on the ballot.
+  /// For example, using `Variant::Borda` with four candidates, a ballot ranking Alice first and Bob and Carlos
+  /// tied for second gives Alice 3 points, and Bob and Carlos 1.5 points each.
+  ///
+  /// If `vote` contains ties, a float or rational count type must be used, otherwise an error is returned.
+  pub fn add_ranked(&mut self, vote: RankedBallot<T>) -> Result<(), TallyError> {
+    self.add_ranked_weighted_ref(&vote, C::one())
+  }
 
-    Ok(())
+  /// Add a ranked vote by reference.
+  pub fn add_ranked_ref(&mut self, vote: &RankedBallot<T>) -> Result<(), TallyError> {
+    self.add_ranked_weighted_ref(vote, C::one())
   }
 
-  /// Add a weighted vote by reference
-  pub fn add_weighted_ref(&mut self, vote: &[T], weight: C) -> Result<(), TallyError> {
-    check_duplicate(vote)?;
+  /// Add a weighted ranked vote.
+  pub fn add_ranked_weighted(&mut self, vote: RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+    self.add_ranked_weighted_ref(&vote, weight)
+  }
 
-    for candidate in vote.iter() {
-      if !self.candidates.contains(candidate) {
-        self.candidates.insert(candidate.clone());
+  /// Add a weighted ranked vote by reference.
+  pub fn add_ranked_weighted_ref(&mut self, vote: &RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+    if vote.has_ties() {
+      vote.check_duplicate()?;
+      if !C::fraction() {
+        return Err(TallyError::TiedVoteRequiresFractionalCount);
       }
     }
+    match &self.registry {
+      Some(registry) => {
+        vote.check_duplicate()?;
+        self.ballots.add_ranked_weighted_ref(&registry.filter_ranked(vote)?, weight)
+      }
+      None => self.ballots.add_ranked_weighted_ref(vote, weight),
+    }
+  }
 
-    let entry = self.running_total.entry(vote.to_vec());
-    *entry.or_insert(C::zero()) += weight;
-
+  /// Add all votes in a `BallotBox`.
+  ///
+  /// If the ballot box contains ties, a float or rational count type must be used, otherwise an error is returned.
+  pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+    if ballot_box.has_ties() && !C::fraction() {
+      return Err(TallyError::TiedVoteRequiresFractionalCount);
+    }
+    match &self.registry {
+      Some(registry) => {
+        registry.check_all(ballot_box.candidate_ids())?;
+        for (vote, weight) in ballot_box.iter() {
+          self.ballots.add_ranked_weighted_ref(&registry.filter_ranked(&vote)?, weight)?;
+        }
+      }
+      None => self.ballots.merge(ballot_box.clone()),
+    }
     Ok(())
   }
 
-  /// Get a ranked list of winners. Winners with the same rank are tied.
+  /// Merge another tally into this one, as if all of its votes had been added to this tally.
+  ///
+  /// Tallies can be merged in any order to get the same result. After merging, candidates are sorted as if by
+  /// [`sort_candidates()`](#method.sort_candidates), so the order of `candidates()`, and of tied candidates in `ranked()`
+  /// and `winners()`, doesn't depend on merge order either. Floating point count types may still accumulate rounding
+  /// differences depending on merge order; use an integer or rational count type for exact results.
+  /// The variant and number of winners for `other` are ignored.
+  ///
+  /// # Panics
+  /// This method will panic if the merged total weight of all votes overflows the count type.
+  pub fn merge(&mut self, other: Self)
+  where
+    T: Ord,
+  {
+    self.ballots.merge(other.ballots);
+    self.sort_candidates();
+  }
+
+  /// Get a ranked list of winners. Winners with the same rank are tied, and are listed in the order they were first seen.
   /// The number of winners might be greater than the requested `num_winners` if there is a tie.
   /// In a borda count, the winners are determine by what candidate obtains the most points.
   pub fn winners(&self) -> RankedWinners<T> {
-    let mut counted = CountedCandidates::new();
-    for (candidate, votecount) in self.totals().iter() {
-      counted.push(candidate.clone(), *votecount);
-    }
-    return counted.into_ranked(self.num_winners);
+    return self.get_counted().into_ranked(self.num_winners);
   }
 
-  /// Get a ranked list of all candidates. Candidates with the same rank are tied.
+  /// Get a ranked list of all candidates. Candidates with the same rank are tied, and are listed in the order they were first seen.
   pub fn ranked(&self) -> Vec<(T, u32)> {
-    let mut counted = CountedCandidates::new();
-    for (candidate, votecount) in self.totals().iter() {
-      counted.push(candidate.clone(), *votecount);
-    }
-    return counted.into_ranked(0).into_vec();
+    return self.get_counted().into_ranked(0).into_vec();
   }
 
   /// Get point totals for this tally.
   ///
-  /// This will return a vector with the number of borda points for each candidate.
+  /// This will return a vector with the number of borda points for each candidate, sorted from most to fewest points.
+  /// Candidates with the same number of points are listed in the order they were first seen.
   ///
   /// # Example
   /// ```
@@ -309,23 +386,57 @@ where
   ///    //   Bob has 30 points
   /// ```
   pub fn totals(&self) -> Vec<(T, C)> {
-    // Make a little plurality tally and use borda points as weights
-    let mut plurality = PluralityTally::with_capacity(self.num_winners, self.candidates.len());
-    for (selection, votecount) in self.running_total.iter() {
-      let num_marked = selection.len();
-      for (position, candidate) in selection.iter().enumerate() {
-        let points: C = self.variant.points(position, self.candidates.len(), num_marked);
-        plurality.add_weighted_ref(candidate, *votecount * points);
-      }
-    }
-
-    return plurality.totals();
+    return self.get_counted().into_vec();
   }
 
   /// Get a list of all candidates seen by this tally.
-  /// Candidates are returned in no particular order.
+  /// Candidates are returned in the order they were first seen.
   pub fn candidates(&self) -> Vec<T> {
-    return self.candidates.iter().map(|x| x.clone()).collect();
+    return self.ballots.candidates();
+  }
+
+  /// Sort candidates by their `Ord` order instead of the order they were first seen.
+  /// See [Determinism](../index.html#determinism) for details.
+  pub fn sort_candidates(&mut self)
+  where
+    T: Ord,
+  {
+    let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+    let order = sorted_order(self.ballots.candidate_ids(), declared);
+    self.ballots.reorder(&order);
+  }
+
+  // Get the point totals as CountedCandidates, in the order candidates were first seen.
+  fn get_counted(&self) -> CountedCandidates<T, C> {
+    let candidates = self.ballots.candidate_ids();
+    let num_candidates = candidates.len();
+
+    // Tied candidates share the average of the points for the positions they occupy.
+    let mut points_total = vec![C::zero(); num_candidates];
+    for (vote, votecount) in self.ballots.interned() {
+      let num_marked = vote.num_candidates();
+      let mut position = 0;
+      for rank in vote.ranks() {
+        let mut points = C::zero();
+        for tied_position in position..(position + rank.len()) {
+          points += self.variant.points(tied_position, num_candidates, num_marked);
+        }
+        if rank.len() > 1 {
+          points = points / C::from_usize(rank.len()).expect(C_FROM_PANIC);
+        }
+        let weighted_points = votecount.checked_mul(points).expect(COUNT_OVERFLOW_PANIC);
+        for candidate in rank.iter() {
+          add_count(&mut points_total[*candidate], weighted_points).expect(COUNT_OVERFLOW_PANIC);
+        }
+        position += rank.len();
+      }
+    }
+
+    let mut counted = CountedCandidates::new();
+    for (candidate, points) in candidates.iter().zip(points_total) {
+      counted.push(candidate.clone(), points);
+    }
+    return counted;
   }
 }
 
@@ -337,8 +448,8 @@ pub type DefaultNansonTally<T> = NansonTally<T, u64>;
 #[allow(dead_code)]
 pub struct NansonTally<T, C = u64>
 where
-  T: Eq + Clone + Hash,                             // Candidate
-  C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+  T: Eq + Clone + Hash,                                       // Candidate
+  C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
   borda: BordaTally<T, C>,
 }
@@ -351,12 +462,44 @@ pub type DefaultBaldwinTally<T> = BaldwinTally<T, u64>;
 #[allow(dead_code)]
 pub struct BaldwinTally<T, C = u64>
 where
-  T: Eq + Clone + Hash,                             // Candidate
-  C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+  T: Eq + Clone + Hash,                                       // Candidate
+  C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
   borda: BordaTally<T, C>,
 }
 
+#[cfg(feature = "parallel")]
+impl<T, C> BordaTally<T, C>
+where
+  T: Eq + Clone + Hash + Send + Sync,                                // Candidate
+  C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send, // Vote count type
+{
+  /// Create a new `BordaTally` from a parallel iterator of votes.
+  ///
+  /// Votes are split across threads and collected per-thread, then merged.
+  /// An error is returned if any vote contains duplicate candidates.
+  /// Requires the `parallel` feature.
+  pub fn from_par_iter<I>(num_winners: u32, variant: Variant<C>, votes: I) -> Result<Self, TallyError>
+  where
+    I: IntoParallelIterator<Item = Vec<T>>,
+  {
+    let mut tally = Self::new(num_winners, variant);
+    tally.par_add_all(votes)?;
+    return Ok(tally);
+  }
+
+  /// Add many votes in parallel.
+  ///
+  /// See [`BallotBox::par_add_all()`](../struct.BallotBox.html#method.par_add_all) for details.
+  /// Requires the `parallel` feature.
+  pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+  where
+    I: IntoParallelIterator<Item = Vec<T>>,
+  {
+    self.ballots.par_add_filtered(votes, self.registry.as_ref())
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -426,6 +569,16 @@ mod tests {
     assert!(tally.ranked() == vec![("Bob", 0), ("Alice", 1), ("Carlos", 1)]);
     assert!(tally.candidates().len() == 3);
 
+    // Testing merging
+    let mut merged = DefaultBordaTally::new(1, Variant::Borda);
+    merged.add_weighted(vec!["Memphis", "Nashville", "Chattanooga", "Knoxville"], 42)?;
+    merged.add_weighted(vec!["Nashville", "Chattanooga", "Knoxville", "Memphis"], 26)?;
+    let mut other = DefaultBordaTally::new(1, Variant::Borda);
+    other.add_weighted(vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"], 15)?;
+    other.add_weighted(vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"], 17)?;
+    merged.merge(other);
+    assert!(merged.totals() == vec![("Nashville", 194), ("Chattanooga", 173), ("Memphis", 126), ("Knoxville", 107)]);
+
     // Testin adding ref
     let vote_1 = vec!["Alice", "Bob", "Carlos"];
     let vote_2 = vec!["Alice", "Bob"];
@@ -439,10 +592,118 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn borda_ranked_ties() -> Result<(), TallyError> {
+    let mut tally = BordaTally::<&str, f64>::new(1, Variant::Borda);
+    tally.add_ranked(RankedBallot::from(vec![vec!["Alice"], vec!["Bob", "Carlos"], vec!["Dave"]]))?;
+    tally.add_ranked(RankedBallot::from(vec!["Bob", "Alice", "Carlos", "Dave"]))?;
+    assert!(tally.totals() == vec![("Alice", 5.0), ("Bob", 4.5), ("Carlos", 2.5), ("Dave", 0.0)]);
+
+    // Points are averaged over the tied positions for any variant
+    let mut tally = BordaTally::<&str, f64>::new(1, Variant::Dowdall);
+    tally.add_ranked_weighted(RankedBallot::from(vec![vec!["Alice", "Bob"], vec!["Carlos"]]), 4.0)?;
+    tally.add(vec!["Alice"])?;
+    assert!(tally.totals() == vec![("Alice", 4.0), ("Bob", 3.0), ("Carlos", 4.0 / 3.0)]);
+
+    // Ties require a fractional count type
+    let mut tally = DefaultBordaTally::new(1, Variant::Borda);
+    assert!(tally.add_ranked(RankedBallot::from(vec![vec!["Alice", "Bob"]])).is_err());
+    tally.add_ranked(RankedBallot::from(vec!["Alice", "Bob"]))?;
+    assert!(tally.totals() == vec![("Alice", 1), ("Bob", 0)]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn borda_candidates() -> Result<(), TallyError> {
+    // Unranked standing candidates still count towards the number of candidates.
+    let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos", "Dave"]).withdrawn(vec!["Dave"]);
+    let mut tally = DefaultBordaTally::with_candidates(1, Variant::Borda, candidates);
+    tally.add(vec!["Dave", "Bob"])?;
+    assert!(tally.add(vec!["Erin"]).is_err());
+    assert_eq!(tally.totals(), vec![("Bob", 2), ("Alice", 0), ("Carlos", 0)]);
+
+    Ok(())
+  }
+
   #[test]
   #[should_panic]
   fn borda_panic_test() {
     // Dowdall should panic when using integers
     let _points: u64 = Variant::Dowdall.points(0, 4, 4);
   }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn borda_serde() -> Result<(), TallyError> {
+    let mut tally = DefaultBordaTally::new(1, Variant::Borda);
+    tally.add(vec!["Alice", "Bob", "Carlos"])?;
+    let json = serde_json::to_string(&tally).unwrap();
+    assert_eq!(
+      json,
+      r#"{"ballots":{"candidate_ids":["Alice","Bob","Carlos"],"ballots":[[[[0],[1],[2]],1]]},"num_winners":1,"variant":"Borda"}"#
+    );
+
+    let resumed: DefaultBordaTally<&str> = serde_json::from_str(&json).unwrap();
+    assert_eq!(resumed.totals(), tally.totals());
+
+    // Custom variants contain a closure, so they can't be serialized.
+    let boxed_func = Box::new(|_candidate_position, _num_candidates, _num_marked| 1);
+    let tally = DefaultBordaTally::<&str>::new(1, Variant::Custom(boxed_func));
+    assert!(serde_json::to_string(&tally).is_err());
+
+    Ok(())
+  }
+
+  #[cfg(feature = "parallel")]
+  #[test]
+  fn borda_parallel() -> Result<(), TallyError> {
+    let mut votes = Vec::new();
+    for _ in 0..51 {
+      votes.push(vec!["Andrew", "Catherine", "Brian", "David"]);
+    }
+    for _ in 0..5 {
+      votes.push(vec!["Catherine", "Brian", "David", "Andrew"]);
+    }
+    for _ in 0..23 {
+      votes.push(vec!["Brian", "Catherine", "David", "Andrew"]);
+    }
+    for _ in 0..21 {
+      votes.push(vec!["David", "Catherine", "Brian", "Andrew"]);
+    }
+
+    let tally = DefaultBordaTally::from_par_iter(1, Variant::Borda, votes)?;
+    assert_eq!(
+      tally.totals(),
+      vec![("Catherine", 205), ("Andrew", 153), ("Brian", 151), ("David", 91)]
+    );
+
+    let mut tally = DefaultBordaTally::new(1, Variant::Borda);
+    tally.add(vec!["Alice", "Bob"])?;
+    assert!(tally.par_add_all(vec![vec!["Alice", "Bob"], vec!["Bob", "Bob"]]).is_err());
+    assert_eq!(tally.totals(), vec![("Alice", 1), ("Bob", 0)]);
+
+    Ok(())
+  }
+
+  #[cfg(feature = "rational")]
+  #[test]
+  fn borda_rational() -> Result<(), TallyError> {
+    use num_rational::Rational64;
+
+    // Dowdall gives 1, 1/2 and 1/3 points, which can't be represented exactly by a float.
+    let mut tally = BordaTally::<&str, Rational64>::new(1, Variant::Dowdall);
+    tally.add_weighted(vec!["Alice", "Bob", "Carlos"], Rational64::from_integer(2))?;
+    tally.add(vec!["Bob", "Alice", "Carlos"])?;
+    tally.add(vec!["Carlos", "Bob", "Alice"])?;
+
+    // Alice: 2 + 1/2 + 1/3, Bob: 2/2 + 1 + 1/2, Carlos: 2/3 + 1/3 + 1
+    assert_eq!(
+      tally.totals(),
+      vec![("Alice", Rational64::new(17, 6)), ("Bob", Rational64::new(5, 2)), ("Carlos", Rational64::new(2, 1))]
+    );
+    assert_eq!(tally.winners().into_unranked(), vec!["Alice"]);
+
+    Ok(())
+  }
 }
@@ -0,0 +1,453 @@
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::check_duplicate;
+use super::plurality::PluralityTally;
+use super::result::RankedWinners;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
+use super::RankedBallot;
+use super::TallyError;
+use super::{sorted_order, COUNT_OVERFLOW_PANIC};
+
+/// Specifies how many rounds of a Bucklin count are held.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Variant {
+    /// Standard Bucklin voting, also known as the Grand Junction system. Rounds continue until a candidate has a majority,
+    /// or until every rank on every vote has been counted.
+    Bucklin,
+
+    /// Majority Choice Approval (MCA). Votes rate candidates as preferred, approved, or disapproved. The first rank of a vote
+    /// holds its preferred candidates, the second rank holds its approved candidates, and any other candidates are disapproved.
+    /// Preferred candidates are counted in the first round and approved candidates in the second, so there are at most two rounds.
+    MajorityChoiceApproval,
+}
+
+impl Variant {
+    // The most rounds that can be held, if votes have enough ranks.
+    fn max_rounds(self) -> Option<usize> {
+        match self {
+            Variant::Bucklin => None,
+            Variant::MajorityChoiceApproval => Some(2),
+        }
+    }
+}
+
+/// A bucklin tally using `u64` integers to count votes.
+/// `DefaultBucklinTally` is generally preferred over `BucklinTally`.
+/// Since this is an alias, refer to [`BucklinTally`](struct.BucklinTally.html) for method documentation.
+///
+/// # Example
+/// ```
+///    use tallystick::bucklin::DefaultBucklinTally;
+///    use tallystick::bucklin::Variant;
+///
+///    let mut tally = DefaultBucklinTally::new(1, Variant::Bucklin);
+///    tally.add_weighted(vec!["Alice", "Bob", "Carlos"], 4).unwrap();
+///    tally.add_weighted(vec!["Bob", "Alice", "Carlos"], 3).unwrap();
+///    tally.add_weighted(vec!["Carlos", "Bob", "Alice"], 3).unwrap();
+///
+///    // Nobody has a majority of first choices, but Bob has a majority once second choices are added.
+///    assert_eq!(tally.majority_round(), Some(1));
+///    assert_eq!(tally.winners().into_unranked(), vec!["Bob"]);
+/// ```
+pub type DefaultBucklinTally<T> = BucklinTally<T, u64>;
+
+/// A generic bucklin tally.
+///
+/// Each round adds the next rank of every vote to the totals from the previous round, until one or more candidates have
+/// a majority of all votes. The candidate with the most votes in that round wins. Candidates are counted for every
+/// vote that ranks them at or above the current round, so a vote may count for several candidates in the same round.
+/// If no candidate ever reaches a majority, the candidate with the most votes in the last round wins.
+///
+/// Generics:
+/// - `T`: The candidate type.
+/// - `C`: The count type. `u64` is recommended, but can be modified to use a different type for counting votes (eg `f64` for fractional vote weights).
+///
+/// # Example
+/// ```
+///    use tallystick::bucklin::BucklinTally;
+///    use tallystick::bucklin::Variant;
+///    use tallystick::RankedBallot;
+///
+///    // Majority Choice Approval, with preferred candidates in the first rank and approved candidates in the second.
+///    let mut tally = BucklinTally::<&str, u64>::new(1, Variant::MajorityChoiceApproval);
+///
+///    let mut vote = RankedBallot::new();
+///    vote.push("Alice");
+///    vote.push_tied(vec!["Bob", "Carlos"]);
+///    tally.add_ranked_weighted(vote, 4).unwrap();
+///
+///    let mut vote = RankedBallot::new();
+///    vote.push("Carlos");
+///    tally.add_ranked_weighted(vote, 3).unwrap();
+///
+///    let mut vote = RankedBallot::new();
+///    vote.push("Bob");
+///    vote.push("Carlos");
+///    tally.add_ranked_weighted(vote, 2).unwrap();
+///
+///    // Carlos is approved by every voter.
+///    assert_eq!(tally.rounds()[1], vec![("Carlos", 9), ("Bob", 6), ("Alice", 4)]);
+///    assert_eq!(tally.winners().into_unranked(), vec!["Carlos"]);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BucklinTally<T, C = u64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    ballots: BallotBox<T, C>, // Identical votes are stored once, with their total weight
+    num_winners: u32,
+    variant: Variant,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    registry: Option<Candidates<T>>, // The declared candidates, if any
+}
+
+// The result of a bucklin count.
+struct Count<T: Clone, C> {
+    rounds: Vec<Vec<(T, C)>>,
+    majority_round: Option<usize>,
+    winners: RankedWinners<T>,
+}
+
+impl<T, C> BucklinTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new `BucklinTally` with the given number of winners.
+    ///
+    /// Winners are ranked by their votes in the final round. If there is a tie, the number of winners might be more than `num_winners`.
+    pub fn new(num_winners: u32, variant: Variant) -> Self {
+        return BucklinTally {
+            ballots: BallotBox::new(),
+            num_winners: num_winners,
+            variant: variant,
+            registry: None,
+        };
+    }
+
+    /// Create a new `BucklinTally` with the given number of winners, and number of expected candidates.
+    pub fn with_capacity(num_winners: u32, variant: Variant, expected_candidates: usize) -> Self {
+        return BucklinTally {
+            ballots: BallotBox::with_capacity(expected_candidates, expected_candidates),
+            num_winners: num_winners,
+            variant: variant,
+            registry: None,
+        };
+    }
+
+    /// Create a new `BucklinTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// Every standing candidate is reported in every round, even if they are not ranked on any vote.
+    /// See [`Candidates`](../struct.Candidates.html) for details.
+    pub fn with_candidates(num_winners: u32, variant: Variant, candidates: Candidates<T>) -> Self {
+        let mut tally = Self::with_capacity(num_winners, variant, candidates.standing().len());
+        tally.ballots.declare(candidates.standing());
+        tally.registry = Some(candidates);
+        return tally;
+    }
+
+    /// Add a new vote
+    ///
+    /// Votes are represented as a vector of ranked candidates, ordered by preference.
+    /// An error will only be returned if `vote` contains duplicate candidates.
+    pub fn add(&mut self, vote: Vec<T>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&vote, C::one())
+    }
+
+    /// Add a new vote by reference
+    pub fn add_ref(&mut self, vote: &[T]) -> Result<(), TallyError> {
+        self.add_weighted_ref(vote, C::one())
+    }
+
+    /// Add a weighted vote.
+    pub fn add_weighted(&mut self, vote: Vec<T>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&vote, weight)
+    }
+
+    /// Add a weighted vote by reference
+    pub fn add_weighted_ref(&mut self, vote: &[T], weight: C) -> Result<(), TallyError> {
+        match &self.registry {
+            Some(registry) => {
+                check_duplicate(vote)?;
+                self.ballots.add_weighted_ref(&registry.filter(vote)?, weight)
+            }
+            None => self.ballots.add_weighted_ref(vote, weight),
+        }
+    }
+
+    /// Add a new ranked vote that may contain tied candidates.
+    ///
+    /// Tied candidates are each counted with the full weight of the vote, in the round their rank is reached.
+    /// Using `Variant::MajorityChoiceApproval`, the first rank holds the preferred candidates and the second rank holds the approved candidates.
+    pub fn add_ranked(&mut self, vote: RankedBallot<T>) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(&vote, C::one())
+    }
+
+    /// Add a ranked vote by reference.
+    pub fn add_ranked_ref(&mut self, vote: &RankedBallot<T>) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(vote, C::one())
+    }
+
+    /// Add a weighted ranked vote.
+    pub fn add_ranked_weighted(&mut self, vote: RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(&vote, weight)
+    }
+
+    /// Add a weighted ranked vote by reference.
+    pub fn add_ranked_weighted_ref(&mut self, vote: &RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+        match &self.registry {
+            Some(registry) => {
+                vote.check_duplicate()?;
+                self.ballots.add_ranked_weighted_ref(&registry.filter_ranked(vote)?, weight)
+            }
+            None => self.ballots.add_ranked_weighted_ref(vote, weight),
+        }
+    }
+
+    /// Add all votes in a `BallotBox`.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+        match &self.registry {
+            Some(registry) => {
+                registry.check_all(ballot_box.candidate_ids())?;
+                for (vote, weight) in ballot_box.iter() {
+                    self.ballots.add_ranked_weighted_ref(&registry.filter_ranked(&vote)?, weight)?;
+                }
+            }
+            None => self.ballots.merge(ballot_box.clone()),
+        }
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Tallies can be merged in any order to get the same result. After merging, candidates are sorted as if by
+    /// [`sort_candidates()`](#method.sort_candidates), so their order doesn't depend on merge order either.
+    /// The variant and number of winners for `other` are ignored.
+    ///
+    /// # Panics
+    /// This method will panic if the merged total weight of all votes overflows the count type.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.ballots.merge(other.ballots);
+        self.sort_candidates();
+    }
+
+    /// Get a list of all candidates seen by this tally.
+    /// Candidates are returned in the order they were first seen.
+    pub fn candidates(&self) -> Vec<T> {
+        return self.ballots.candidates();
+    }
+
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+        let order = sorted_order(self.ballots.candidate_ids(), declared);
+        self.ballots.reorder(&order);
+    }
+
+    /// Get a ranked list of winners, ranked by their votes in the round a majority was reached, or in the last round if no candidate reached a majority.
+    /// Winners with the same rank are tied, and are listed in the order they were first seen.
+    /// The number of winners might be greater than the requested `num_winners` if there is a tie.
+    pub fn winners(&self) -> RankedWinners<T> {
+        return self.count().winners;
+    }
+
+    /// Get the totals for each round, in order.
+    ///
+    /// Each round is sorted from most to fewest votes. Candidates with the same number of votes are listed in the order they were first seen.
+    /// Rounds stop as soon as a candidate has a majority, so the last round is the round used to find the winners.
+    ///
+    /// # Example
+    /// ```
+    ///    use tallystick::bucklin::DefaultBucklinTally;
+    ///    use tallystick::bucklin::Variant;
+    ///
+    ///    let mut tally = DefaultBucklinTally::new(1, Variant::Bucklin);
+    ///    tally.add_weighted(vec!["Alice", "Bob"], 4).unwrap();
+    ///    tally.add_weighted(vec!["Bob", "Alice"], 3).unwrap();
+    ///    tally.add_weighted(vec!["Carlos", "Bob"], 3).unwrap();
+    ///
+    ///    let rounds = tally.rounds();
+    ///    assert_eq!(rounds[0], vec![("Alice", 4), ("Bob", 3), ("Carlos", 3)]);
+    ///    assert_eq!(rounds[1], vec![("Bob", 10), ("Alice", 7), ("Carlos", 3)]);
+    /// ```
+    pub fn rounds(&self) -> Vec<Vec<(T, C)>> {
+        return self.count().rounds;
+    }
+
+    /// Get the round in which a candidate first had a majority of all votes, starting from `0` for first choices.
+    ///
+    /// Returns `None` if no candidate reached a majority, in which case the winners are found using the last round.
+    pub fn majority_round(&self) -> Option<usize> {
+        return self.count().majority_round;
+    }
+
+    // Count the votes, using a plurality tally for the running totals of each round.
+    fn count(&self) -> Count<T, C> {
+        let candidates = self.ballots.candidate_ids();
+        let total = self.ballots.total();
+        let num_ranks = self.ballots.interned().iter().map(|(vote, _)| vote.len()).max().unwrap_or(0);
+        let num_rounds = match self.variant.max_rounds() {
+            Some(max_rounds) => num_ranks.min(max_rounds),
+            None => num_ranks,
+        };
+
+        let mut plurality = PluralityTally::with_capacity(self.num_winners, candidates.len());
+        for candidate in candidates.iter() {
+            plurality.count(candidate, C::zero()).expect(COUNT_OVERFLOW_PANIC);
+        }
+        let mut rounds = Vec::with_capacity(num_rounds);
+        let mut majority_round = None;
+        for round in 0..num_rounds {
+            for (vote, weight) in self.ballots.interned() {
+                if let Some(rank) = vote.ranks().get(round) {
+                    for candidate in rank.iter() {
+                        plurality.count(&candidates[*candidate], *weight).expect(COUNT_OVERFLOW_PANIC);
+                    }
+                }
+            }
+
+            // A candidate has a majority if they have more than half of all votes.
+            let totals = plurality.totals();
+            let majority = totals.first().is_some_and(|(_, votes)| *votes > total - *votes);
+            rounds.push(totals);
+            if majority {
+                majority_round = Some(round);
+                break;
+            }
+        }
+
+        return Count {
+            rounds: rounds,
+            majority_round: majority_round,
+            winners: plurality.winners(),
+        };
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T, C> BucklinTally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send, // Vote count type
+{
+    /// Create a new `BucklinTally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and collected per-thread, then merged.
+    /// An error is returned if any vote contains duplicate candidates.
+    /// Requires the `parallel` feature.
+    pub fn from_par_iter<I>(num_winners: u32, variant: Variant, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let mut tally = Self::new(num_winners, variant);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// See [`BallotBox::par_add_all()`](../struct.BallotBox.html#method.par_add_all) for details.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        self.ballots.par_add_filtered(votes, self.registry.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucklin_tennessee() -> Result<(), TallyError> {
+        // From: https://en.wikipedia.org/wiki/Bucklin_voting
+        let mut tally = DefaultBucklinTally::new(1, Variant::Bucklin);
+        tally.add_weighted(vec!["Memphis", "Nashville", "Chattanooga", "Knoxville"], 42)?;
+        tally.add_weighted(vec!["Nashville", "Chattanooga", "Knoxville", "Memphis"], 26)?;
+        tally.add_weighted(vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"], 15)?;
+        tally.add_weighted(vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"], 17)?;
+
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0], vec![("Memphis", 42), ("Nashville", 26), ("Knoxville", 17), ("Chattanooga", 15)]);
+        assert_eq!(rounds[1], vec![("Nashville", 68), ("Chattanooga", 58), ("Memphis", 42), ("Knoxville", 32)]);
+        assert_eq!(tally.majority_round(), Some(1));
+        assert_eq!(tally.winners().into_unranked(), vec!["Nashville"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bucklin_majority_choice_approval() -> Result<(), TallyError> {
+        let vote = |ranks: Vec<Vec<&'static str>>| {
+            let mut vote = RankedBallot::new();
+            for rank in ranks {
+                vote.push_tied(rank);
+            }
+            vote
+        };
+
+        let mut tally = DefaultBucklinTally::new(1, Variant::MajorityChoiceApproval);
+        tally.add_ranked_weighted(vote(vec![vec!["Alice"], vec!["Bob"]]), 4)?;
+        tally.add_ranked_weighted(vote(vec![vec!["Bob", "Carlos"]]), 2)?;
+        tally.add_ranked_weighted(vote(vec![vec!["Carlos"], vec!["Alice"]]), 3)?;
+        tally.add(vec!["Dave"])?;
+
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0], vec![("Carlos", 5), ("Alice", 4), ("Bob", 2), ("Dave", 1)]);
+        assert_eq!(rounds[1], vec![("Alice", 7), ("Bob", 6), ("Carlos", 5), ("Dave", 1)]);
+        assert_eq!(tally.majority_round(), Some(1));
+        assert_eq!(tally.winners().into_unranked(), vec!["Alice"]);
+
+        // Third ranks are disapproved, so Carlos never reaches a majority.
+        // Without a majority, the candidates with the most approvals win.
+        let mut tally = DefaultBucklinTally::new(1, Variant::MajorityChoiceApproval);
+        tally.add_ranked_weighted(vote(vec![vec!["Alice"], vec!["Bob"], vec!["Carlos"]]), 4)?;
+        tally.add_ranked_weighted(vote(vec![vec!["Carlos"], vec!["Bob"]]), 1)?;
+        tally.add_ranked_weighted(vote(vec![vec!["Carlos"]]), 4)?;
+        tally.add_ranked_weighted(vote(vec![vec!["Dave"]]), 2)?;
+
+        assert_eq!(tally.majority_round(), None);
+        assert_eq!(tally.rounds()[1], vec![("Bob", 5), ("Carlos", 5), ("Alice", 4), ("Dave", 2)]);
+        assert_eq!(tally.winners().into_vec(), vec![("Bob", 0), ("Carlos", 0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bucklin_candidates() -> Result<(), TallyError> {
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos"]);
+        let mut tally = DefaultBucklinTally::with_candidates(1, Variant::Bucklin, candidates);
+        tally.add(vec!["Alice"])?;
+        tally.add(vec!["Bob", "Alice"])?;
+        assert!(tally.add(vec!["Dave"]).is_err());
+
+        assert_eq!(tally.rounds()[0], vec![("Alice", 1), ("Bob", 1), ("Carlos", 0)]);
+        assert_eq!(tally.majority_round(), Some(1));
+        assert_eq!(tally.winners().into_unranked(), vec!["Alice"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::RankedBallot;
+use super::TallyError;
+
+/// A `Candidates` registry declares the candidates standing in an election before any votes are counted.
+///
+/// Tallies normally learn about candidates from the votes they count, so a candidate without any votes is never reported.
+/// A tally created with `with_candidates()` is given the full slate up-front instead:
+///
+/// - Every standing candidate is reported by `candidates()`, `totals()` and `ranked()`, even if they receive no votes.
+///   Standing candidates are listed in the order they were declared, followed by write-in candidates in the order they were first seen.
+/// - Votes for withdrawn candidates are ignored. On a ranked vote, the candidates ranked after them move up.
+/// - A vote for a candidate that is neither standing nor withdrawn is rejected with `TallyError::UnknownCandidate`,
+///   unless write-ins are accepted.
+/// - Ranked tallies treat standing candidates that are not ranked on a vote as less preferred than every ranked candidate.
+///   [`stv::Tally`](stv/struct.Tally.html) never transfers a vote to an unranked candidate, and
+///   [`CondorcetTally`](condorcet/struct.CondorcetTally.html) and [`SchulzeTally`](schulze/struct.SchulzeTally.html)
+///   count every ranked candidate as preferred over every unranked standing candidate.
+///
+/// # Example
+/// ```
+///    use tallystick::Candidates;
+///    use tallystick::plurality::DefaultPluralityTally;
+///
+///    let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos", "Dave"]).withdrawn(vec!["Dave"]).write_ins(true);
+///    let mut tally = DefaultPluralityTally::with_candidates(1, candidates);
+///    tally.add("Bob").unwrap();
+///    tally.add("Dave").unwrap(); // Ignored, since Dave has withdrawn
+///    tally.add("Erin").unwrap(); // A write-in
+///
+///    assert_eq!(tally.totals(), vec![("Bob", 1), ("Erin", 1), ("Alice", 0), ("Carlos", 0)]);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Candidates<T>
+where
+    T: Eq + Clone + Hash, // Candidate
+{
+    standing: Vec<T>,
+    withdrawn: Vec<T>,
+    write_ins: bool,
+}
+
+impl<T> Candidates<T>
+where
+    T: Eq + Clone + Hash, // Candidate
+{
+    /// Create a new registry with the given candidates, in the order they should be reported.
+    /// Write-ins are not accepted by default. Duplicate candidates are ignored.
+    pub fn new(candidates: Vec<T>) -> Self {
+        let mut standing: Vec<T> = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            if !standing.contains(&candidate) {
+                standing.push(candidate);
+            }
+        }
+        return Candidates {
+            standing: standing,
+            withdrawn: Vec::new(),
+            write_ins: false,
+        };
+    }
+
+    /// Mark candidates as withdrawn. Withdrawn candidates are no longer standing, and votes for them are ignored.
+    pub fn withdrawn(mut self, withdrawn: Vec<T>) -> Self {
+        for candidate in withdrawn {
+            self.standing.retain(|standing| *standing != candidate);
+            if !self.withdrawn.contains(&candidate) {
+                self.withdrawn.push(candidate);
+            }
+        }
+        return self;
+    }
+
+    /// Set whether votes for candidates that are not in the registry are accepted as write-ins. Defaults to `false`.
+    pub fn write_ins(mut self, write_ins: bool) -> Self {
+        self.write_ins = write_ins;
+        return self;
+    }
+
+    /// Get the standing candidates, in the order they were declared.
+    pub fn standing(&self) -> &[T] {
+        return &self.standing;
+    }
+
+    /// Get the withdrawn candidates.
+    pub fn withdrawn_candidates(&self) -> &[T] {
+        return &self.withdrawn;
+    }
+
+    /// Check if write-in candidates are accepted.
+    pub fn accepts_write_ins(&self) -> bool {
+        return self.write_ins;
+    }
+
+    /// Check if a candidate has withdrawn.
+    pub fn is_withdrawn(&self, candidate: &T) -> bool {
+        return self.withdrawn.contains(candidate);
+    }
+
+    // Check a candidate marked on a vote. Returns `false` if the candidate has withdrawn and the mark should be ignored.
+    pub(crate) fn check(&self, candidate: &T) -> Result<bool, TallyError> {
+        if self.is_withdrawn(candidate) {
+            return Ok(false);
+        }
+        if !self.write_ins && !self.standing.contains(candidate) {
+            return Err(TallyError::UnknownCandidate);
+        }
+        return Ok(true);
+    }
+
+    // Check every candidate in a ballot box up front, so that no votes are added if any are rejected.
+    pub(crate) fn check_all(&self, candidates: &[T]) -> Result<(), TallyError> {
+        for candidate in candidates {
+            self.check(candidate)?;
+        }
+        return Ok(());
+    }
+
+    // Remove withdrawn candidates from a vote, returning an error if it marks an unknown candidate.
+    pub(crate) fn filter(&self, vote: &[T]) -> Result<Vec<T>, TallyError> {
+        let mut filtered = Vec::with_capacity(vote.len());
+        for candidate in vote {
+            if self.check(candidate)? {
+                filtered.push(candidate.clone());
+            }
+        }
+        return Ok(filtered);
+    }
+
+    // Remove withdrawn candidates from a ranked vote, returning an error if it marks an unknown candidate.
+    // Ranks that only contained withdrawn candidates are removed, so that later ranks move up.
+    pub(crate) fn filter_ranked(&self, vote: &RankedBallot<T>) -> Result<RankedBallot<T>, TallyError> {
+        let mut filtered = RankedBallot::new();
+        for rank in vote.ranks() {
+            filtered.push_tied(self.filter(rank)?);
+        }
+        return Ok(filtered);
+    }
+
+    // Remove withdrawn candidates from a score vote, returning an error if it marks an unknown candidate.
+    pub(crate) fn filter_scores<C: Copy>(&self, vote: &[(T, C)]) -> Result<Vec<(T, C)>, TallyError> {
+        let mut filtered = Vec::with_capacity(vote.len());
+        for (candidate, score) in vote {
+            if self.check(candidate)? {
+                filtered.push((candidate.clone(), *score));
+            }
+        }
+        return Ok(filtered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_filter() -> Result<(), TallyError> {
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos", "Alice"]).withdrawn(vec!["Bob"]);
+        assert_eq!(candidates.standing(), &["Alice", "Carlos"]);
+        assert_eq!(candidates.withdrawn_candidates(), &["Bob"]);
+        assert!(candidates.is_withdrawn(&"Bob"));
+
+        assert_eq!(candidates.filter(&["Bob", "Carlos"])?, vec!["Carlos"]);
+        let ranked = RankedBallot::from(vec![vec!["Bob"], vec!["Carlos", "Bob"], vec!["Alice"]]);
+        assert_eq!(candidates.filter_ranked(&ranked)?, RankedBallot::from(vec!["Carlos", "Alice"]));
+        assert_eq!(candidates.filter_scores(&[("Alice", 3), ("Bob", 5)])?, vec![("Alice", 3)]);
+        assert!(candidates.filter(&["Dave"]).is_err());
+
+        let candidates = candidates.write_ins(true);
+        assert_eq!(candidates.filter(&["Dave", "Bob"])?, vec!["Dave"]);
+
+        Ok(())
+    }
+}
@@ -1,14 +1,31 @@
 use super::check_duplicate;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
+use super::RankedBallot;
 use super::RankedWinners;
 use super::TallyError;
+use super::{add_count, sorted_order, COUNT_OVERFLOW_PANIC};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
 use hashbrown::HashMap;
-use num_traits::cast::NumCast;
+use num_traits::cast::FromPrimitive;
 use num_traits::Num;
-use petgraph::algo::tarjan_scc;
+
+#[cfg(feature = "serde")]
+use core::convert::TryFrom;
+
+#[cfg(feature = "std")]
 use petgraph::graph::NodeIndex;
+#[cfg(feature = "std")]
 use petgraph::Graph;
-use std::hash::Hash;
-use std::ops::AddAssign;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// A condorcet tally using `u64` integers to count votes.
 /// `DefaultCondorcetTally` is generally preferred over `CondorcetTally`, except when using vote weights that contains fractions.
@@ -56,20 +73,194 @@ pub type DefaultCondorcetTally<T> = CondorcetTally<T, u64>;
 ///
 ///    let winners = tally.winners();
 /// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "CondorcetState<T, C>"))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "T: Deserialize<'de>, C: Deserialize<'de>")))]
 pub struct CondorcetTally<T, C = u64>
 where
-    T: Eq + Clone + Hash,                             // Candidate type
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // Count type
+    T: Eq + Clone + Hash,                                       // Candidate type
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Count type
 {
-    crate running_total: HashMap<(usize, usize), C>,
-    crate num_winners: u32,
-    crate candidates: HashMap<T, usize>, // Map candiates to a unique integer identifiers
+    pub(crate) running_total: PairwiseMatrix<C>,
+    pub(crate) num_winners: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) candidates: HashMap<T, usize>, // Map candiates to a unique integer identifiers
+    pub(crate) candidate_ids: Vec<T>, // Map unique integer identifiers back to candidates
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) registry: Option<Candidates<T>>, // The declared candidates, if any. Standing candidates have identifiers `0..n`.
+}
+
+// The serialized state of a `CondorcetTally`. The candidate index is rebuilt when it is deserialized.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Eq + Clone + Hash + Deserialize<'de>, C: Copy + Num + AddAssign + Deserialize<'de>"))]
+struct CondorcetState<T: Eq + Clone + Hash, C: Copy + Num + AddAssign> {
+    running_total: PairwiseMatrix<C>,
+    num_winners: u32,
+    candidate_ids: Vec<T>,
+    #[serde(default)]
+    registry: Option<Candidates<T>>,
+}
+
+#[cfg(feature = "serde")]
+impl<T, C> TryFrom<CondorcetState<T, C>> for CondorcetTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate type
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Count type
+{
+    type Error = TallyError;
+
+    fn try_from(state: CondorcetState<T, C>) -> Result<Self, TallyError> {
+        if state.running_total.len() != state.candidate_ids.len() {
+            return Err(TallyError::InvalidState);
+        }
+        if let Some(registry) = &state.registry {
+            if !state.candidate_ids.starts_with(registry.standing()) {
+                return Err(TallyError::InvalidState);
+            }
+        }
+        let mut candidates = HashMap::with_capacity(state.candidate_ids.len());
+        for (id, candidate) in state.candidate_ids.iter().enumerate() {
+            if candidates.insert(candidate.clone(), id).is_some() {
+                return Err(TallyError::InvalidState);
+            }
+        }
+        return Ok(CondorcetTally {
+            running_total: state.running_total,
+            num_winners: state.num_winners,
+            candidates: candidates,
+            candidate_ids: state.candidate_ids,
+            registry: state.registry,
+        });
+    }
+}
+
+// A dense row-major matrix of pairwise counts, indexed by candidate identifiers.
+// The count at `(i, j)` is the number of votes where candidate `i` is preferred over candidate `j`.
+// The matrix grows as new candidates are seen, doubling the row length so that growth is amortized.
+#[derive(Debug, Clone)]
+pub(crate) struct PairwiseMatrix<C: Copy + Num + AddAssign> {
+    size: usize,   // Number of candidates in the matrix
+    stride: usize, // Allocated row length, always greater than or equal to `size`
+    counts: Vec<C>,
+}
+
+impl<C: Copy + Numeric + AddAssign> PairwiseMatrix<C> {
+    pub(crate) fn with_capacity(expected_candidates: usize) -> Self {
+        return PairwiseMatrix {
+            size: 0,
+            stride: expected_candidates,
+            counts: vec![C::zero(); expected_candidates * expected_candidates],
+        };
+    }
+
+    // Get the number of candidates in the matrix.
+    pub(crate) fn len(&self) -> usize {
+        return self.size;
+    }
+
+    // Grow the matrix to hold `size` candidates. New candidates start with zero counts.
+    pub(crate) fn grow(&mut self, size: usize) {
+        if size <= self.size {
+            return;
+        }
+        if size > self.stride {
+            let stride = core::cmp::max(size, self.stride * 2);
+            let mut counts = vec![C::zero(); stride * stride];
+            for i in 0..self.size {
+                let old_row = &self.counts[(i * self.stride)..(i * self.stride + self.size)];
+                counts[(i * stride)..(i * stride + self.size)].copy_from_slice(old_row);
+            }
+            self.counts = counts;
+            self.stride = stride;
+        }
+        self.size = size;
+    }
+
+    // Get a copy of the matrix with the candidates moved, where `order[i]` is the current position of the candidate moved to `i`.
+    pub(crate) fn reordered(&self, order: &[usize]) -> Self {
+        let mut matrix = PairwiseMatrix::with_capacity(self.size);
+        matrix.grow(self.size);
+        for i in 0..self.size {
+            for j in 0..self.size {
+                matrix.counts[i * matrix.stride + j] = self.get(order[i], order[j]);
+            }
+        }
+        return matrix;
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, i: usize, j: usize) -> C {
+        return self.counts[i * self.stride + j];
+    }
+
+    #[inline]
+    pub(crate) fn add(&mut self, i: usize, j: usize, weight: C) -> Result<(), TallyError> {
+        return add_count(&mut self.counts[i * self.stride + j], weight);
+    }
+
+    // Add weights to many cells at once, or to none of them if any cell would overflow. Each cell must appear at most once.
+    pub(crate) fn add_all(&mut self, additions: &[(usize, usize, C)]) -> Result<(), TallyError> {
+        let mut counts = Vec::with_capacity(additions.len());
+        for (i, j, weight) in additions.iter() {
+            counts.push(self.get(*i, *j).checked_add(*weight).ok_or(TallyError::CountOverflow)?);
+        }
+        for ((i, j, _), count) in additions.iter().zip(counts) {
+            self.counts[i * self.stride + j] = count;
+        }
+        return Ok(());
+    }
+
+    // Shrink the matrix back to `size` candidates. The removed candidates must not have been counted yet.
+    pub(crate) fn truncate(&mut self, size: usize) {
+        if size < self.size {
+            self.size = size;
+        }
+    }
+}
+
+// The matrix is serialized without the unused space at the end of each row, eg `{"size": 2, "counts": [0, 3, 1, 0]}`.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct PairwiseCounts<C> {
+    size: usize,
+    counts: Vec<C>,
+}
+
+#[cfg(feature = "serde")]
+impl<C: Copy + Num + AddAssign + Serialize> Serialize for PairwiseMatrix<C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut counts = Vec::with_capacity(self.size * self.size);
+        for i in 0..self.size {
+            counts.extend_from_slice(&self.counts[(i * self.stride)..(i * self.stride + self.size)]);
+        }
+        let pairwise_counts = PairwiseCounts {
+            size: self.size,
+            counts: counts,
+        };
+        pairwise_counts.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, C: Copy + Num + AddAssign + Deserialize<'de>> Deserialize<'de> for PairwiseMatrix<C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairwise_counts = PairwiseCounts::deserialize(deserializer)?;
+        if pairwise_counts.size.checked_mul(pairwise_counts.size) != Some(pairwise_counts.counts.len()) {
+            return Err(serde::de::Error::custom(TallyError::InvalidState));
+        }
+        Ok(PairwiseMatrix {
+            size: pairwise_counts.size,
+            stride: pairwise_counts.size,
+            counts: pairwise_counts.counts,
+        })
+    }
 }
 
 impl<T, C> CondorcetTally<T, C>
 where
-    T: Eq + Clone + Hash,                             // Candidate type
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // Count type
+    T: Eq + Clone + Hash,                                       // Candidate type
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Count type
 {
     /// Create a new `CondorcetTally` with the given number of winners.
     ///
@@ -77,21 +268,51 @@ where
     /// (See [`winners()`](#method.winners) for more information on ties.)
     pub fn new(num_winners: u32) -> Self {
         return CondorcetTally {
-            running_total: HashMap::new(),
+            running_total: PairwiseMatrix::with_capacity(0),
             num_winners: num_winners,
             candidates: HashMap::new(),
+            candidate_ids: Vec::new(),
+            registry: None,
         };
     }
 
     /// Create a new `CondorcetTally` with the given number of winners, and number of expected candidates.
     pub fn with_capacity(num_winners: u32, expected_candidates: usize) -> Self {
         return CondorcetTally {
-            running_total: HashMap::with_capacity(expected_candidates ^ 2),
+            running_total: PairwiseMatrix::with_capacity(expected_candidates),
             num_winners: num_winners,
             candidates: HashMap::with_capacity(expected_candidates),
+            candidate_ids: Vec::with_capacity(expected_candidates),
+            registry: None,
         };
     }
 
+    /// Create a new `CondorcetTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// Every standing candidate is reported, even if they are not ranked on any vote. Standing candidates that are not
+    /// ranked on a vote are counted as less preferred than every candidate ranked on it. Withdrawn candidates are removed
+    /// from votes, and votes with unknown candidates are rejected unless write-ins are accepted.
+    /// See [`Candidates`](../struct.Candidates.html) for details.
+    ///
+    /// # Example
+    /// ```
+    ///    use tallystick::Candidates;
+    ///    use tallystick::condorcet::DefaultCondorcetTally;
+    ///
+    ///    let mut tally = DefaultCondorcetTally::with_candidates(1, Candidates::new(vec!["Alice", "Bob", "Carlos"]));
+    ///    tally.add(vec!["Bob"]).unwrap();
+    ///
+    ///    // Bob is preferred over both unranked candidates.
+    ///    assert_eq!(tally.totals(), vec![(("Bob", "Alice"), 1), (("Bob", "Carlos"), 1)]);
+    ///    assert_eq!(tally.winners().into_unranked(), vec!["Bob"]);
+    /// ```
+    pub fn with_candidates(num_winners: u32, candidates: Candidates<T>) -> Self {
+        let mut tally = Self::with_capacity(num_winners, candidates.standing().len());
+        tally.mapped_candidates(candidates.standing());
+        tally.registry = Some(candidates);
+        return tally;
+    }
+
     /// Add a new vote
     pub fn add(&mut self, vote: Vec<T>) -> Result<(), TallyError> {
         self.add_weighted_ref(&vote, C::one())
@@ -115,22 +336,286 @@ where
         }
         check_duplicate(vote)?;
 
-        let selection = self.mapped_candidates(&vote);
+        let filtered = match &self.registry {
+            Some(registry) => Some(registry.filter(vote)?),
+            None => None,
+        };
+        let seen = self.candidate_ids.len();
+        let selection = self.mapped_candidates(filtered.as_ref().map_or(vote, |filtered| filtered));
+        let mut additions = Vec::new();
+        self.push_mapped(&selection, weight, &mut additions);
+        self.push_unranked(&selection, weight, &mut additions);
+        if let Err(error) = self.running_total.add_all(&additions) {
+            self.forget_candidates(seen);
+            return Err(error);
+        }
+
+        Ok(())
+    }
 
+    /// Add many pre-aggregated votes at once.
+    ///
+    /// Each element of `votes` is a vote paired with the number of times it was cast (or its total weight).
+    /// This is much faster than adding identical votes one at a time, since each unique vote is only processed once.
+    /// If any vote contains duplicate candidates, or a count would overflow, an error is returned and none of the votes are added.
+    ///
+    /// # Example
+    /// ```
+    ///    use tallystick::condorcet::DefaultCondorcetTally;
+    ///
+    ///    let mut tally = DefaultCondorcetTally::new(1);
+    ///    tally.add_aggregated(&[
+    ///        (vec!["Alice", "Bob", "Carlos"], 50_000),
+    ///        (vec!["Bob", "Alice", "Carlos"], 40_000),
+    ///        (vec!["Carlos", "Alice", "Bob"], 30_000),
+    ///    ]).unwrap();
+    ///
+    ///    assert_eq!(tally.winners().into_unranked(), vec!["Alice"]);
+    /// ```
+    pub fn add_aggregated(&mut self, votes: &[(Vec<T>, C)]) -> Result<(), TallyError> {
+        let mut filtered = Vec::with_capacity(votes.len());
+        for (vote, weight) in votes.iter() {
+            check_duplicate(vote)?;
+            if let Some(registry) = &self.registry {
+                filtered.push((registry.filter(vote)?, *weight));
+            }
+        }
+
+        // Map every vote up front, then count them into a copy of the matrix so that an overflow leaves this tally unchanged.
+        let seen = self.candidate_ids.len();
+        let mut selections = Vec::with_capacity(votes.len());
+        if self.registry.is_none() {
+            for (vote, weight) in votes.iter() {
+                selections.push((self.mapped_candidates(vote), *weight));
+            }
+        }
+        for (vote, weight) in filtered {
+            selections.push((self.mapped_candidates(&vote), weight));
+        }
+
+        let mut running_total = self.running_total.clone();
+        let mut additions = Vec::new();
+        for (selection, weight) in selections.iter() {
+            additions.clear();
+            self.push_mapped(selection, *weight, &mut additions);
+            self.push_unranked(selection, *weight, &mut additions);
+            if let Err(error) = running_total.add_all(&additions) {
+                self.forget_candidates(seen);
+                return Err(error);
+            }
+        }
+        self.running_total = running_total;
+
+        Ok(())
+    }
+
+    // Get the pairwise counts for a vote that has already been mapped to candidate identifiers.
+    fn push_mapped(&self, selection: &[usize], weight: C, additions: &mut Vec<(usize, usize, C)>) {
         for (i, candidate) in selection.iter().enumerate() {
-            let mut j = i + 1;
-            while let Some(candidate_2) = selection.get(j) {
-                *self.running_total.entry((*candidate, *candidate_2)).or_insert(C::zero()) += weight;
-                j += 1;
+            for candidate_2 in selection[(i + 1)..].iter() {
+                additions.push((*candidate, *candidate_2, weight));
+            }
+        }
+    }
+
+    // Count every candidate on a mapped vote as preferred over each standing candidate that is not on it.
+    // Standing candidates are mapped first by `with_candidates()`, so their identifiers are `0..n`.
+    fn push_unranked(&self, ranked: &[usize], weight: C, additions: &mut Vec<(usize, usize, C)>) {
+        let num_standing = match &self.registry {
+            Some(registry) => registry.standing().len(),
+            None => return,
+        };
+        for unranked in 0..num_standing {
+            if !ranked.contains(&unranked) {
+                for candidate in ranked.iter() {
+                    additions.push((*candidate, unranked, weight));
+                }
+            }
+        }
+    }
+
+    /// Add a new ranked vote that may contain tied candidates.
+    ///
+    /// For each pair of tied candidates, both candidates are given half a point over the other.
+    /// If `vote` contains ties, a float or rational count type must be used, otherwise an error is returned.
+    ///
+    /// # Example
+    /// ```
+    ///    use tallystick::condorcet::CondorcetTally;
+    ///    use tallystick::RankedBallot;
+    ///
+    ///    let mut tally = CondorcetTally::<&str, f64>::new(1);
+    ///    tally.add_ranked(RankedBallot::from(vec![vec!["Alice", "Bob"], vec!["Carlos"]])).unwrap();
+    ///
+    ///    // Alice and Bob are each preferred over the other on half a ballot.
+    ///    for ((candidate1, candidate2), num_votes) in tally.totals().iter() {
+    ///       println!("{} is preferred over {} {} times", candidate1, candidate2, num_votes);
+    ///    }
+    /// ```
+    pub fn add_ranked(&mut self, vote: RankedBallot<T>) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(&vote, C::one())
+    }
+
+    /// Add a ranked vote by reference.
+    pub fn add_ranked_ref(&mut self, vote: &RankedBallot<T>) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(vote, C::one())
+    }
+
+    /// Add a weighted ranked vote.
+    pub fn add_ranked_weighted(&mut self, vote: RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(&vote, weight)
+    }
+
+    /// Add a weighted ranked vote by reference.
+    pub fn add_ranked_weighted_ref(&mut self, vote: &RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+        if vote.is_empty() {
+            return Ok(());
+        }
+        vote.check_duplicate()?;
+        if vote.has_ties() && !C::fraction() {
+            return Err(TallyError::TiedVoteRequiresFractionalCount);
+        }
+
+        let filtered = match &self.registry {
+            Some(registry) => Some(registry.filter_ranked(vote)?),
+            None => None,
+        };
+        let vote = filtered.as_ref().unwrap_or(vote);
+        let seen = self.candidate_ids.len();
+        let ranks: Vec<Vec<usize>> = vote.ranks().iter().map(|rank| self.mapped_candidates(rank)).collect();
+        let mut additions = Vec::new();
+        self.push_mapped_ranks(&ranks, weight, &mut additions);
+        self.push_unranked(&ranks.concat(), weight, &mut additions);
+        if let Err(error) = self.running_total.add_all(&additions) {
+            self.forget_candidates(seen);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Add all votes in a `BallotBox`.
+    ///
+    /// Each unique vote in the ballot box is only counted once, weighted by the number of times it was cast.
+    /// If the ballot box contains ties, a float or rational count type must be used, otherwise an error is returned.
+    /// If a count would overflow, an error is returned and none of the votes in the ballot box are added.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+        if ballot_box.has_ties() && !C::fraction() {
+            return Err(TallyError::TiedVoteRequiresFractionalCount);
+        }
+
+        // Map ballot box candidates to our own candidate identifiers once, up front.
+        // Withdrawn candidates are not mapped, and are removed from each vote.
+        let mut standing = Vec::with_capacity(ballot_box.candidate_ids().len());
+        for candidate in ballot_box.candidate_ids() {
+            match &self.registry {
+                Some(registry) => standing.push(registry.check(candidate)?),
+                None => standing.push(true),
             }
         }
+        let kept: Vec<T> = ballot_box
+            .candidate_ids()
+            .iter()
+            .zip(standing.iter())
+            .filter(|(_, standing)| **standing)
+            .map(|(candidate, _)| candidate.clone())
+            .collect();
+        let seen = self.candidate_ids.len();
+        let mut mapped = self.mapped_candidates(&kept).into_iter();
+        let ids: Vec<Option<usize>> = standing.iter().map(|standing| if *standing { mapped.next() } else { None }).collect();
+
+        // Count into a copy of the matrix so that an overflow leaves this tally unchanged.
+        let mut running_total = self.running_total.clone();
+        let mut additions = Vec::new();
+        for (ballot, weight) in ballot_box.interned() {
+            let ranks: Vec<Vec<usize>> = ballot
+                .ranks()
+                .iter()
+                .map(|rank| rank.iter().filter_map(|id| ids[*id]).collect::<Vec<usize>>())
+                .filter(|rank| !rank.is_empty())
+                .collect();
+            additions.clear();
+            self.push_mapped_ranks(&ranks, *weight, &mut additions);
+            self.push_unranked(&ranks.concat(), *weight, &mut additions);
+            if let Err(error) = running_total.add_all(&additions) {
+                self.forget_candidates(seen);
+                return Err(error);
+            }
+        }
+        self.running_total = running_total;
 
         Ok(())
     }
 
+    // Get the pairwise counts for a ranked vote that has already been mapped to candidate identifiers.
+    fn push_mapped_ranks(&self, ranks: &[Vec<usize>], weight: C, additions: &mut Vec<(usize, usize, C)>) {
+        let half = weight / (C::one() + C::one());
+
+        for (i, rank) in ranks.iter().enumerate() {
+            for (k, candidate) in rank.iter().enumerate() {
+                // Tied candidates are each preferred over the other on half the vote.
+                for candidate_2 in rank[k + 1..].iter() {
+                    additions.push((*candidate, *candidate_2, half));
+                    additions.push((*candidate_2, *candidate, half));
+                }
+                for lower_rank in ranks[i + 1..].iter() {
+                    for candidate_2 in lower_rank.iter() {
+                        additions.push((*candidate, *candidate_2, weight));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Candidates in `other` are mapped onto the candidates of this tally, so the two tallies do not need to have seen
+    /// the same candidates or seen them in the same order. Tallies can be merged in any order to get the same result.
+    /// After merging, candidates are sorted as if by [`sort_candidates()`](#method.sort_candidates), so the order of
+    /// `candidates()`, and of tied candidates in `ranked()` and `winners()`, doesn't depend on merge order either.
+    /// Floating point count types may still accumulate rounding differences depending on merge order; use an integer or
+    /// rational count type for exact results. The number of winners for `other` is ignored.
+    ///
+    /// # Example
+    /// ```
+    ///    use tallystick::condorcet::DefaultCondorcetTally;
+    ///
+    ///    let mut precinct_1 = DefaultCondorcetTally::new(1);
+    ///    precinct_1.add_weighted(vec!["Alice", "Bob"], 10).unwrap();
+    ///
+    ///    let mut precinct_2 = DefaultCondorcetTally::new(1);
+    ///    precinct_2.add_weighted(vec!["Carlos", "Bob", "Alice"], 12).unwrap();
+    ///
+    ///    precinct_1.merge(precinct_2);
+    ///    assert_eq!(precinct_1.winners().into_unranked(), vec!["Carlos"]);
+    /// ```
+    ///
+    /// # Panics
+    /// This method will panic if a merged pairwise count overflows the count type.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.merge_counts(other);
+        self.sort_candidates();
+    }
+
+    // Merge another tally into this one, placing candidates first seen by `other` after the candidates already seen.
+    pub(crate) fn merge_counts(&mut self, other: Self) {
+        let ids = self.mapped_candidates(&other.candidate_ids);
+        for i in 0..ids.len() {
+            for j in 0..ids.len() {
+                if i != j {
+                    self.running_total.add(ids[i], ids[j], other.running_total.get(i, j)).expect(COUNT_OVERFLOW_PANIC);
+                }
+            }
+        }
+    }
+
     /// Get total counts for this tally.
     /// Totals are returned as a list of pairwise comparisons
     /// For a pairwise comparison `((T1, T2), C)`, `C` is the number of votes where candidate `T1` is preferred over candidate `T2`.
+    /// Pairwise comparisons with a count of zero are not included.
     ///
     /// # Example
     /// ```
@@ -149,25 +634,22 @@ where
     /// ```
     // TODO: 'C' could just be a regular integer (usize?).
     pub fn totals(&self) -> Vec<((T, T), C)> {
-        let mut totals = Vec::<((T, T), C)>::with_capacity(self.running_total.len());
+        let num_candidates = self.running_total.len();
+        let mut totals = Vec::<((T, T), C)>::with_capacity(num_candidates * num_candidates);
 
-        // Invert the candidate map.
-        let mut candidates = HashMap::<usize, T>::with_capacity(self.candidates.len());
-        for (candidate, i) in self.candidates.iter() {
-            candidates.insert(*i, candidate.clone());
-        }
-
-        for ((candidate1, candidate2), count) in self.running_total.iter() {
-            // Ok to unwrap here since candidates must exist.
-            let candidate1 = candidates.get(candidate1).unwrap().clone();
-            let candidate2 = candidates.get(candidate2).unwrap().clone();
-            totals.push(((candidate1, candidate2), *count));
+        for i in 0..num_candidates {
+            for j in 0..num_candidates {
+                let count = self.running_total.get(i, j);
+                if i != j && count != C::zero() {
+                    totals.push(((self.candidate_ids[i].clone(), self.candidate_ids[j].clone()), count));
+                }
+            }
         }
 
         return totals;
     }
 
-    /// Get a ranked list of all candidates. Candidates with the same rank are tied.
+    /// Get a ranked list of all candidates. Candidates with the same rank are tied, and are listed in the order they were first seen.
     /// Candidates are ranked in ascending order. The highest ranked candidate has a rank of `0`.
     ///
     /// # Example
@@ -189,26 +671,19 @@ where
     /// ```
     pub fn ranked(&self) -> Vec<(T, u32)> {
         // Compute smith-sets using Tarjan's strongly connected components algorithm.
-        let graph = self.build_graph();
-        let smith_sets = tarjan_scc(&graph);
-
-        // Invert the candidate map, cloned candidates will be moved into the winners list.
-        let mut candidates = HashMap::<usize, T>::with_capacity(self.candidates.len());
-        for (candidate, i) in self.candidates.iter() {
-            candidates.insert(*i, candidate.clone());
-        }
+        let smith_sets = tarjan_scc(self.candidate_ids.len(), |loser, winner| self.has_edge(loser, winner));
 
         // Add to ranked list.
         let mut ranked = Vec::<(T, u32)>::with_capacity(self.candidates.len());
-        for (rank, smith_set) in smith_sets.iter().enumerate() {
+        for (rank, mut smith_set) in smith_sets.into_iter().enumerate() {
             // We need to add all members of a smith set at the same time,
             // even if it means more winners than needed. All members of a smith_set
             // have the same rank.
 
-            // TODO: Check performance difference between cloning here and using a stable graph (where we can remove_node())
-            for graph_id in smith_set.iter() {
-                let candidate = graph.node_weight(*graph_id).unwrap(); // Safe to unwrap here since graph should always contain a node-weight at this graph-id.
-                ranked.push((candidate.clone(), rank as u32));
+            // Candidate identifiers are assigned in the order candidates were first seen, so sorting keeps ties in that order.
+            smith_set.sort();
+            for candidate_id in smith_set.iter() {
+                ranked.push((self.candidate_ids[*candidate_id].clone(), rank as u32));
             }
         }
 
@@ -269,51 +744,225 @@ where
     ///
     /// <img src="https://raw.githubusercontent.com/phayes/tallystick/master/docs/pairwise-graph.png" height="320px">
     /// Image Source: [https://arxiv.org/pdf/1804.02973.pdf](https://arxiv.org/pdf/1804.02973.pdf)
+    ///
+    /// This method requires the `std` feature.
+    #[cfg(feature = "std")]
     pub fn build_graph(&self) -> Graph<T, (C, C)> {
-        let mut graph = Graph::<T, (C, C)>::with_capacity(self.candidates.len(), self.candidates.len() ^ 2);
+        let num_candidates = self.candidate_ids.len();
+        let mut graph = Graph::<T, (C, C)>::with_capacity(num_candidates, num_candidates * num_candidates);
 
-        // Add all candidates
-        let mut graph_ids = HashMap::<usize, NodeIndex>::new();
-        for (candidate, candidate_id) in self.candidates.iter() {
-            graph_ids.insert(*candidate_id, graph.add_node(candidate.clone()));
+        // Add all candidates, graph node indexes are the same as candidate identifiers.
+        let mut graph_ids = Vec::<NodeIndex>::with_capacity(num_candidates);
+        for candidate in self.candidate_ids.iter() {
+            graph_ids.push(graph.add_node(candidate.clone()));
         }
 
-        let zero = C::zero();
-        for ((candidate_1, candidate_2), votecount_1) in self.running_total.iter() {
-            let votecount_2 = self.running_total.get(&(*candidate_2, *candidate_1)).unwrap_or(&zero);
+        for candidate_1 in 0..num_candidates {
+            for candidate_2 in 0..num_candidates {
+                if candidate_1 == candidate_2 {
+                    continue;
+                }
 
-            // Only add if candidate_1 vs candidate_2 votecount is larger than candidate_2 vs candidate_1 votecount
-            // Otherwise we will catch it when we come around to it again.
-            if votecount_1 >= votecount_2 {
-                let candidate_1_id = graph_ids.get(candidate_1).unwrap(); // Safe to unwrap since graph-ids contain all candidates.
-                let candidate_2_id = graph_ids.get(candidate_2).unwrap();
-                graph.add_edge(*candidate_2_id, *candidate_1_id, (*votecount_1, *votecount_2));
+                // Only add if candidate_1 vs candidate_2 votecount is larger than candidate_2 vs candidate_1 votecount
+                // Otherwise we will catch it when we come around to it again.
+                if self.has_edge(candidate_2, candidate_1) {
+                    let votecount_1 = self.running_total.get(candidate_1, candidate_2);
+                    let votecount_2 = self.running_total.get(candidate_2, candidate_1);
+                    graph.add_edge(graph_ids[candidate_2], graph_ids[candidate_1], (votecount_1, votecount_2));
+                }
             }
         }
 
         return graph;
     }
 
+    // Check if the pairwise graph has an edge leading from `loser` to `winner`.
+    // There is an edge if `winner` is preferred over `loser` by at least one vote, and by at least as many votes as the reverse.
+    fn has_edge(&self, loser: usize, winner: usize) -> bool {
+        let winner_count = self.running_total.get(winner, loser);
+        return winner_count != C::zero() && winner_count >= self.running_total.get(loser, winner);
+    }
+
     /// Get a list of all candidates seen by this tally.
-    /// Candidates are returned in no particular order.
+    /// Candidates are returned in the order they were first seen.
     pub fn candidates(&self) -> Vec<T> {
-        return self.candidates.iter().map(|(k, _v)| k.clone()).collect();
+        return self.candidate_ids.clone();
+    }
+
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+        let order = sorted_order(&self.candidate_ids, declared);
+        self.running_total = self.running_total.reordered(&order);
+        self.candidate_ids = order.iter().map(|old| self.candidate_ids[*old].clone()).collect();
+        for (id, candidate) in self.candidate_ids.iter().enumerate() {
+            self.candidates.insert(candidate.clone(), id);
+        }
     }
 
     // Ensure that candidates are in our list of candidates, and return an internal numeric representation of the same
     fn mapped_candidates(&mut self, selection: &[T]) -> Vec<usize> {
         let mut mapped = Vec::<usize>::new();
         for selected in selection.iter() {
-            if self.candidates.contains_key(&selected) {
-                mapped.push(*self.candidates.get(&selected).unwrap()); // Safe to unwrap here since we just checked it one-line above with contains_key()
+            if self.candidates.contains_key(selected) {
+                mapped.push(*self.candidates.get(selected).unwrap()); // Safe to unwrap here since we just checked it one-line above with contains_key()
             } else {
                 let len = self.candidates.len();
                 self.candidates.insert(selected.clone(), len);
+                self.candidate_ids.push(selected.clone());
                 mapped.push(len);
             }
         }
+        self.running_total.grow(self.candidates.len());
         return mapped;
     }
+
+    // Forget the candidates seen after the first `seen` candidates, after a vote that mapped them could not be counted.
+    fn forget_candidates(&mut self, seen: usize) {
+        for candidate in self.candidate_ids.drain(seen..) {
+            self.candidates.remove(&candidate);
+        }
+        self.running_total.truncate(seen);
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T, C> CondorcetTally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                       // Candidate type
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send + Sync, // Count type
+{
+    /// Create a new `CondorcetTally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and counted into per-thread tallies, which are then merged.
+    /// An error is returned if any vote contains duplicate candidates.
+    /// Requires the `parallel` feature.
+    pub fn from_par_iter<I>(num_winners: u32, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let mut tally = Self::new(num_winners);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    // Create an empty tally that checks votes against the same registry, to count part of the votes separately.
+    pub(crate) fn fork(&self) -> Self {
+        return match &self.registry {
+            Some(registry) => Self::with_candidates(0, registry.clone()),
+            None => Self::new(0),
+        };
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// Votes are split across threads and counted into per-thread tallies, which are then merged into this tally.
+    /// If any vote contains duplicate candidates, an error is returned and this tally is left unchanged.
+    ///
+    /// Candidates are assigned ids in the order they are seen, so the result is the same as adding the votes sequentially.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let tally = votes
+            .into_par_iter()
+            .try_fold(
+                || self.fork(),
+                |mut tally, vote| {
+                    tally.add(vote)?;
+                    Ok(tally)
+                },
+            )
+            .try_reduce(
+                || self.fork(),
+                |mut tally, other| {
+                    tally.merge_counts(other);
+                    Ok(tally)
+                },
+            )?;
+        self.merge_counts(tally);
+        Ok(())
+    }
+}
+
+// Tarjan's strongly connected components algorithm, over a graph of `num_nodes` nodes where `has_edge(v, w)` checks for an edge
+// leading from `v` to `w`. Components are returned in reverse topological order.
+//
+// Only `alloc` is needed, so that smith-sets can be computed without petgraph. Neighbours are visited from the highest identifier
+// down, the same order petgraph's `tarjan_scc` visits them in on a graph from `build_graph()`, so components come out in the same order.
+fn tarjan_scc<F: Fn(usize, usize) -> bool>(num_nodes: usize, has_edge: F) -> Vec<Vec<usize>> {
+    struct Tarjan<F> {
+        has_edge: F,
+        index: usize,
+        indexes: Vec<Option<usize>>,
+        lowlinks: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl<F: Fn(usize, usize) -> bool> Tarjan<F> {
+        fn visit(&mut self, v: usize) {
+            self.indexes[v] = Some(self.index);
+            self.lowlinks[v] = self.index;
+            self.on_stack[v] = true;
+            self.stack.push(v);
+            self.index += 1;
+
+            for w in (0..self.indexes.len()).rev() {
+                if w == v || !(self.has_edge)(v, w) {
+                    continue;
+                }
+                match self.indexes[w] {
+                    None => {
+                        self.visit(w);
+                        self.lowlinks[v] = core::cmp::min(self.lowlinks[v], self.lowlinks[w]);
+                    }
+                    Some(w_index) => {
+                        // Successor w is on the stack and hence in the current component
+                        if self.on_stack[w] {
+                            self.lowlinks[v] = core::cmp::min(self.lowlinks[v], w_index);
+                        }
+                    }
+                }
+            }
+
+            // If v is a root node, pop the stack and generate a component
+            if self.indexes[v] == Some(self.lowlinks[v]) {
+                let mut component = Vec::new();
+                loop {
+                    let w = self.stack.pop().unwrap(); // Safe to unwrap here since v is still on the stack.
+                    self.on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        has_edge: has_edge,
+        index: 0,
+        indexes: vec![None; num_nodes],
+        lowlinks: vec![0; num_nodes],
+        on_stack: vec![false; num_nodes],
+        stack: Vec::new(),
+        components: Vec::new(),
+    };
+    for node in 0..num_nodes {
+        if tarjan.indexes[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+    return tarjan.components;
 }
 
 #[cfg(test)]
@@ -401,6 +1050,105 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn condorcet_ranked_ties() -> Result<(), TallyError> {
+        let mut tally = CondorcetTally::<&str, f64>::new(1);
+        tally.add_ranked(RankedBallot::from(vec![vec!["Alice", "Bob"], vec!["Carol"]]))?;
+        tally.add_ranked_weighted(RankedBallot::from(vec!["Bob", "Alice", "Carol"]), 2.0)?;
+
+        let totals = tally.totals();
+        for ((candidate1, candidate2), count) in totals.iter() {
+            match (*candidate1, *candidate2) {
+                ("Alice", "Bob") => assert_eq!(*count, 0.5),
+                ("Bob", "Alice") => assert_eq!(*count, 2.5),
+                ("Alice", "Carol") => assert_eq!(*count, 3.0),
+                ("Bob", "Carol") => assert_eq!(*count, 3.0),
+                _ => panic!("Invalid pairwise total"),
+            }
+        }
+        assert_eq!(tally.winners().into_unranked(), vec!["Bob"]);
+
+        // Ties require a fractional count type
+        let mut tally = DefaultCondorcetTally::new(1);
+        assert!(tally.add_ranked(RankedBallot::from(vec![vec!["Alice", "Bob"]])).is_err());
+        assert!(tally.add_ranked(RankedBallot::from(vec![vec!["Alice"], vec!["Alice"]])).is_err());
+        tally.add_ranked(RankedBallot::from(vec!["Alice", "Bob"]))?;
+        assert_eq!(tally.totals(), vec![(("Alice", "Bob"), 1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn condorcet_aggregated() -> Result<(), TallyError> {
+        // Candidates beyond the expected capacity grow the pairwise matrix.
+        let mut aggregated = DefaultCondorcetTally::with_capacity(1, 2);
+        aggregated.add_aggregated(&[
+            (vec!["Memphis", "Nashville", "Chattanooga", "Knoxville"], 42),
+            (vec!["Nashville", "Chattanooga", "Knoxville", "Memphis"], 26),
+            (vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"], 15),
+            (vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"], 17),
+        ])?;
+
+        let mut tally = DefaultCondorcetTally::new(1);
+        for _ in 0..42 {
+            tally.add(vec!["Memphis", "Nashville", "Chattanooga", "Knoxville"])?;
+        }
+        for _ in 0..26 {
+            tally.add(vec!["Nashville", "Chattanooga", "Knoxville", "Memphis"])?;
+        }
+        for _ in 0..15 {
+            tally.add(vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"])?;
+        }
+        for _ in 0..17 {
+            tally.add(vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"])?;
+        }
+
+        assert_eq!(aggregated.totals(), tally.totals());
+        assert_eq!(aggregated.ranked(), tally.ranked());
+
+        // Invalid votes are rejected before anything is added.
+        assert!(aggregated.add_aggregated(&[(vec!["Alice"], 1), (vec!["Bob", "Bob"], 1)]).is_err());
+        assert_eq!(aggregated.candidates().len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn condorcet_merge() -> Result<(), TallyError> {
+        let precinct = |votes: Vec<(Vec<&'static str>, u64)>| -> Result<DefaultCondorcetTally<&'static str>, TallyError> {
+            let mut tally = DefaultCondorcetTally::new(1);
+            tally.add_aggregated(&votes)?;
+            Ok(tally)
+        };
+        let precinct_1 = || precinct(vec![(vec!["Memphis", "Nashville", "Chattanooga", "Knoxville"], 42)]);
+        let precinct_2 = || precinct(vec![(vec!["Nashville", "Chattanooga", "Knoxville", "Memphis"], 26)]);
+        let precinct_3 = || {
+            precinct(vec![
+                (vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"], 17),
+                (vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"], 15),
+            ])
+        };
+
+        let mut forward = precinct_1()?;
+        forward.merge(precinct_2()?);
+        forward.merge(precinct_3()?);
+
+        let mut backward = precinct_3()?;
+        let mut county = precinct_2()?;
+        county.merge(precinct_1()?);
+        backward.merge(county);
+
+        let forward_totals: HashSet<_> = HashSet::from_iter(forward.totals().iter().cloned());
+        let backward_totals: HashSet<_> = HashSet::from_iter(backward.totals().iter().cloned());
+        assert_eq!(forward_totals, backward_totals);
+        assert!(forward_totals.contains(&(("Nashville", "Memphis"), 58)));
+        assert_eq!(forward.ranked(), backward.ranked());
+        assert_eq!(forward.ranked()[0], ("Nashville", 0));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
     #[test]
     fn condorcet_graph() -> Result<(), TallyError> {
         // From: https://arxiv.org/pdf/1804.02973.pdf
@@ -432,4 +1180,163 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn condorcet_tarjan_scc() -> Result<(), TallyError> {
+        // Smith-sets, including unconnected ones, come out in the same order as petgraph's tarjan_scc.
+        let mut tally = DefaultCondorcetTally::new(1);
+        tally.add(vec!["a", "b"])?;
+        tally.add(vec!["c", "d", "e"])?;
+        tally.add(vec!["e", "c", "d"])?;
+        tally.add(vec!["d", "e", "c"])?;
+        tally.add(vec!["f"])?;
+        tally.add(vec!["g", "b"])?;
+
+        let graph = tally.build_graph();
+        let expected: Vec<Vec<usize>> = petgraph::algo::tarjan_scc(&graph)
+            .iter()
+            .map(|scc| scc.iter().map(|node| node.index()).collect())
+            .collect();
+        assert_eq!(tarjan_scc(7, |loser, winner| tally.has_edge(loser, winner)), expected);
+        assert_eq!(expected.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn condorcet_candidates() -> Result<(), TallyError> {
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos", "Dave"]).withdrawn(vec!["Dave"]);
+        let mut tally = DefaultCondorcetTally::with_candidates(1, candidates);
+        assert_eq!(tally.candidates(), vec!["Alice", "Bob", "Carlos"]);
+
+        // Ranked candidates are preferred over every unranked standing candidate.
+        tally.add(vec!["Bob"])?;
+        tally.add_ranked(RankedBallot::from(vec!["Dave", "Alice"]))?;
+        let mut ballot_box = BallotBox::new();
+        ballot_box.add(vec!["Carlos", "Dave", "Bob"])?;
+        tally.add_ballot_box(&ballot_box)?;
+        assert_eq!(
+            tally.totals(),
+            vec![
+                (("Alice", "Bob"), 1),
+                (("Alice", "Carlos"), 1),
+                (("Bob", "Alice"), 2),
+                (("Bob", "Carlos"), 1),
+                (("Carlos", "Alice"), 1),
+                (("Carlos", "Bob"), 1),
+            ]
+        );
+
+        // Votes for unknown candidates are rejected, and the tally is left unchanged.
+        assert!(tally.add(vec!["Erin", "Alice"]).is_err());
+        ballot_box.add(vec!["Erin"])?;
+        assert!(tally.add_ballot_box(&ballot_box).is_err());
+        assert_eq!(tally.candidates(), vec!["Alice", "Bob", "Carlos"]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn condorcet_serde() -> Result<(), TallyError> {
+        let mut tally = DefaultCondorcetTally::new(1);
+        tally.add_weighted(vec!["Alice", "Bob"], 3)?;
+        tally.add(vec!["Bob", "Alice"])?;
+        tally.add(vec!["Carlos"])?;
+
+        let json = serde_json::to_string(&tally).unwrap();
+        assert_eq!(
+            json,
+            r#"{"running_total":{"size":3,"counts":[0,3,0,1,0,0,0,0,0]},"num_winners":1,"candidate_ids":["Alice","Bob","Carlos"]}"#
+        );
+
+        let mut resumed: DefaultCondorcetTally<&str> = serde_json::from_str(&json).unwrap();
+        resumed.add(vec!["Alice", "Dave"])?;
+        tally.add(vec!["Alice", "Dave"])?;
+        assert_eq!(resumed.candidates(), vec!["Alice", "Bob", "Carlos", "Dave"]);
+        assert_eq!(resumed.totals(), tally.totals());
+        assert_eq!(resumed.ranked(), tally.ranked());
+
+        // The matrix must match the number of candidates.
+        let invalid = r#"{"running_total":{"size":1,"counts":[0]},"num_winners":1,"candidate_ids":["Alice","Bob"]}"#;
+        assert!(serde_json::from_str::<DefaultCondorcetTally<String>>(invalid).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn condorcet_parallel() -> Result<(), TallyError> {
+        let mut votes = Vec::new();
+        for i in 0..10_000 {
+            match i % 4 {
+                0 => votes.push(vec!["Memphis", "Nashville", "Chattanooga", "Knoxville"]),
+                1 => votes.push(vec!["Nashville", "Chattanooga", "Knoxville", "Memphis"]),
+                2 => votes.push(vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"]),
+                _ => votes.push(vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"]),
+            }
+        }
+
+        let mut tally = DefaultCondorcetTally::new(1);
+        for vote in votes.iter() {
+            tally.add_ref(vote)?;
+        }
+
+        let par_tally = DefaultCondorcetTally::from_par_iter(1, votes)?;
+        assert_eq!(par_tally.candidates(), tally.candidates());
+        assert_eq!(par_tally.totals(), tally.totals());
+        assert_eq!(par_tally.ranked(), tally.ranked());
+
+        assert!(DefaultCondorcetTally::from_par_iter(1, vec![vec!["Alice", "Alice"]]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn condorcet_overflow() -> Result<(), TallyError> {
+        let mut tally = CondorcetTally::<&str, u16>::new(1);
+        tally.add_weighted(vec!["Alice", "Bob"], 60_000)?;
+        assert!(matches!(tally.add_weighted(vec!["Alice", "Bob"], 10_000), Err(TallyError::CountOverflow)));
+
+        // Votes that don't overflow can still be counted.
+        tally.add_weighted(vec!["Bob", "Alice"], 10_000)?;
+        assert_eq!(tally.winners().into_unranked(), vec!["Alice"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn condorcet_overflow_unchanged() -> Result<(), TallyError> {
+        // Carlos over Alice overflows after Carlos over Bob has been counted.
+        let mut tally = CondorcetTally::<&str, u16>::new(1);
+        tally.add_weighted(vec!["Alice", "Bob"], 60_000)?;
+        tally.add_weighted(vec!["Carlos", "Alice"], 60_000)?;
+        let totals = tally.totals();
+        assert!(matches!(tally.add_weighted(vec!["Carlos", "Bob", "Alice", "Dave"], 10_000), Err(TallyError::CountOverflow)));
+        assert_eq!(tally.totals(), totals);
+        assert_eq!(tally.candidates(), vec!["Alice", "Bob", "Carlos"]);
+
+        let vote = RankedBallot::from(vec![vec!["Carlos"], vec!["Bob"], vec!["Alice"], vec!["Dave"]]);
+        assert!(matches!(tally.add_ranked_weighted(vote, 10_000), Err(TallyError::CountOverflow)));
+        assert_eq!(tally.totals(), totals);
+
+        let votes = vec![(vec!["Bob", "Alice"], 10_000), (vec!["Dave", "Carlos", "Alice"], 10_000)];
+        assert!(matches!(tally.add_aggregated(&votes), Err(TallyError::CountOverflow)));
+        assert_eq!(tally.totals(), totals);
+        assert_eq!(tally.candidates(), vec!["Alice", "Bob", "Carlos"]);
+
+        let mut ballot_box = BallotBox::new();
+        ballot_box.add_weighted(vec!["Bob", "Alice"], 10_000)?;
+        ballot_box.add_weighted(vec!["Dave", "Carlos", "Alice"], 10_000)?;
+        assert!(matches!(tally.add_ballot_box(&ballot_box), Err(TallyError::CountOverflow)));
+        assert_eq!(tally.totals(), totals);
+        assert_eq!(tally.candidates(), vec!["Alice", "Bob", "Carlos"]);
+
+        // Dave can still be counted once seen by a vote that doesn't overflow.
+        tally.add(vec!["Dave", "Bob"])?;
+        assert_eq!(tally.candidates(), vec!["Alice", "Bob", "Carlos", "Dave"]);
+
+        Ok(())
+    }
 }
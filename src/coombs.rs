@@ -0,0 +1,434 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::check_duplicate;
+use super::result::RankedWinners;
+use super::runoff;
+use super::runoff::Elimination;
+use super::runoff::Round;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
+use super::sorted_order;
+use super::TallyError;
+use super::{add_count, COUNT_OVERFLOW_PANIC, C_FROM_PANIC};
+
+/// A coombs tally using `u64` integers to count votes.
+/// `DefaultCoombsTally` is generally preferred over `CoombsTally`.
+/// Since this is an alias, refer to [`CoombsTally`](struct.CoombsTally.html) for method documentation.
+///
+/// # Example
+/// ```
+///    use tallystick::coombs::DefaultCoombsTally;
+///
+///    let mut tally = DefaultCoombsTally::new(1);
+///    tally.add_weighted(vec!["Alice", "Bob", "Carlos"], 4).unwrap();
+///    tally.add_weighted(vec!["Bob", "Alice", "Carlos"], 2).unwrap();
+///    tally.add_weighted(vec!["Carlos", "Bob", "Alice"], 3).unwrap();
+///
+///    // Carlos is ranked last by the most voters, and his votes go to Bob.
+///    let winners = tally.winners().into_unranked();
+///    assert_eq!(winners[0], "Bob");
+/// ```
+pub type DefaultCoombsTally<T> = CoombsTally<T, u64>;
+
+/// A generic coombs tally.
+///
+/// Each round, every vote is counted for its most preferred continuing candidate. If a candidate has more than half of
+/// these first preference votes they win. Otherwise the candidate ranked last by the most votes is eliminated, and the
+/// count continues with the next round. If several candidates tie for the most last place votes, they are all
+/// eliminated, unless they are the only continuing candidates, in which case the count stops and they are ranked by
+/// their first preference votes.
+///
+/// A vote that doesn't rank every continuing candidate splits its last place vote evenly between the continuing candidates
+/// it leaves unranked.
+///
+/// Generics:
+/// - `T`: The candidate type.
+/// - `C`: The count type. `u64` is recommended, but can be modified to use a different type for counting votes (eg `f64` for fractional vote weights).
+///
+/// # Example
+/// ```
+///    use tallystick::coombs::CoombsTally;
+///
+///    let mut tally = CoombsTally::<&str, f64>::new(1);
+///    tally.add_weighted(vec!["Alice", "Bob", "Carlos"], 4.5).unwrap();
+///    tally.add_weighted(vec!["Bob", "Carlos", "Alice"], 2.0).unwrap();
+///    tally.add_weighted(vec!["Carlos", "Bob", "Alice"], 3.0).unwrap();
+///
+///    // Alice is ranked last by the most voters, and her votes go to Bob, who has a majority.
+///    let rounds = tally.rounds();
+///    assert_eq!(rounds[0].eliminated, vec!["Alice"]);
+///    assert_eq!(rounds[1].totals, vec![("Bob", 6.5), ("Carlos", 3.0)]);
+///    assert_eq!(tally.winners().into_unranked(), vec!["Bob"]);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CoombsTally<T, C = u64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    ballots: BallotBox<T, C>, // Identical votes are stored once, with their total weight
+    num_winners: u32,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    registry: Option<Candidates<T>>, // The declared candidates, if any
+}
+
+impl<T, C> CoombsTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new `CoombsTally` with the given number of winners.
+    ///
+    /// Candidates left in the final round are ranked by their first preference votes, followed by the eliminated candidates
+    /// in reverse order of elimination. If there is a tie, the number of winners might be more than `num_winners`.
+    pub fn new(num_winners: u32) -> Self {
+        return CoombsTally {
+            ballots: BallotBox::new(),
+            num_winners: num_winners,
+            registry: None,
+        };
+    }
+
+    /// Create a new `CoombsTally` with the given number of winners, and number of expected candidates.
+    pub fn with_capacity(num_winners: u32, expected_candidates: usize) -> Self {
+        return CoombsTally {
+            ballots: BallotBox::with_capacity(expected_candidates, expected_candidates),
+            num_winners: num_winners,
+            registry: None,
+        };
+    }
+
+    /// Create a new `CoombsTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// Every standing candidate takes part in the count, even if they are not ranked on any vote.
+    /// See [`Candidates`](../struct.Candidates.html) for details.
+    pub fn with_candidates(num_winners: u32, candidates: Candidates<T>) -> Self {
+        let mut tally = Self::with_capacity(num_winners, candidates.standing().len());
+        tally.ballots.declare(candidates.standing());
+        tally.registry = Some(candidates);
+        return tally;
+    }
+
+    /// Add a new vote
+    ///
+    /// Votes are represented as a vector of ranked candidates, ordered by preference.
+    /// An error will only be returned if `vote` contains duplicate candidates.
+    pub fn add(&mut self, vote: Vec<T>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&vote, C::one())
+    }
+
+    /// Add a new vote by reference
+    pub fn add_ref(&mut self, vote: &[T]) -> Result<(), TallyError> {
+        self.add_weighted_ref(vote, C::one())
+    }
+
+    /// Add a weighted vote.
+    pub fn add_weighted(&mut self, vote: Vec<T>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&vote, weight)
+    }
+
+    /// Add a weighted vote by reference
+    pub fn add_weighted_ref(&mut self, vote: &[T], weight: C) -> Result<(), TallyError> {
+        match &self.registry {
+            Some(registry) => {
+                check_duplicate(vote)?;
+                self.ballots.add_weighted_ref(&registry.filter(vote)?, weight)
+            }
+            None => self.ballots.add_weighted_ref(vote, weight),
+        }
+    }
+
+    /// Add all votes in a `BallotBox`.
+    ///
+    /// Tied candidates are not supported, so an error is returned if the ballot box contains a vote with a tie.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+        if ballot_box.has_ties() {
+            return Err(TallyError::TiedVoteNotSupported);
+        }
+        match &self.registry {
+            Some(registry) => {
+                registry.check_all(ballot_box.candidate_ids())?;
+                for (vote, weight) in ballot_box.iter() {
+                    self.ballots.add_ranked_weighted_ref(&registry.filter_ranked(&vote)?, weight)?;
+                }
+            }
+            None => self.ballots.merge(ballot_box.clone()),
+        }
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Tallies can be merged in any order to get the same result. After merging, candidates are sorted as if by
+    /// [`sort_candidates()`](#method.sort_candidates), so their order doesn't depend on merge order either.
+    /// The number of winners for `other` is ignored.
+    ///
+    /// # Panics
+    /// This method will panic if the merged total weight of all votes overflows the count type.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.ballots.merge(other.ballots);
+        self.sort_candidates();
+    }
+
+    /// Get a list of all candidates seen by this tally.
+    /// Candidates are returned in the order they were first seen.
+    pub fn candidates(&self) -> Vec<T> {
+        return self.ballots.candidates();
+    }
+
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+        let order = sorted_order(self.ballots.candidate_ids(), declared);
+        self.ballots.reorder(&order);
+    }
+
+    /// Get a ranked list of winners. Winners with the same rank are tied, and are listed in the order they were first seen.
+    /// The number of winners might be greater than the requested `num_winners` if there is a tie.
+    pub fn winners(&self) -> RankedWinners<T> {
+        return runoff::winners(self.rounds(), self.num_winners);
+    }
+
+    /// Get the rounds of the count, in order.
+    ///
+    /// Each round lists the first preference votes of the continuing candidates and the candidates eliminated at the end of the round.
+    pub fn rounds(&self) -> Vec<Round<T, C>> {
+        return runoff::count(self.ballots.candidate_ids(), self);
+    }
+}
+
+impl<T, C> Elimination<C> for CoombsTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    fn count(&self, continuing: &[bool]) -> Vec<C> {
+        let mut totals = vec![C::zero(); continuing.len()];
+        for (vote, weight) in self.ballots.interned() {
+            if let Some(first) = vote.candidates().find(|candidate| continuing[**candidate]) {
+                totals[*first] += *weight;
+            }
+        }
+        return totals;
+    }
+
+    fn eliminate(&self, continuing: &[bool], _totals: &[C]) -> Vec<usize> {
+        let num_continuing = continuing.iter().filter(|continuing| **continuing).count();
+        let unranked: Vec<usize> = self
+            .ballots
+            .interned()
+            .iter()
+            .map(|(vote, _)| num_continuing - vote.candidates().filter(|candidate| continuing[**candidate]).count())
+            .collect();
+
+        // Continuing candidates left off a vote share its last place evenly. With an integer count type, every vote is
+        // scaled by a common multiple of the number of candidates sharing last place, so that each share is a whole number.
+        let scale = if C::fraction() {
+            C::one()
+        } else {
+            let multiple = unranked.iter().filter(|unranked| **unranked > 1).fold(1, |multiple, unranked| {
+                (multiple / gcd(multiple, *unranked)).checked_mul(*unranked).expect(COUNT_OVERFLOW_PANIC)
+            });
+            C::from_usize(multiple).expect(COUNT_OVERFLOW_PANIC)
+        };
+
+        let mut last_place = vec![C::zero(); continuing.len()];
+        for ((vote, weight), unranked) in self.ballots.interned().iter().zip(unranked) {
+            let weight = weight.checked_mul(scale).expect(COUNT_OVERFLOW_PANIC);
+            if unranked > 0 {
+                let share = weight / C::from_usize(unranked).expect(C_FROM_PANIC);
+                for (candidate, _) in continuing.iter().enumerate().filter(|(_, continuing)| **continuing) {
+                    if !vote.candidates().any(|ranked| *ranked == candidate) {
+                        add_count(&mut last_place[candidate], share).expect(COUNT_OVERFLOW_PANIC);
+                    }
+                }
+            } else if let Some(last) = vote.candidates().filter(|candidate| continuing[**candidate]).last() {
+                add_count(&mut last_place[*last], weight).expect(COUNT_OVERFLOW_PANIC);
+            }
+        }
+
+        let mut eliminated = Vec::new();
+        let mut most = C::zero();
+        for (candidate, votes) in last_place.into_iter().enumerate().filter(|(candidate, _)| continuing[*candidate]) {
+            if eliminated.is_empty() || votes > most {
+                eliminated = vec![candidate];
+                most = votes;
+            } else if votes == most {
+                eliminated.push(candidate);
+            }
+        }
+        return eliminated;
+    }
+
+    // A candidate is elected once they have a majority of the first preference votes that are still counted.
+    fn elected(&self, votes: C, active: C) -> bool {
+        return votes > active - votes;
+    }
+}
+
+// Get the greatest common divisor of two numbers.
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        return a;
+    }
+    return gcd(b, a % b);
+}
+
+#[cfg(feature = "parallel")]
+impl<T, C> CoombsTally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send, // Vote count type
+{
+    /// Create a new `CoombsTally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and collected per-thread, then merged.
+    /// An error is returned if any vote contains duplicate candidates.
+    /// Requires the `parallel` feature.
+    pub fn from_par_iter<I>(num_winners: u32, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let mut tally = Self::new(num_winners);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// See [`BallotBox::par_add_all()`](../struct.BallotBox.html#method.par_add_all) for details.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        self.ballots.par_add_filtered(votes, self.registry.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankedBallot;
+
+    #[test]
+    fn coombs_tennessee() -> Result<(), TallyError> {
+        // From: https://en.wikipedia.org/wiki/Coombs%27_method
+        let mut tally = DefaultCoombsTally::new(4);
+        tally.add_weighted(vec!["Memphis", "Nashville", "Chattanooga", "Knoxville"], 42)?;
+        tally.add_weighted(vec!["Nashville", "Chattanooga", "Knoxville", "Memphis"], 26)?;
+        tally.add_weighted(vec!["Chattanooga", "Knoxville", "Nashville", "Memphis"], 15)?;
+        tally.add_weighted(vec!["Knoxville", "Chattanooga", "Nashville", "Memphis"], 17)?;
+
+        // Memphis is ranked last by 58 voters, and their first preferences go to Nashville.
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].eliminated, vec!["Memphis"]);
+        assert_eq!(rounds[1].totals, vec![("Nashville", 68), ("Chattanooga", 15), ("Knoxville", 17)]);
+        assert_eq!(rounds[1].eliminated, Vec::<&str>::new());
+        assert_eq!(rounds[1].elected, vec!["Nashville"]);
+
+        let winners = tally.winners().into_vec();
+        assert_eq!(winners, vec![("Nashville", 0), ("Knoxville", 1), ("Chattanooga", 2), ("Memphis", 3)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn coombs_differs_from_instant_runoff() -> Result<(), TallyError> {
+        // Carlos has the fewest first preferences, but is elected since no voter ranks him last.
+        let mut tally = DefaultCoombsTally::new(1);
+        tally.add_weighted(vec!["Alice", "Carlos", "Bob", "Dave"], 5)?;
+        tally.add_weighted(vec!["Bob", "Carlos", "Dave", "Alice"], 4)?;
+        tally.add_weighted(vec!["Dave", "Carlos", "Bob", "Alice"], 3)?;
+        tally.add_weighted(vec!["Carlos", "Dave", "Bob", "Alice"], 2)?;
+
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 3);
+        assert_eq!(rounds[0].eliminated, vec!["Alice"]);
+        assert_eq!(rounds[1].totals, vec![("Carlos", 7), ("Bob", 4), ("Dave", 3)]);
+        assert_eq!(rounds[1].eliminated, vec!["Dave"]);
+        assert_eq!(rounds[2].totals, vec![("Carlos", 10), ("Bob", 4)]);
+        assert_eq!(tally.winners().into_unranked(), vec!["Carlos"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn coombs_ties() -> Result<(), TallyError> {
+        // A cycle, where every candidate is ranked last once.
+        let mut tally = DefaultCoombsTally::new(1);
+        tally.add(vec!["Alice", "Bob", "Carlos"])?;
+        tally.add(vec!["Bob", "Carlos", "Alice"])?;
+        tally.add(vec!["Carlos", "Alice", "Bob"])?;
+        assert_eq!(tally.rounds().len(), 1);
+        assert_eq!(tally.winners().into_unranked(), vec!["Alice", "Bob", "Carlos"]);
+
+        // Votes for Alice leave Bob and Carlos unranked, so they tie for the most last place votes and are both eliminated.
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos"]);
+        let mut tally = DefaultCoombsTally::with_candidates(2, candidates);
+        tally.add_weighted(vec!["Alice"], 3)?;
+        tally.add_weighted(vec!["Bob", "Alice", "Carlos"], 2)?;
+        tally.add_weighted(vec!["Carlos", "Alice", "Bob"], 2)?;
+        assert!(tally.add(vec!["Dave"]).is_err());
+
+        let rounds = tally.rounds();
+        assert_eq!(rounds[0].eliminated, vec!["Bob", "Carlos"]);
+        assert_eq!(rounds[1].totals, vec![("Alice", 7)]);
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Bob", 1), ("Carlos", 1)]);
+
+        // Votes with tied candidates can't be counted.
+        let mut ballot_box = BallotBox::<&str, u64>::new();
+        ballot_box.add_ranked(RankedBallot::from(vec![vec!["Alice", "Bob"], vec!["Carlos"]]))?;
+        let mut tally = DefaultCoombsTally::new(1);
+        assert!(matches!(tally.add_ballot_box(&ballot_box), Err(TallyError::TiedVoteNotSupported)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn coombs_truncated() -> Result<(), TallyError> {
+        // Last place votes, with truncated votes split evenly between the candidates they leave unranked:
+        // Alice: 8/2 = 4, Bob: 3/2 + 8/2 = 5.5, Carlos: 3/2 + 7 = 8.5.
+        // Counting a full last place vote for every unranked candidate would give Bob 11, Carlos 10 and Alice 8,
+        // eliminating Bob and electing Alice instead.
+        let mut tally = DefaultCoombsTally::new(1);
+        tally.add_weighted(vec!["Alice"], 3)?;
+        tally.add_weighted(vec!["Bob", "Alice"], 7)?;
+        tally.add_weighted(vec!["Carlos"], 8)?;
+
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].totals, vec![("Alice", 3), ("Bob", 7), ("Carlos", 8)]);
+        assert_eq!(rounds[0].eliminated, vec!["Carlos"]);
+        assert_eq!(rounds[1].totals, vec![("Alice", 3), ("Bob", 7)]);
+        assert_eq!(rounds[1].elected, vec!["Bob"]);
+
+        // Fractional count types give the same result.
+        let mut tally = CoombsTally::<&str, f64>::new(1);
+        tally.add_weighted(vec!["Alice"], 3.0)?;
+        tally.add_weighted(vec!["Bob", "Alice"], 7.0)?;
+        tally.add_weighted(vec!["Carlos"], 8.0)?;
+        assert_eq!(tally.rounds()[0].eliminated, vec!["Carlos"]);
+        assert_eq!(tally.winners().into_unranked(), vec!["Bob"]);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+// The failure derive expands to impls inside a const block, which newer compilers warn about.
+#![allow(non_local_definitions)]
+
+use alloc::string::String;
 use failure::Fail;
 
 /// TallyError enum contains a list of all errors that may occur during a tally.
@@ -6,4 +10,52 @@ pub enum TallyError {
   #[fail(display = "tallystick: vote contains duplicate candidates")]
   /// A vote contains duplicate candidates.
   VoteHasDuplicateCandidates,
+
+  #[fail(display = "tallystick: vote contains tied candidates, which requires a fractional count type")]
+  /// A vote contains tied candidates, but the tally uses an integer count type that cannot split points between them.
+  /// Use a float or rational count type to tally votes with ties.
+  TiedVoteRequiresFractionalCount,
+
+  #[fail(display = "tallystick: vote contains tied candidates, which this voting method does not support")]
+  /// A vote contains tied candidates, but the voting method has no rule for counting them.
+  TiedVoteNotSupported,
+
+  #[fail(display = "tallystick: vote marks more candidates than allowed")]
+  /// A vote marks more candidates than allowed, or marks more than one candidate at the same rank.
+  Overvote,
+
+  #[fail(display = "tallystick: vote contains an unknown candidate")]
+  /// A vote contains a candidate that is not standing in the election.
+  UnknownCandidate,
+
+  #[fail(display = "tallystick: vote skips a rank")]
+  /// A ranked vote leaves a rank empty before a later rank that is marked.
+  SkippedRank,
+
+  #[fail(display = "tallystick: vote uses more ranks than allowed")]
+  /// A ranked vote uses more ranks than allowed.
+  TooManyRanks,
+
+  #[fail(display = "tallystick: vote contains a score outside the allowed range")]
+  /// A score vote gives a candidate a score that is lower or higher than allowed.
+  ScoreOutOfRange,
+
+  #[fail(display = "tallystick: vote count overflowed the count type")]
+  /// Counting a vote would overflow the count type, for example a `u32` count going past `u32::MAX`.
+  /// The vote might have been partially counted, so the tally should be discarded. Use a larger count type and count again.
+  CountOverflow,
+
+  #[fail(display = "tallystick: serialized tally state is inconsistent")]
+  /// A serialized tally or ballot box could not be restored because its state is inconsistent.
+  /// For example, it lists the same candidate twice, or a vote refers to a candidate that doesn't exist.
+  InvalidState,
+
+  #[fail(display = "tallystick: line {}: {}", line, message)]
+  /// A ballot file could not be parsed.
+  ParseError {
+    /// The 1-based line number where the problem was found, or 0 if the problem isn't on a particular line.
+    line: usize,
+    /// A description of the problem.
+    message: String,
+  },
 }
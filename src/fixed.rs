@@ -0,0 +1,398 @@
+use alloc::format;
+#[cfg(feature = "serde")]
+use alloc::string::String;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Rem, Sub, SubAssign};
+use core::str::FromStr;
+use num_traits::float::FloatCore;
+use num_traits::{FromPrimitive, Num, NumCast, One, ToPrimitive, Zero};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Numeric;
+use super::TallyError;
+
+/// A fixed-point decimal count type with `P` decimal places.
+///
+/// Some election rules mandate the precision of a count. Scottish STV truncates transfer values and transferred votes to
+/// 5 decimal places, and other STV rules use 4 or 6. `FixedDecimal` stores a value as a whole number of `10^-P` units,
+/// and after every multiplication and division it truncates (rounds toward zero) any digits past the `P`th decimal place,
+/// so that a tally matches the official count sheets digit for digit.
+///
+/// Addition and subtraction are exact. Values range up to about ±9.2 × 10^(18 - P). Overflowing addition and subtraction
+/// panics in debug builds like the primitive integer types do, and overflowing multiplication and division always panics.
+/// `P` can be at most 18.
+///
+/// Converting from a float rounds to the nearest `10^-P`, since most decimals can't be represented exactly as floats.
+///
+/// # Example
+/// ```
+///    use tallystick::stv::Tally;
+///    use tallystick::FixedDecimal;
+///    use tallystick::Quota;
+///
+///    let third = FixedDecimal::<5>::from(1) / FixedDecimal::from(3);
+///    assert_eq!(third.to_string(), "0.33333");
+///
+///    // Count an STV election to 5 decimal places.
+///    let mut tally = Tally::<&str, FixedDecimal<5>>::new(2, Quota::Droop);
+///    tally.add(vec!["Alice", "Bob"]).unwrap();
+///    tally.add_weighted(vec!["Carlos"], "2.5".parse().unwrap()).unwrap();
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedDecimal<const P: u32>(i64);
+
+impl<const P: u32> FixedDecimal<P> {
+    /// The number of units in one, `10^P`.
+    const SCALE: i64 = 10i64.pow(P);
+
+    /// Create a value from a whole number of `10^-P` units. For example, `FixedDecimal::<2>::from_units(150)` is `1.50`.
+    pub fn from_units(units: i64) -> Self {
+        return FixedDecimal(units);
+    }
+
+    /// Get the value as a whole number of `10^-P` units. For example, `1.50` with 2 decimal places is `150`.
+    pub fn units(self) -> i64 {
+        return self.0;
+    }
+
+    // Truncate a scaled intermediate result back into units.
+    fn truncate(value: i128) -> Self {
+        return FixedDecimal(i64::try_from(value).expect("tallystick::FixedDecimal multiplication or division overflowed"));
+    }
+}
+
+impl<const P: u32> From<i64> for FixedDecimal<P> {
+    fn from(value: i64) -> Self {
+        return FixedDecimal(value * Self::SCALE);
+    }
+}
+
+impl<const P: u32> fmt::Display for FixedDecimal<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let units = self.0.unsigned_abs();
+        let scale = Self::SCALE as u64;
+        if P == 0 {
+            return write!(f, "{}{}", sign, units);
+        }
+        return write!(f, "{}{}.{:0width$}", sign, units / scale, units % scale, width = P as usize);
+    }
+}
+
+impl<const P: u32> fmt::Debug for FixedDecimal<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Display::fmt(self, f);
+    }
+}
+
+impl<const P: u32> FromStr for FixedDecimal<P> {
+    type Err = TallyError;
+
+    /// Parse a decimal such as `-12.345`. Digits past the `P`th decimal place are truncated.
+    fn from_str(s: &str) -> Result<Self, TallyError> {
+        let invalid = || TallyError::ParseError {
+            line: 0,
+            message: format!("invalid decimal number \"{}\"", s),
+        };
+
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (whole, fraction) = match digits.find('.') {
+            Some(point) => (&digits[..point], &digits[point + 1..]),
+            None => (digits, ""),
+        };
+        if (whole.is_empty() && fraction.is_empty()) || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let mut units: i64 = 0;
+        for b in whole.bytes() {
+            units = units.checked_mul(10).and_then(|u| u.checked_add((b - b'0') as i64)).ok_or_else(invalid)?;
+        }
+        units = units.checked_mul(Self::SCALE).ok_or_else(invalid)?;
+
+        let mut place = Self::SCALE;
+        for b in fraction.bytes().take(P as usize) {
+            place /= 10;
+            units = units.checked_add((b - b'0') as i64 * place).ok_or_else(invalid)?;
+        }
+
+        return Ok(FixedDecimal(if negative { -units } else { units }));
+    }
+}
+
+impl<const P: u32> Add for FixedDecimal<P> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        return FixedDecimal(self.0 + other.0);
+    }
+}
+
+impl<const P: u32> Sub for FixedDecimal<P> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        return FixedDecimal(self.0 - other.0);
+    }
+}
+
+impl<const P: u32> Mul for FixedDecimal<P> {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        return Self::truncate(self.0 as i128 * other.0 as i128 / Self::SCALE as i128);
+    }
+}
+
+impl<const P: u32> Div for FixedDecimal<P> {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        return Self::truncate(self.0 as i128 * Self::SCALE as i128 / other.0 as i128);
+    }
+}
+
+impl<const P: u32> Rem for FixedDecimal<P> {
+    type Output = Self;
+    fn rem(self, other: Self) -> Self {
+        return FixedDecimal(self.0 % other.0);
+    }
+}
+
+impl<const P: u32> Neg for FixedDecimal<P> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        return FixedDecimal(-self.0);
+    }
+}
+
+impl<const P: u32> AddAssign for FixedDecimal<P> {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl<const P: u32> SubAssign for FixedDecimal<P> {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0;
+    }
+}
+
+impl<const P: u32> Zero for FixedDecimal<P> {
+    fn zero() -> Self {
+        return FixedDecimal(0);
+    }
+    fn is_zero(&self) -> bool {
+        return self.0 == 0;
+    }
+}
+
+impl<const P: u32> One for FixedDecimal<P> {
+    fn one() -> Self {
+        return FixedDecimal(Self::SCALE);
+    }
+}
+
+impl<const P: u32> Num for FixedDecimal<P> {
+    type FromStrRadixErr = TallyError;
+
+    /// Only radix 10 is supported.
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, TallyError> {
+        if radix != 10 {
+            return Err(TallyError::ParseError {
+                line: 0,
+                message: format!("decimal numbers can't be parsed with radix {}", radix),
+            });
+        }
+        return s.parse();
+    }
+}
+
+impl<const P: u32> Numeric for FixedDecimal<P> {
+    fn floor(self) -> Self {
+        return FixedDecimal(self.0.div_euclid(Self::SCALE) * Self::SCALE);
+    }
+    fn fraction() -> bool {
+        return P > 0;
+    }
+    fn checked_add(self, other: Self) -> Option<Self> {
+        return self.0.checked_add(other.0).map(FixedDecimal);
+    }
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        let units = self.0 as i128 * other.0 as i128 / Self::SCALE as i128;
+        return i64::try_from(units).ok().map(FixedDecimal);
+    }
+}
+
+impl<const P: u32> ToPrimitive for FixedDecimal<P> {
+    fn to_i64(&self) -> Option<i64> {
+        return Some(self.0 / Self::SCALE);
+    }
+    fn to_u64(&self) -> Option<u64> {
+        return (self.0 / Self::SCALE).to_u64();
+    }
+    fn to_f64(&self) -> Option<f64> {
+        return Some(self.0 as f64 / Self::SCALE as f64);
+    }
+}
+
+impl<const P: u32> FromPrimitive for FixedDecimal<P> {
+    fn from_i64(n: i64) -> Option<Self> {
+        return n.checked_mul(Self::SCALE).map(FixedDecimal);
+    }
+    fn from_u64(n: u64) -> Option<Self> {
+        return i64::from_u64(n).and_then(Self::from_i64);
+    }
+    fn from_f64(n: f64) -> Option<Self> {
+        return FloatCore::round(n * Self::SCALE as f64).to_i64().map(FixedDecimal);
+    }
+}
+
+impl<const P: u32> NumCast for FixedDecimal<P> {
+    fn from<N: ToPrimitive>(n: N) -> Option<Self> {
+        // Integers convert exactly, even when they are too large to be represented exactly as a float.
+        let float = n.to_f64()?;
+        if FloatCore::fract(float) == 0.0 {
+            if let Some(integer) = n.to_i64() {
+                return Self::from_i64(integer);
+            }
+        }
+        return Self::from_f64(float);
+    }
+}
+
+// Serialized as a decimal string, so that no precision is lost.
+#[cfg(feature = "serde")]
+impl<const P: u32> Serialize for FixedDecimal<P> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const P: u32> Deserialize<'de> for FixedDecimal<P> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let decimal = String::deserialize(deserializer)?;
+        return decimal.parse().map_err(serde::de::Error::custom);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stv::Tally;
+    use crate::Quota;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    type Fixed5 = FixedDecimal<5>;
+
+    fn d(s: &str) -> Fixed5 {
+        return s.parse().unwrap();
+    }
+
+    #[test]
+    fn fixed_decimal_arithmetic() {
+        assert_eq!(d("1.5") + d("2.25"), d("3.75"));
+        assert_eq!(d("1.5") - d("2.25"), d("-0.75"));
+        assert_eq!(d("1.5") * d("2.25"), d("3.375"));
+        assert_eq!(d("1") / d("3"), d("0.33333"));
+        assert_eq!(d("2") / d("3"), d("0.66666")); // Truncated, not rounded
+        assert_eq!(d("-2") / d("3"), d("-0.66666")); // Truncated toward zero
+        assert_eq!(d("0.00001") * d("0.5"), Fixed5::zero());
+        assert_eq!(d("7.5") % d("2"), d("1.5"));
+
+        assert_eq!(Numeric::floor(d("7.5")), d("7"));
+        assert_eq!(Numeric::floor(d("-7.5")), d("-8"));
+        assert!(Fixed5::fraction());
+        assert!(!FixedDecimal::<0>::fraction());
+        assert_eq!(Numeric::checked_add(Fixed5::from_units(i64::MAX), d("0.00001")), None);
+        assert_eq!(Numeric::checked_mul(Fixed5::from_units(i64::MAX), d("0.5")), Some(Fixed5::from_units(i64::MAX / 2)));
+        assert_eq!(Numeric::checked_mul(Fixed5::from_units(i64::MAX), d("2")), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflowed")]
+    fn fixed_decimal_mul_overflow() {
+        let _ = Fixed5::from_units(i64::MAX) * d("2");
+    }
+
+    #[test]
+    fn fixed_decimal_conversions() {
+        assert_eq!(d("12.3456789").units(), 1234567);
+        assert_eq!(d("-0.5").to_string(), "-0.50000");
+        assert_eq!(d("+3").to_string(), "3.00000");
+        assert_eq!(FixedDecimal::<0>::from_units(42).to_string(), "42");
+        assert_eq!(FixedDecimal::<2>::from_units(150).to_string(), "1.50");
+        assert!("".parse::<Fixed5>().is_err());
+        assert!("1.2.3".parse::<Fixed5>().is_err());
+        assert!("abc".parse::<Fixed5>().is_err());
+        assert!(Fixed5::from_str_radix("10", 2).is_err());
+        assert!("99999999999999999999".parse::<Fixed5>().is_err());
+
+        assert_eq!(Fixed5::from_usize(3), Some(d("3")));
+        assert_eq!(Fixed5::from_f64(0.3), Some(d("0.3")));
+        assert_eq!(<Fixed5 as NumCast>::from(0.3f64), Some(d("0.3")));
+        assert_eq!(<Fixed5 as NumCast>::from(7u64), Some(d("7")));
+        assert_eq!(d("7.9").to_u64(), Some(7));
+        assert_eq!(d("7.5").to_f64(), Some(7.5));
+    }
+
+    #[test]
+    fn fixed_decimal_quota() {
+        assert_eq!(Quota::Droop.threshold(d("100"), d("2")), d("34"));
+        assert_eq!(Quota::Hagenbach.threshold(d("100"), d("3")), d("25"));
+        assert_eq!(Quota::Hagenbach.threshold(d("100"), d("2")), d("33.33333"));
+    }
+
+    #[test]
+    fn fixed_decimal_stv() -> Result<(), TallyError> {
+        // Alice's surplus of 30000 is transferred at a value of 3/7, truncated to 0.42857. Bob receives 70000 * 0.42857 = 29999.9
+        // votes, which leaves him short of the quota and behind Carlos.
+        let mut tally = Tally::<&str, Fixed5>::new(2, Quota::Static(d("40000")));
+        tally.add_weighted(vec!["Alice", "Bob"], d("70000"))?;
+        tally.add_weighted(vec!["Bob"], d("10000"))?;
+        tally.add_weighted(vec!["Carlos"], d("39999.95"))?;
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Carlos", 1)]);
+
+        // With floats, Bob receives the full surplus and reaches the quota.
+        let mut tally = Tally::<&str, f64>::new(2, Quota::Static(40000.0));
+        tally.add_weighted(vec!["Alice", "Bob"], 70000.0)?;
+        tally.add_weighted(vec!["Bob"], 10000.0)?;
+        tally.add_weighted(vec!["Carlos"], 39999.95)?;
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Bob", 1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_decimal_tallies() -> Result<(), TallyError> {
+        let mut borda = crate::borda::BordaTally::<&str, Fixed5>::new(1, crate::borda::Variant::Dowdall);
+        borda.add(vec!["Alice", "Bob", "Carlos"])?;
+        assert_eq!(borda.totals(), vec![("Alice", d("1")), ("Bob", d("0.5")), ("Carlos", d("0.33333"))]);
+
+        let mut condorcet = crate::condorcet::CondorcetTally::<&str, Fixed5>::new(1);
+        condorcet.add_ranked(crate::RankedBallot::from(vec![vec!["Alice", "Bob"], vec!["Carlos"]]))?;
+        assert_eq!(condorcet.winners().into_vec(), vec![("Alice", 0), ("Bob", 0)]);
+
+        let mut schulze = crate::schulze::SchulzeTally::<&str, Fixed5>::new(1, crate::schulze::Variant::Margin);
+        schulze.add_weighted(vec!["Alice", "Bob"], d("0.5"))?;
+        assert_eq!(schulze.winners().into_vec(), vec![("Alice", 0)]);
+
+        let mut plurality = crate::plurality::PluralityTally::<&str, Fixed5>::new(1);
+        plurality.add_weighted("Alice", d("1.25"))?;
+        assert_eq!(plurality.totals(), vec![("Alice", d("1.25"))]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn fixed_decimal_serde() {
+        let json = serde_json::to_string(&d("-1.25")).unwrap();
+        assert_eq!(json, "\"-1.25000\"");
+        assert_eq!(serde_json::from_str::<Fixed5>(&json).unwrap(), d("-1.25"));
+    }
+}
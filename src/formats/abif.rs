@@ -0,0 +1,573 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use core::str::FromStr;
+use hashbrown::HashMap;
+use num_traits::cast::FromPrimitive;
+
+use crate::BallotBox;
+use crate::Numeric;
+use crate::RankedBallot;
+use crate::TallyError;
+
+use super::parse_error;
+
+// Ranks on a ballot, with each candidate paired with its rating.
+type Ranks<T, C> = Vec<Vec<(T, Option<C>)>>;
+
+/// A line of identical ballots in an ABIF file.
+///
+/// Each rank holds one or more tied candidates, from most to least preferred, along with the rating given to each candidate, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ballot<T, C = u64> {
+    /// The ranks on the ballot, from most to least preferred. Each candidate is paired with its rating, if it was rated.
+    pub ranks: Ranks<T, C>,
+
+    /// The number of identical ballots.
+    pub count: C,
+}
+
+impl<T: Clone, C: Copy> Ballot<T, C> {
+    /// Get the ranked vote on this ballot, without ratings.
+    pub fn ranked(&self) -> RankedBallot<T> {
+        let ranks: Vec<Vec<T>> = self
+            .ranks
+            .iter()
+            .map(|rank| rank.iter().map(|(candidate, _)| candidate.clone()).collect())
+            .collect();
+        return RankedBallot::from(ranks);
+    }
+
+    /// Get the ratings on this ballot, as used by [`ScoreTally`](../../score/struct.ScoreTally.html). Unrated candidates are omitted.
+    pub fn scores(&self) -> Vec<(T, C)> {
+        return self
+            .ranks
+            .iter()
+            .flatten()
+            .filter_map(|(candidate, rating)| rating.map(|rating| (candidate.clone(), rating)))
+            .collect();
+    }
+}
+
+/// An election read from, or to be written to, an ABIF file.
+///
+/// The same ballots can be fed to every tally: use [`ballot_box()`](#method.ballot_box) for the ranked tallies and
+/// [`scores()`](#method.scores) for [`ScoreTally`](../../score/struct.ScoreTally.html).
+///
+/// Generics:
+/// - `T`: The candidate type. Parsed elections use `String`.
+/// - `C`: The count and rating type.
+#[derive(Debug, Clone)]
+pub struct Election<T, C = u64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// The title of the election. Empty if the file does not have a title.
+    pub title: String,
+
+    /// All candidates, in the order they were declared or first seen.
+    pub candidates: Vec<T>,
+
+    /// The ballots, in the order they appear in the file.
+    pub ballots: Vec<Ballot<T, C>>,
+}
+
+impl<T, C> Election<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new election without any ballots.
+    pub fn new(title: &str) -> Self {
+        return Election {
+            title: title.to_string(),
+            candidates: Vec::new(),
+            ballots: Vec::new(),
+        };
+    }
+
+    /// Add all votes in a `BallotBox` as unrated ballots.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) {
+        for (ballot, count) in ballot_box.iter() {
+            let ranks = ballot
+                .into_vec()
+                .into_iter()
+                .map(|rank| rank.into_iter().map(|candidate| (candidate, None)).collect())
+                .collect();
+            self.add_ballot(Ballot {
+                ranks: ranks,
+                count: count,
+            });
+        }
+    }
+
+    /// Add a weighted score vote as a rated ballot. Candidates are ranked by their score, with equal scores tied.
+    pub fn add_scores(&mut self, scores: Vec<(T, C)>, count: C) {
+        let ranks = rank_by_rating(scores.into_iter().map(|(candidate, score)| (candidate, Some(score))).collect());
+        self.add_ballot(Ballot {
+            ranks: ranks,
+            count: count,
+        });
+    }
+
+    /// Add a ballot. Candidates that are not yet listed in `candidates` are added.
+    pub fn add_ballot(&mut self, ballot: Ballot<T, C>) {
+        for (candidate, _) in ballot.ranks.iter().flatten() {
+            if !self.candidates.contains(candidate) {
+                self.candidates.push(candidate.clone());
+            }
+        }
+        self.ballots.push(ballot);
+    }
+
+    /// Collect all ballots into a `BallotBox` for use with [`BordaTally`](../../borda/struct.BordaTally.html),
+    /// [`CondorcetTally`](../../condorcet/struct.CondorcetTally.html), [`SchulzeTally`](../../schulze/struct.SchulzeTally.html),
+    /// [`stv::Tally`](../../stv/struct.Tally.html) or any other tally that supports `add_ballot_box()`.
+    ///
+    /// An error is returned if a ballot lists the same candidate more than once.
+    pub fn ballot_box(&self) -> Result<BallotBox<T, C>, TallyError> {
+        let mut ballot_box = BallotBox::with_capacity(self.candidates.len(), self.ballots.len());
+        for ballot in self.ballots.iter() {
+            ballot_box.add_ranked_weighted(ballot.ranked(), ballot.count)?;
+        }
+        return Ok(ballot_box);
+    }
+
+    /// Get the ratings on every ballot, paired with the number of identical ballots.
+    ///
+    /// Each entry can be added to a [`ScoreTally`](../../score/struct.ScoreTally.html) with `add_weighted()`.
+    /// Unrated candidates are omitted, so ballots without ratings are empty.
+    pub fn scores(&self) -> Vec<(Vec<(T, C)>, C)> {
+        return self.ballots.iter().map(|ballot| (ballot.scores(), ballot.count)).collect();
+    }
+}
+
+/// Parse an election from the contents of an ABIF file.
+///
+/// Ballot counts and ratings are parsed as the count type `C`. Candidates are named by their declared full name,
+/// or by the token used on the ballots if they are not declared.
+pub fn parse<C>(input: &str) -> Result<Election<String, C>, TallyError>
+where
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + FromStr, // Vote count type
+{
+    let mut election = Election::new("");
+    let mut tokens: HashMap<String, usize> = HashMap::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let line_num = i + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // Metadata, eg `{"title": "Example election"}`
+        if line.starts_with('{') {
+            if !line.ends_with('}') {
+                return Err(parse_error(line_num, "unterminated metadata"));
+            }
+            let mut parts = line[1..line.len() - 1].splitn(2, ':');
+            let key = parts.next().unwrap_or_default().trim().trim_matches('"');
+            let value = parts.next().unwrap_or_default().trim();
+            if key == "title" {
+                election.title = unquote(line_num, value)?;
+            }
+            continue;
+        }
+
+        // Candidate declarations, eg `=A:[Alice Smith]`
+        if let Some(declaration) = line.strip_prefix('=') {
+            let mut parts = declaration.splitn(2, ':');
+            let token = parts.next().unwrap_or_default().trim();
+            if !is_token(token) {
+                return Err(parse_error(line_num, format!("invalid candidate token '{}'", token)));
+            }
+            let name = parts.next().unwrap_or_default().trim();
+            let name = if name.is_empty() {
+                token.to_string()
+            } else if name.len() >= 2 && name.starts_with('[') && name.ends_with(']') {
+                name[1..name.len() - 1].to_string()
+            } else {
+                name.to_string()
+            };
+            if tokens.contains_key(token) || election.candidates.contains(&name) {
+                return Err(parse_error(line_num, format!("candidate '{}' is declared more than once", name)));
+            }
+            tokens.insert(token.to_string(), election.candidates.len());
+            election.candidates.push(name);
+            continue;
+        }
+
+        // Ballots, eg `27: A/5 > B/3 = C/3`
+        let separator = match line.find(':') {
+            Some(separator) => separator,
+            None => return Err(parse_error(line_num, "ballot must start with a count followed by ':'")),
+        };
+        let count = line[..separator].trim();
+        let count: C = count
+            .parse()
+            .map_err(|_| parse_error(line_num, format!("invalid ballot count '{}'", count)))?;
+        let ranks = parse_ranks(line_num, &line[separator + 1..], &mut tokens, &mut election.candidates)?;
+
+        let candidates: Vec<&String> = ranks.iter().flatten().map(|(candidate, _)| candidate).collect();
+        for i in 1..candidates.len() {
+            if candidates[i..].contains(&candidates[i - 1]) {
+                return Err(parse_error(line_num, "ballot lists the same candidate more than once"));
+            }
+        }
+        election.ballots.push(Ballot {
+            ranks: ranks,
+            count: count,
+        });
+    }
+
+    return Ok(election);
+}
+
+// Write the election as an ABIF file.
+// Every candidate is declared, so candidates without votes are kept. Candidates that appear on ballots
+// but not in `candidates` are declared after all other candidates.
+impl<T, C> fmt::Display for Election<T, C>
+where
+    T: Eq + Clone + Hash + fmt::Display,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + fmt::Display, // Vote count type
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.title.is_empty() {
+            writeln!(f, "{{\"title\": \"{}\"}}", self.title.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+
+        let on_ballots = self
+            .ballots
+            .iter()
+            .flat_map(|ballot| ballot.ranks.iter().flatten().map(|(candidate, _)| candidate));
+        let mut candidates: Vec<&T> = Vec::with_capacity(self.candidates.len());
+        for candidate in self.candidates.iter().chain(on_ballots) {
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+
+        // Names that are valid tokens are reserved first, so that a generated token such as `C1` is never also a candidate's name.
+        let names: Vec<String> = candidates.iter().map(|candidate| candidate.to_string()).collect();
+        let mut used: Vec<String> = names.iter().filter(|name| is_token(name)).cloned().collect();
+        let mut tokens: HashMap<&T, String> = HashMap::with_capacity(candidates.len());
+        for (i, (candidate, name)) in candidates.iter().zip(names.iter()).enumerate() {
+            let token = if is_token(name) && !tokens.values().any(|token| token == name) {
+                name.clone()
+            } else {
+                let mut n = i + 1;
+                while used.contains(&format!("C{}", n)) {
+                    n += 1;
+                }
+                used.push(format!("C{}", n));
+                format!("C{}", n)
+            };
+            writeln!(f, "={}:[{}]", token, name)?;
+            tokens.insert(candidate, token);
+        }
+
+        for ballot in self.ballots.iter() {
+            let ranks: Vec<String> = ballot
+                .ranks
+                .iter()
+                .map(|rank| {
+                    let rank: Vec<String> = rank
+                        .iter()
+                        .map(|(candidate, rating)| match rating {
+                            Some(rating) => format!("{}/{}", tokens[candidate], rating),
+                            None => tokens[candidate].clone(),
+                        })
+                        .collect();
+                    rank.join("=")
+                })
+                .collect();
+            writeln!(f, "{}:{}", ballot.count, ranks.join(">"))?;
+        }
+        Ok(())
+    }
+}
+
+// Parse the candidates on a ballot into ranks.
+//
+// Candidates separated by `>` are ranked in order, and candidates separated by `=` are tied. If a ballot only uses `,`
+// as a separator, the order of the candidates has no meaning: they are ranked by their ratings if every candidate is rated,
+// otherwise they are all tied.
+fn parse_ranks<C: Copy + PartialOrd + FromStr>(
+    line: usize,
+    mut rest: &str,
+    tokens: &mut HashMap<String, usize>,
+    candidates: &mut Vec<String>,
+) -> Result<Ranks<String, C>, TallyError> {
+    let mut ranks: Ranks<String, C> = vec![Vec::new()];
+    let mut ordered = false;
+
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+
+        // A candidate is either a bare token, or a name in square brackets.
+        let candidate;
+        if rest.starts_with('[') {
+            let end = match rest.find(']') {
+                Some(end) => end,
+                None => return Err(parse_error(line, "unterminated '['")),
+            };
+            candidate = &rest[1..end];
+            rest = &rest[end + 1..];
+        } else {
+            let end = rest.find(|c: char| !is_token_char(c)).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(parse_error(line, format!("expected a candidate at '{}'", rest)));
+            }
+            candidate = &rest[..end];
+            rest = &rest[end..];
+        }
+        let candidate = match tokens.get(candidate) {
+            Some(id) => candidates[*id].clone(),
+            None => {
+                if !candidates.iter().any(|other| other == candidate) {
+                    candidates.push(candidate.to_string());
+                }
+                candidate.to_string()
+            }
+        };
+
+        // An optional rating, eg `/5`
+        rest = rest.trim_start();
+        let mut rating = None;
+        if let Some(after) = rest.strip_prefix('/') {
+            let after = after.trim_start();
+            let end = after
+                .find(|c: char| c == '>' || c == '=' || c == ',' || c.is_whitespace())
+                .unwrap_or(after.len());
+            rating = match after[..end].parse() {
+                Ok(rating) => Some(rating),
+                Err(_) => return Err(parse_error(line, format!("invalid rating '{}'", &after[..end]))),
+            };
+            rest = after[end..].trim_start();
+        }
+        ranks.last_mut().unwrap().push((candidate, rating));
+
+        // The separator before the next candidate
+        if rest.is_empty() {
+            break;
+        }
+        match rest.chars().next().unwrap() {
+            '>' => {
+                ordered = true;
+                ranks.push(Vec::new());
+            }
+            '=' => ordered = true,
+            ',' => {}
+            c => return Err(parse_error(line, format!("unexpected '{}'", c))),
+        }
+        rest = &rest[1..];
+        if rest.trim().is_empty() {
+            return Err(parse_error(line, "ballot ends with a separator"));
+        }
+    }
+
+    if ranks.len() == 1 && ranks[0].is_empty() {
+        return Ok(Vec::new());
+    }
+    if !ordered && ranks[0].iter().all(|(_, rating)| rating.is_some()) {
+        return Ok(rank_by_rating(ranks.pop().unwrap()));
+    }
+    return Ok(ranks);
+}
+
+// Rank rated candidates from the highest to the lowest rating, with equal ratings tied.
+// Unrated candidates are tied in the last rank.
+fn rank_by_rating<T, C: Copy + PartialOrd>(mut candidates: Vec<(T, Option<C>)>) -> Ranks<T, C> {
+    // A stable sort keeps tied candidates in the order they were listed.
+    candidates.sort_by(|(_, a), (_, b)| match (a, b) {
+        (Some(a), Some(b)) => b.partial_cmp(a).unwrap_or(core::cmp::Ordering::Equal),
+        (Some(_), None) => core::cmp::Ordering::Less,
+        (None, Some(_)) => core::cmp::Ordering::Greater,
+        (None, None) => core::cmp::Ordering::Equal,
+    });
+    let mut ranks: Ranks<T, C> = Vec::new();
+    for (candidate, rating) in candidates {
+        match ranks.last_mut() {
+            Some(rank) if rank[0].1 == rating => rank.push((candidate, rating)),
+            _ => ranks.push(vec![(candidate, rating)]),
+        }
+    }
+    return ranks;
+}
+
+// Remove a `#` comment, unless it is inside a bracketed candidate name or a quoted metadata value.
+fn strip_comment(line: &str) -> &str {
+    let mut in_brackets = false;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' if !in_brackets => in_quotes = !in_quotes,
+            '[' if !in_quotes => in_brackets = true,
+            ']' if !in_quotes => in_brackets = false,
+            '#' if !in_brackets && !in_quotes => return &line[..i],
+            _ => {}
+        }
+    }
+    return line;
+}
+
+// Remove the quotes from a quoted metadata value.
+fn unquote(line: usize, value: &str) -> Result<String, TallyError> {
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Err(parse_error(line, "metadata value must be quoted"));
+    }
+    return Ok(value[1..value.len() - 1].replace("\\\"", "\"").replace("\\\\", "\\"));
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn is_token(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(is_token_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::borda::{BordaTally, Variant};
+    use crate::score::DefaultScoreTally;
+    use crate::stv;
+    use crate::Quota;
+
+    #[test]
+    fn abif_parse() -> Result<(), TallyError> {
+        let input = r#"
+            # An example election
+            {"title": "Example election #1"}
+            =A:[Alice Smith]
+            =B:[Bob]
+            =C:[Carlos]
+            27: A/5 > B/3 > C/0
+            12: B/4 = C/4 > A/1
+            10: C/5, A/2, B/2   # Ordered by rating
+            3: [Carlos]>A
+        "#;
+        let election = parse::<u64>(input)?;
+        assert_eq!(election.title, "Example election #1");
+        assert_eq!(election.candidates, vec!["Alice Smith", "Bob", "Carlos"]);
+        assert_eq!(election.ballots.len(), 4);
+        assert_eq!(
+            election.ballots[2].ranks,
+            vec![
+                vec![("Carlos".to_string(), Some(5))],
+                vec![("Alice Smith".to_string(), Some(2)), ("Bob".to_string(), Some(2))],
+            ]
+        );
+        assert_eq!(election.ballots[3].scores(), vec![]);
+
+        // The same file feeds score and ranked tallies.
+        let mut tally = DefaultScoreTally::new(1);
+        for (scores, count) in election.scores() {
+            tally.add_weighted(scores, count).unwrap();
+        }
+        assert_eq!(
+            tally.totals(),
+            vec![
+                ("Alice Smith".to_string(), 167),
+                ("Bob".to_string(), 149),
+                ("Carlos".to_string(), 98)
+            ]
+        );
+
+        let ballot_box = parse::<f64>(input)?.ballot_box()?;
+        let mut tally = stv::DefaultTally::new(1, Quota::Droop);
+        tally.add_ballot_box(&ballot_box)?;
+        assert_eq!(tally.winners().into_unranked(), vec!["Alice Smith".to_string()]);
+
+        let mut tally = BordaTally::<String, f64>::new(1, Variant::Borda);
+        tally.add_ballot_box(&ballot_box)?;
+        assert_eq!(tally.winners().into_unranked(), vec!["Alice Smith".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn abif_errors() {
+        let line = |input: &str| match parse::<u64>(input) {
+            Err(TallyError::ParseError { line, .. }) => line,
+            _ => panic!("expected a parse error"),
+        };
+
+        assert_eq!(line("{\"title\": \"Example\"\n"), 1);
+        assert_eq!(line("{\"title\": Example}\n"), 1);
+        assert_eq!(line("=A:[Alice]\n=A:[Bob]\n"), 2);
+        assert_eq!(line("=[A]:Alice\n"), 1);
+        assert_eq!(line("A>B\n"), 1);
+        assert_eq!(line("x: A>B\n"), 1);
+        assert_eq!(line("1: A>B>A\n"), 1);
+        assert_eq!(line("1: A/x>B\n"), 1);
+        assert_eq!(line("1: A>\n"), 1);
+        assert_eq!(line("1: A>[B\n"), 1);
+        assert_eq!(line("1: A;B\n"), 1);
+    }
+
+    #[test]
+    fn abif_write() -> Result<(), TallyError> {
+        let mut ballots = BallotBox::<&str, u64>::new();
+        ballots.add_weighted(vec!["Alice Smith", "Bob"], 3)?;
+        ballots.add_ranked(RankedBallot::from(vec![vec!["Carlos", "Bob"]]))?;
+        let mut election = Election::new("Example \"election\"");
+        election.add_ballot_box(&ballots);
+        election.add_scores(vec![("Bob", 3), ("Carlos", 5), ("Alice Smith", 3)], 2);
+        election.candidates.push("Dave");
+
+        let output = election.to_string();
+        assert_eq!(
+            output,
+            "{\"title\": \"Example \\\"election\\\"\"}\n=C1:[Alice Smith]\n=Bob:[Bob]\n=Carlos:[Carlos]\n=Dave:[Dave]\n\
+             3:C1>Bob\n1:Carlos=Bob\n2:Carlos/5>Bob/3=C1/3\n"
+        );
+
+        // Writing and then parsing an election preserves it.
+        let parsed = parse::<u64>(&output)?;
+        assert_eq!(parsed.title, "Example \"election\"");
+        assert_eq!(parsed.candidates, vec!["Alice Smith", "Bob", "Carlos", "Dave"]);
+        assert_eq!(parsed.to_string(), output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn abif_write_token_collision() -> Result<(), TallyError> {
+        // The generated token for "Alice Smith" can't be `C1`, since that is another candidate's name.
+        let mut ballots = BallotBox::<&str, u64>::new();
+        ballots.add_weighted(vec!["Alice Smith", "C1"], 3)?;
+        ballots.add_weighted(vec!["C1", "Bob Jones", "Alice Smith"], 2)?;
+        let mut election = Election::new("");
+        election.add_ballot_box(&ballots);
+
+        let output = election.to_string();
+        assert_eq!(output, "=C2:[Alice Smith]\n=C1:[C1]\n=C3:[Bob Jones]\n3:C2>C1\n2:C1>C3>C2\n");
+
+        let parsed = parse::<u64>(&output)?;
+        assert_eq!(parsed.candidates, vec!["Alice Smith", "C1", "Bob Jones"]);
+        let parsed_ballots = parsed.ballot_box()?;
+        assert_eq!(
+            parsed_ballots.iter().collect::<Vec<_>>(),
+            vec![
+                (RankedBallot::from(vec!["Alice Smith".to_string(), "C1".to_string()]), 3),
+                (RankedBallot::from(vec!["C1".to_string(), "Bob Jones".to_string(), "Alice Smith".to_string()]), 2),
+            ]
+        );
+        assert_eq!(parsed.to_string(), output);
+
+        Ok(())
+    }
+}
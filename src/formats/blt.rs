@@ -0,0 +1,386 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use core::str::FromStr;
+use hashbrown::HashMap;
+use num_traits::cast::FromPrimitive;
+
+use crate::BallotBox;
+use crate::Numeric;
+use crate::RankedBallot;
+use crate::TallyError;
+
+use super::parse_error;
+
+/// An election read from, or to be written to, a BLT file.
+///
+/// The ballots can be added to [`stv::Tally`](../../stv/struct.Tally.html) or any other ranked tally with `add_ballot_box()`.
+///
+/// Generics:
+/// - `T`: The candidate type. Parsed elections use `String`.
+/// - `C`: The count type used for ballot weights. Use a float or rational count type if ballots contain equal rankings.
+#[derive(Debug, Clone)]
+pub struct Election<T, C = u64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// The title of the election.
+    pub title: String,
+
+    /// The number of seats to fill.
+    pub seats: u32,
+
+    /// All candidates standing in the election, including withdrawn candidates, in the order they are listed in the file.
+    pub candidates: Vec<T>,
+
+    /// Candidates that have withdrawn from the election.
+    pub withdrawn: Vec<T>,
+
+    /// The ballots cast in the election. Withdrawn candidates are removed from ballots when they are parsed.
+    pub ballots: BallotBox<T, C>,
+}
+
+impl<T, C> Election<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new election from a set of ballots.
+    /// Candidates are listed in the order they were first seen in `ballots`.
+    pub fn new(title: &str, seats: u32, ballots: BallotBox<T, C>) -> Self {
+        return Election {
+            title: title.to_string(),
+            seats: seats,
+            candidates: ballots.candidates(),
+            withdrawn: Vec::new(),
+            ballots: ballots,
+        };
+    }
+}
+
+/// Parse an election from the contents of a BLT file.
+///
+/// Ballot weights are parsed as the count type `C`. Candidate names may be quoted, in which case several
+/// names may share a line, or unquoted with one name per line.
+pub fn parse<C>(input: &str) -> Result<Election<String, C>, TallyError>
+where
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + FromStr, // Vote count type
+{
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .peekable();
+
+    // The header contains the number of candidates and the number of seats.
+    let (line_num, header) = match lines.next() {
+        Some(line) => line,
+        None => return Err(parse_error(1, "missing header")),
+    };
+    let header: Vec<&str> = header.split_whitespace().collect();
+    if header.len() != 2 {
+        return Err(parse_error(
+            line_num,
+            "header must contain the number of candidates and the number of seats",
+        ));
+    }
+    let num_candidates: usize = header[0]
+        .parse()
+        .map_err(|_| parse_error(line_num, format!("invalid number of candidates '{}'", header[0])))?;
+    let seats: u32 = header[1]
+        .parse()
+        .map_err(|_| parse_error(line_num, format!("invalid number of seats '{}'", header[1])))?;
+
+    // Withdrawn candidates are listed as negative candidate numbers directly after the header.
+    let mut withdrawn = Vec::new();
+    if let Some((line_num, line)) = lines.peek().cloned() {
+        if line.starts_with('-') {
+            lines.next();
+            for token in line.split_whitespace() {
+                if !token.starts_with('-') {
+                    return Err(parse_error(line_num, format!("invalid withdrawn candidate '{}'", token)));
+                }
+                withdrawn.push(parse_candidate(line_num, &token[1..], num_candidates)?);
+            }
+        }
+    }
+
+    // Ballots, with candidates as 1-based candidate numbers.
+    let mut ballots: Vec<(RankedBallot<usize>, C)> = Vec::new();
+    let mut last_line = line_num;
+    let mut finished = false;
+    for (line_num, line) in &mut lines {
+        last_line = line_num;
+        let mut tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens == ["0"] {
+            finished = true;
+            break;
+        }
+
+        // Skip the optional ballot identifier.
+        if tokens[0].starts_with('(') {
+            tokens.remove(0);
+        }
+        if tokens.len() < 2 || tokens[tokens.len() - 1] != "0" {
+            return Err(parse_error(line_num, "ballot must contain a weight and end with 0"));
+        }
+        let weight: C = tokens[0]
+            .parse()
+            .map_err(|_| parse_error(line_num, format!("invalid ballot weight '{}'", tokens[0])))?;
+
+        let mut ballot = RankedBallot::new();
+        for token in tokens[1..tokens.len() - 1].iter() {
+            // A skipped rank
+            if *token == "-" {
+                continue;
+            }
+            let mut rank = Vec::new();
+            for candidate in token.split('=') {
+                let candidate = parse_candidate(line_num, candidate, num_candidates)?;
+                if !withdrawn.contains(&candidate) {
+                    rank.push(candidate);
+                }
+            }
+            ballot.push_tied(rank);
+        }
+        if ballot.check_duplicate().is_err() {
+            return Err(parse_error(line_num, "ballot ranks the same candidate more than once"));
+        }
+        ballots.push((ballot, weight));
+    }
+    if !finished {
+        return Err(parse_error(last_line, "missing 0 at the end of the ballots"));
+    }
+
+    // Candidate names, followed by the title.
+    let mut names: Vec<(usize, String)> = Vec::new();
+    for (line_num, line) in lines {
+        last_line = line_num;
+        if line.starts_with('"') {
+            parse_quoted(line_num, line, &mut names)?;
+        } else {
+            names.push((line_num, line.to_string()));
+        }
+    }
+    if names.len() < num_candidates {
+        return Err(parse_error(
+            last_line,
+            format!("expected {} candidate names, found {}", num_candidates, names.len()),
+        ));
+    }
+    if names.len() > num_candidates + 1 {
+        return Err(parse_error(names[num_candidates + 1].0, "unexpected text after the title"));
+    }
+    let title = names.split_off(num_candidates).pop().map(|(_, title)| title).unwrap_or_default();
+    for (i, (line_num, name)) in names.iter().enumerate() {
+        if names[..i].iter().any(|(_, other)| other == name) {
+            return Err(parse_error(*line_num, format!("candidate '{}' is listed more than once", name)));
+        }
+    }
+    let candidates: Vec<String> = names.into_iter().map(|(_, name)| name).collect();
+
+    let mut ballot_box = BallotBox::with_capacity(num_candidates, ballots.len());
+    for (ballot, weight) in ballots {
+        let ranks: Vec<Vec<String>> = ballot
+            .into_vec()
+            .into_iter()
+            .map(|rank| rank.into_iter().map(|candidate| candidates[candidate - 1].clone()).collect())
+            .collect();
+        ballot_box.add_ranked_weighted(RankedBallot::from(ranks), weight)?;
+    }
+
+    return Ok(Election {
+        title: title,
+        seats: seats,
+        withdrawn: withdrawn.into_iter().map(|candidate| candidates[candidate - 1].clone()).collect(),
+        candidates: candidates,
+        ballots: ballot_box,
+    });
+}
+
+// Write the election as a BLT file.
+// Candidates that appear on ballots or in the withdrawn list but not in `candidates` are listed after all other candidates.
+impl<T, C> fmt::Display for Election<T, C>
+where
+    T: Eq + Clone + Hash + fmt::Display,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + fmt::Display, // Vote count type
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut candidates: Vec<&T> = Vec::with_capacity(self.candidates.len());
+        let mut numbers: HashMap<&T, usize> = HashMap::with_capacity(self.candidates.len());
+        for candidate in self
+            .candidates
+            .iter()
+            .chain(self.withdrawn.iter())
+            .chain(self.ballots.candidate_ids())
+        {
+            if !numbers.contains_key(candidate) {
+                candidates.push(candidate);
+                numbers.insert(candidate, candidates.len());
+            }
+        }
+
+        writeln!(f, "{} {}", candidates.len(), self.seats)?;
+        if !self.withdrawn.is_empty() {
+            let withdrawn: Vec<String> = self.withdrawn.iter().map(|candidate| format!("-{}", numbers[candidate])).collect();
+            writeln!(f, "{}", withdrawn.join(" "))?;
+        }
+
+        let ballot_numbers: Vec<usize> = self.ballots.candidate_ids().iter().map(|candidate| numbers[candidate]).collect();
+        for (ballot, weight) in self.ballots.interned() {
+            write!(f, "{}", weight)?;
+            for rank in ballot.ranks() {
+                let rank: Vec<String> = rank.iter().map(|id| ballot_numbers[*id].to_string()).collect();
+                write!(f, " {}", rank.join("="))?;
+            }
+            writeln!(f, " 0")?;
+        }
+        writeln!(f, "0")?;
+
+        for candidate in candidates {
+            writeln!(f, "\"{}\"", candidate)?;
+        }
+        writeln!(f, "\"{}\"", self.title)
+    }
+}
+
+// Parse a 1-based candidate number.
+fn parse_candidate(line: usize, token: &str, num_candidates: usize) -> Result<usize, TallyError> {
+    match token.parse::<usize>() {
+        Ok(candidate) if candidate >= 1 && candidate <= num_candidates => Ok(candidate),
+        _ => Err(parse_error(line, format!("invalid candidate '{}'", token))),
+    }
+}
+
+// Parse one or more quoted strings from a single line.
+fn parse_quoted(line_num: usize, mut line: &str, strings: &mut Vec<(usize, String)>) -> Result<(), TallyError> {
+    loop {
+        line = line.trim_start();
+        if line.is_empty() {
+            return Ok(());
+        }
+        if !line.starts_with('"') {
+            return Err(parse_error(line_num, "expected a quoted string"));
+        }
+        match line[1..].find('"') {
+            Some(end) => {
+                strings.push((line_num, line[1..=end].to_string()));
+                line = &line[end + 2..];
+            }
+            None => return Err(parse_error(line_num, "unterminated quoted string")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condorcet::CondorcetTally;
+    use crate::stv;
+    use crate::Quota;
+
+    #[test]
+    fn blt_parse() -> Result<(), TallyError> {
+        let input = r#"
+            4 2
+            -4
+            3 1 2 0
+            4 2 1 3 0
+            (a1) 2 3 4 0
+            1 1=2 - 3 0
+            1 2 1 3 0
+            0
+            "Alice" "Bob"
+            "Carlos"
+            Dave
+            "Example election"
+        "#;
+        let election = parse::<f64>(input)?;
+        assert_eq!(election.title, "Example election");
+        assert_eq!(election.seats, 2);
+        assert_eq!(election.candidates, vec!["Alice", "Bob", "Carlos", "Dave"]);
+        assert_eq!(election.withdrawn, vec!["Dave"]);
+        assert_eq!(
+            election.ballots.iter().collect::<Vec<_>>(),
+            vec![
+                (RankedBallot::from(vec!["Alice".to_string(), "Bob".to_string()]), 3.0),
+                (
+                    RankedBallot::from(vec!["Bob".to_string(), "Alice".to_string(), "Carlos".to_string()]),
+                    5.0
+                ),
+                (RankedBallot::from(vec!["Carlos".to_string()]), 2.0),
+                (
+                    RankedBallot::from(vec![vec!["Alice".to_string(), "Bob".to_string()], vec!["Carlos".to_string()]]),
+                    1.0
+                ),
+            ]
+        );
+
+        // The ballots can be fed to any ranked tally.
+        let mut tally = stv::DefaultTally::new(election.seats, Quota::Droop);
+        tally.add_ballot_box(&election.ballots)?;
+        assert_eq!(tally.winners().into_unranked(), vec!["Bob".to_string(), "Alice".to_string()]);
+
+        let mut tally = CondorcetTally::<String, f64>::new(1);
+        tally.add_ballot_box(&election.ballots)?;
+        assert_eq!(tally.winners().into_unranked(), vec!["Bob".to_string()]);
+
+        // A title is optional.
+        let election = parse::<u64>("2 1\n1 1 2 0\n0\n\"Alice\"\n\"Bob\"\n")?;
+        assert_eq!(election.title, "");
+        assert_eq!(election.candidates, vec!["Alice", "Bob"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn blt_parse_errors() {
+        let line = |input: &str| match parse::<u64>(input) {
+            Err(TallyError::ParseError { line, .. }) => line,
+            _ => panic!("expected a parse error"),
+        };
+
+        assert_eq!(line(""), 1);
+        assert_eq!(line("2\n"), 1);
+        assert_eq!(line("2 1\n-3\n0\n\"A\"\n\"B\"\n"), 2);
+        assert_eq!(line("2 1\n1 1 2\n0\n\"A\"\n\"B\"\n"), 2);
+        assert_eq!(line("2 1\nx 1 2 0\n0\n\"A\"\n\"B\"\n"), 2);
+        assert_eq!(line("2 1\n1 1 3 0\n0\n\"A\"\n\"B\"\n"), 2);
+        assert_eq!(line("2 1\n1 1 1=2 0\n0\n\"A\"\n\"B\"\n"), 2);
+        assert_eq!(line("2 1\n1 1 2 0\n"), 2);
+        assert_eq!(line("2 1\n1 1 2 0\n0\n\"A\"\n"), 4);
+        assert_eq!(line("2 1\n1 1 2 0\n0\n\"A\"\n\"B\"\n\"Title\"\n\"Extra\"\n"), 7);
+        assert_eq!(line("2 1\n1 1 2 0\n0\n\"A\" \"B\n"), 4);
+        assert_eq!(line("2 1\n1 1 2 0\n0\n\"A\"\n\"A\"\n"), 5);
+    }
+
+    #[test]
+    fn blt_write() -> Result<(), TallyError> {
+        let mut ballots = BallotBox::<&str, u64>::new();
+        ballots.add_weighted(vec!["Alice", "Bob"], 3)?;
+        ballots.add_ranked(RankedBallot::from(vec![vec!["Carlos", "Bob"], vec!["Alice"]]))?;
+        let mut election = Election::new("Example", 1, ballots);
+        election.candidates.push("Dave");
+        election.withdrawn.push("Erin");
+
+        let output = election.to_string();
+        assert_eq!(
+            output,
+            "5 1\n-5\n3 1 2 0\n1 3=2 1 0\n0\n\"Alice\"\n\"Bob\"\n\"Carlos\"\n\"Dave\"\n\"Erin\"\n\"Example\"\n"
+        );
+
+        // Writing and then parsing an election preserves it.
+        let parsed = parse::<u64>(&output)?;
+        assert_eq!(parsed.title, "Example");
+        assert_eq!(parsed.candidates, vec!["Alice", "Bob", "Carlos", "Dave", "Erin"]);
+        assert_eq!(parsed.withdrawn, vec!["Erin"]);
+        assert_eq!(parsed.to_string(), output);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,736 @@
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+use crate::approval::DefaultApprovalTally;
+use crate::borda::{DefaultBordaTally, Variant};
+use crate::plurality::DefaultPluralityTally;
+use crate::score::DefaultScoreTally;
+use crate::stv;
+use crate::BallotPolicy;
+use crate::Candidates;
+use crate::Duplicates;
+use crate::Quota;
+use crate::RankedBallot;
+use crate::RankedWinners;
+use crate::TallyError;
+
+use super::parse_error;
+
+/// The voting method used by a contest.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Method {
+    /// Each voter marks up to `votes_allowed` candidates, and the candidates with the most votes win.
+    /// Contests that allow a single vote are tallied with [`PluralityTally`](../../plurality/struct.PluralityTally.html),
+    /// and contests that allow more are tallied with [`ApprovalTally`](../../approval/struct.ApprovalTally.html).
+    Plurality,
+
+    /// Each voter marks any number of candidates. Tallied with [`ApprovalTally`](../../approval/struct.ApprovalTally.html).
+    Approval,
+
+    /// Each voter ranks candidates. Tallied with [`stv::Tally`](../../stv/struct.Tally.html) using the Droop quota,
+    /// which is instant-runoff voting for a single seat.
+    RankedChoice,
+
+    /// Each voter ranks candidates. Tallied with [`BordaTally`](../../borda/struct.BordaTally.html).
+    Borda,
+
+    /// Each voter gives each candidate a number of votes, such as a score. Tallied with [`ScoreTally`](../../score/struct.ScoreTally.html).
+    Score,
+}
+
+impl Method {
+    // Map an NIST 1500-103 `VoteVariation` to a method.
+    fn from_vote_variation(vote_variation: &str) -> Option<Method> {
+        match vote_variation {
+            "plurality" | "majority" | "super-majority" | "n-of-m" => Some(Method::Plurality),
+            "approval" => Some(Method::Approval),
+            "rcv" => Some(Method::RankedChoice),
+            "borda" => Some(Method::Borda),
+            "range" | "cumulative" => Some(Method::Score),
+            _ => None,
+        }
+    }
+}
+
+/// A candidate marked on a ballot.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Selection {
+    /// The name of the candidate.
+    pub candidate: String,
+
+    /// The rank given to the candidate, starting from 1, if the contest is ranked.
+    pub rank: Option<u32>,
+
+    /// The number of votes given to the candidate. This is 1 unless the contest allows several votes per candidate.
+    pub votes: u64,
+}
+
+/// A single voter's ballot in one contest.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Ballot {
+    /// The unique identifier of the cast vote record, or its position in the file if it doesn't have one.
+    pub id: String,
+
+    /// The candidates marked on the ballot, in the order they appear in the cast vote record.
+    pub selections: Vec<Selection>,
+
+    /// The number of overvotes recorded by the tabulator.
+    pub overvotes: u32,
+
+    /// The number of undervotes recorded by the tabulator.
+    pub undervotes: u32,
+}
+
+/// A contest, along with every ballot cast in it.
+#[derive(Debug)]
+pub struct Contest {
+    /// The identifier of the contest in the cast vote record report.
+    pub id: String,
+
+    /// The name of the contest.
+    pub name: String,
+
+    /// The voting method used by the contest.
+    pub method: Method,
+
+    /// The maximum number of candidates a voter may mark. Ballots in plurality contests that mark more are rejected.
+    pub votes_allowed: u32,
+
+    /// The number of candidates to elect.
+    pub seats: u32,
+
+    /// All candidates standing in the contest, in the order they are listed in the report.
+    pub candidates: Vec<String>,
+
+    /// The ballots that passed validation.
+    pub ballots: Vec<Ballot>,
+
+    /// The ballots that failed validation, along with the reason they were rejected.
+    pub rejected: Vec<(Ballot, TallyError)>,
+}
+
+/// A tally for a single contest, using the tally that matches the contest's voting method.
+pub enum ContestTally {
+    /// A single-vote plurality contest.
+    Plurality(DefaultPluralityTally<String>),
+
+    /// An approval contest, or a plurality contest that allows more than one vote.
+    Approval(DefaultApprovalTally<String>),
+
+    /// A ranked-choice contest.
+    RankedChoice(stv::DefaultTally<String>),
+
+    /// A Borda count contest.
+    Borda(DefaultBordaTally<String>),
+
+    /// A range or cumulative voting contest.
+    Score(DefaultScoreTally<String>),
+}
+
+impl ContestTally {
+    /// Get the winners of the contest.
+    pub fn winners(&self) -> RankedWinners<String> {
+        match self {
+            ContestTally::Plurality(tally) => tally.winners(),
+            ContestTally::Approval(tally) => tally.winners(),
+            ContestTally::RankedChoice(tally) => tally.winners(),
+            ContestTally::Borda(tally) => tally.winners(),
+            ContestTally::Score(tally) => tally.winners(),
+        }
+    }
+}
+
+impl Contest {
+    /// Tally all ballots that passed validation, using the tally for the contest's voting method.
+    ///
+    /// Every candidate standing in the contest is added to the tally, so candidates without votes are still reported.
+    pub fn tally(&self) -> Result<ContestTally, TallyError> {
+        let seats = self.seats;
+        let candidates = Candidates::new(self.candidates.clone()).write_ins(true);
+        let tally = match self.method {
+            Method::Plurality if self.votes_allowed <= 1 => {
+                let mut tally = DefaultPluralityTally::with_candidates(seats, candidates);
+                for ballot in self.ballots.iter() {
+                    if let Some(selection) = ballot.selections.first() {
+                        tally.add_ref(&selection.candidate)?;
+                    }
+                }
+                ContestTally::Plurality(tally)
+            }
+            Method::Plurality | Method::Approval => {
+                let mut tally = DefaultApprovalTally::with_candidates(seats, candidates);
+                for ballot in self.ballots.iter() {
+                    let selection: Vec<String> = ballot.selections.iter().map(|selection| selection.candidate.clone()).collect();
+                    tally.add(selection)?;
+                }
+                ContestTally::Approval(tally)
+            }
+            Method::Score => {
+                let mut tally = DefaultScoreTally::with_candidates(seats, candidates);
+                for ballot in self.ballots.iter() {
+                    tally.add(
+                        ballot
+                            .selections
+                            .iter()
+                            .map(|selection| (selection.candidate.clone(), selection.votes))
+                            .collect(),
+                    )?;
+                }
+                ContestTally::Score(tally)
+            }
+            Method::RankedChoice => {
+                let mut tally = stv::DefaultTally::with_candidates(seats, Quota::Droop, candidates);
+                for ballot in self.ballots.iter() {
+                    tally.add_ranked(ranked(ballot))?;
+                }
+                ContestTally::RankedChoice(tally)
+            }
+            Method::Borda => {
+                let mut tally = DefaultBordaTally::with_candidates(seats, Variant::Borda, candidates);
+                for ballot in self.ballots.iter() {
+                    tally.add_ranked(ranked(ballot))?;
+                }
+                ContestTally::Borda(tally)
+            }
+        };
+        return Ok(tally);
+    }
+}
+
+// Order the selections on a ballot by rank. Selections without a rank are tied after all ranked selections.
+fn ranked(ballot: &Ballot) -> RankedBallot<String> {
+    let mut selections: Vec<&Selection> = ballot.selections.iter().collect();
+    selections.sort_by_key(|selection| selection.rank.unwrap_or(u32::MAX));
+    let mut ranks: Vec<(Option<u32>, Vec<String>)> = Vec::new();
+    for selection in selections {
+        match ranks.last_mut() {
+            Some((rank, candidates)) if *rank == selection.rank => candidates.push(selection.candidate.clone()),
+            _ => ranks.push((selection.rank, vec![selection.candidate.clone()])),
+        }
+    }
+    return RankedBallot::from(ranks.into_iter().map(|(_, candidates)| candidates).collect::<Vec<_>>());
+}
+
+// Create the policy that ballots in a contest are checked against.
+fn policy(contest: &Contest) -> BallotPolicy<String> {
+    match contest.method {
+        // Ranked-choice ballots are counted up to their first overvoted rank, and only the first ranking of a candidate counts.
+        Method::RankedChoice => return BallotPolicy::new().duplicates(Duplicates::KeepFirst),
+        Method::Plurality => return BallotPolicy::new().allow_ties(false).max_marks(contest.votes_allowed as usize),
+        _ => return BallotPolicy::new().allow_ties(false),
+    }
+}
+
+// Check a ballot against the rules of its contest. Ranked-choice ballots are cleaned up so that only the counted selections are left.
+fn validate(policy: &mut BallotPolicy<String>, contest: &Contest, ballot: &mut Ballot, flagged_overvote: bool) -> Result<(), TallyError> {
+    match contest.method {
+        Method::RankedChoice => {
+            let mut ranks = ranked(ballot).into_vec();
+            if let Some(overvote) = ranks.iter().position(|rank| rank.iter().any(|candidate| *candidate != rank[0])) {
+                ranks.truncate(overvote);
+            }
+            let counted = policy.check_ranked(ranks)?.into_vec().concat();
+
+            // Keep the highest ranked selection for each counted candidate.
+            let mut selections: Vec<Selection> = Vec::with_capacity(counted.len());
+            let mut sorted: Vec<&Selection> = ballot.selections.iter().collect();
+            sorted.sort_by_key(|selection| selection.rank.unwrap_or(u32::MAX));
+            for selection in sorted {
+                if counted.contains(&selection.candidate) && !selections.iter().any(|kept| kept.candidate == selection.candidate) {
+                    selections.push(selection.clone());
+                }
+            }
+            ballot.selections = selections;
+        }
+        _ if ballot.overvotes > 0 || flagged_overvote => return Err(TallyError::Overvote),
+        Method::Borda => {
+            policy.check_ranked(ranked(ballot).into_vec())?;
+        }
+        _ => {
+            policy.check(ballot.selections.iter().map(|selection| selection.candidate.clone()).collect())?;
+        }
+    }
+    Ok(())
+}
+
+/// Read every contest from a cast vote record report in the NIST SP 1500-103 JSON format.
+///
+/// The voting method of each contest is taken from its `VoteVariation`. If it doesn't have one, or it isn't supported,
+/// contests where any selection has a rank are treated as ranked-choice contests, and other contests as plurality contests.
+///
+/// For each cast vote record, the current snapshot is used, or the last snapshot if there is no current snapshot.
+/// Selection positions without an indication, or that are not allocable, are ignored.
+///
+/// Ballots in ranked-choice contests are counted following the usual ranked-choice rules: a ballot is counted up to the
+/// first rank that marks more than one candidate, and only the highest ranking of a candidate counts. The selections of
+/// these ballots are cleaned up to leave only the counted selections.
+///
+/// Ballots in other contests are rejected if the tabulator recorded an overvote, if they mark more candidates than allowed
+/// (in plurality contests), if they rank two candidates at the same rank, or if they mark the same candidate twice.
+/// Ballots in any contest are rejected if they mark a selection that isn't in the contest.
+///
+/// An error is returned if the report isn't valid JSON, or if a cast vote record refers to a contest that doesn't exist.
+pub fn parse(input: &str) -> Result<Vec<Contest>, TallyError> {
+    let report: ReportJson = serde_json::from_str(input).map_err(|e| parse_error(e.line(), e.to_string()))?;
+
+    let mut contests: Vec<Contest> = Vec::new();
+    let mut contest_ids: HashMap<String, usize> = HashMap::new();
+    let mut selection_ids: HashMap<(usize, String), String> = HashMap::new(); // Keyed by contest, since selection ids are only valid in their own contest
+    let mut ranked: Vec<bool> = Vec::new();
+    let mut methods: Vec<Option<Method>> = Vec::new();
+    for election in report.election {
+        let names: HashMap<String, String> = election
+            .candidate
+            .into_iter()
+            .map(|candidate| match candidate.name {
+                Some(name) => (candidate.id, name),
+                None => (candidate.id.clone(), candidate.id),
+            })
+            .collect();
+
+        for contest in election.contest {
+            let mut candidates = Vec::with_capacity(contest.contest_selection.len());
+            for selection in contest.contest_selection {
+                let name = if selection.is_write_in == Some(true) {
+                    "Write-in".to_string()
+                } else if let Some(name) = selection.selection {
+                    name
+                } else {
+                    let ids = selection.candidate_ids.iter();
+                    let names: Vec<&str> = ids.map(|id| names.get(id).unwrap_or(id).as_str()).collect();
+                    if names.is_empty() {
+                        selection.id.clone()
+                    } else {
+                        names.join(", ")
+                    }
+                };
+                if !candidates.contains(&name) {
+                    candidates.push(name.clone());
+                }
+                selection_ids.insert((contests.len(), selection.id), name);
+            }
+
+            let id = contest.id;
+            let name = contest.name.unwrap_or_else(|| id.clone());
+            contest_ids.insert(id.clone(), contests.len());
+            methods.push(
+                contest
+                    .vote_variation
+                    .as_ref()
+                    .and_then(|variation| Method::from_vote_variation(variation)),
+            );
+            ranked.push(false);
+            contests.push(Contest {
+                id: id,
+                name: name,
+                method: Method::Plurality,
+                votes_allowed: contest.votes_allowed.unwrap_or(1),
+                seats: contest.number_elected.unwrap_or(1),
+                candidates: candidates,
+                ballots: Vec::new(),
+                rejected: Vec::new(),
+            });
+        }
+    }
+
+    // Collect each contest's ballots, along with whether the tabulator flagged them as overvoted and whether they mark an unknown selection.
+    let mut ballots: Vec<Vec<(Ballot, bool, bool)>> = contests.iter().map(|_| Vec::new()).collect();
+    for (i, cvr) in report.cvr.into_iter().enumerate() {
+        let id = cvr.unique_id.unwrap_or_else(|| (i + 1).to_string());
+        let current = cvr.current_snapshot_id;
+        let snapshot = match cvr.cvr_snapshot.iter().position(|snapshot| Some(&snapshot.id) == current.as_ref()) {
+            Some(position) => cvr.cvr_snapshot.into_iter().nth(position),
+            None => cvr.cvr_snapshot.into_iter().last(),
+        };
+        let snapshot = match snapshot {
+            Some(snapshot) => snapshot,
+            None => continue,
+        };
+
+        for cvr_contest in snapshot.cvr_contest {
+            let contest = match contest_ids.get(&cvr_contest.contest_id) {
+                Some(contest) => *contest,
+                None => {
+                    return Err(parse_error(
+                        0,
+                        format!("cast vote record {} refers to unknown contest '{}'", id, cvr_contest.contest_id),
+                    ))
+                }
+            };
+            let mut ballot = Ballot {
+                id: id.clone(),
+                selections: Vec::new(),
+                overvotes: cvr_contest.overvotes.unwrap_or(0),
+                undervotes: cvr_contest.undervotes.unwrap_or(0),
+            };
+            let overvoted = cvr_contest.status.iter().any(|status| status == "overvoted");
+            let mut unknown = false;
+            for selection in cvr_contest.cvr_contest_selection {
+                let candidate = match selection.contest_selection_id.as_ref().and_then(|id| selection_ids.get(&(contest, id.clone()))) {
+                    Some(candidate) => candidate.clone(),
+                    None => {
+                        unknown = true;
+                        selection.contest_selection_id.unwrap_or_default()
+                    }
+                };
+                // A candidate marked at several ranks keeps their highest (lowest numbered) rank, with the votes marked at that rank.
+                let positions: Vec<SelectionPositionJson> = selection
+                    .selection_position
+                    .into_iter()
+                    .filter(|position| position.has_indication.as_deref() != Some("no") && position.is_allocable.as_deref() != Some("no"))
+                    .collect();
+                let rank = positions.iter().filter_map(|position| position.rank).min().or(selection.rank);
+                let votes: u64 = positions
+                    .iter()
+                    .filter(|position| position.rank.is_none() || position.rank == rank)
+                    .map(|position| position.number_votes.unwrap_or(1))
+                    .sum();
+                if votes > 0 {
+                    ranked[contest] |= rank.is_some();
+                    ballot.selections.push(Selection {
+                        candidate: candidate,
+                        rank: rank,
+                        votes: votes,
+                    });
+                }
+            }
+            ballots[contest].push((ballot, overvoted, unknown));
+        }
+    }
+
+    for (i, contest) in contests.iter_mut().enumerate() {
+        contest.method = match methods[i] {
+            Some(method) => method,
+            None if ranked[i] => Method::RankedChoice,
+            None => Method::Plurality,
+        };
+        let mut policy = policy(contest);
+        for (mut ballot, overvoted, unknown) in ballots[i].drain(..) {
+            let result = if unknown {
+                Err(TallyError::UnknownCandidate)
+            } else {
+                validate(&mut policy, contest, &mut ballot, overvoted)
+            };
+            match result {
+                Ok(()) => contest.ballots.push(ballot),
+                Err(e) => contest.rejected.push((ballot, e)),
+            }
+        }
+    }
+
+    return Ok(contests);
+}
+
+// The parts of the NIST SP 1500-103 JSON schema that are needed to tally contests.
+
+#[derive(Deserialize)]
+struct ReportJson {
+    #[serde(rename = "Election", default)]
+    election: Vec<ElectionJson>,
+    #[serde(rename = "CVR", default)]
+    cvr: Vec<CvrJson>,
+}
+
+#[derive(Deserialize)]
+struct ElectionJson {
+    #[serde(rename = "Candidate", default)]
+    candidate: Vec<CandidateJson>,
+    #[serde(rename = "Contest", default)]
+    contest: Vec<ContestJson>,
+}
+
+#[derive(Deserialize)]
+struct CandidateJson {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "Name")]
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ContestJson {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "Name")]
+    name: Option<String>,
+    #[serde(rename = "VoteVariation")]
+    vote_variation: Option<String>,
+    #[serde(rename = "VotesAllowed")]
+    votes_allowed: Option<u32>,
+    #[serde(rename = "NumberElected")]
+    number_elected: Option<u32>,
+    #[serde(rename = "ContestSelection", default)]
+    contest_selection: Vec<ContestSelectionJson>,
+}
+
+#[derive(Deserialize)]
+struct ContestSelectionJson {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "CandidateIds", default)]
+    candidate_ids: Vec<String>,
+    #[serde(rename = "Selection")]
+    selection: Option<String>,
+    #[serde(rename = "IsWriteIn")]
+    is_write_in: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct CvrJson {
+    #[serde(rename = "UniqueId")]
+    unique_id: Option<String>,
+    #[serde(rename = "CurrentSnapshotId")]
+    current_snapshot_id: Option<String>,
+    #[serde(rename = "CVRSnapshot", default)]
+    cvr_snapshot: Vec<SnapshotJson>,
+}
+
+#[derive(Deserialize)]
+struct SnapshotJson {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "CVRContest", default)]
+    cvr_contest: Vec<CvrContestJson>,
+}
+
+#[derive(Deserialize)]
+struct CvrContestJson {
+    #[serde(rename = "ContestId")]
+    contest_id: String,
+    #[serde(rename = "Overvotes")]
+    overvotes: Option<u32>,
+    #[serde(rename = "Undervotes")]
+    undervotes: Option<u32>,
+    #[serde(rename = "Status", default)]
+    status: Vec<String>,
+    #[serde(rename = "CVRContestSelection", default)]
+    cvr_contest_selection: Vec<CvrContestSelectionJson>,
+}
+
+#[derive(Deserialize)]
+struct CvrContestSelectionJson {
+    #[serde(rename = "ContestSelectionId")]
+    contest_selection_id: Option<String>,
+    #[serde(rename = "Rank")]
+    rank: Option<u32>,
+    #[serde(rename = "SelectionPosition", default)]
+    selection_position: Vec<SelectionPositionJson>,
+}
+
+#[derive(Deserialize)]
+struct SelectionPositionJson {
+    #[serde(rename = "HasIndication")]
+    has_indication: Option<String>,
+    #[serde(rename = "IsAllocable")]
+    is_allocable: Option<String>,
+    #[serde(rename = "NumberVotes")]
+    number_votes: Option<u64>,
+    #[serde(rename = "Rank")]
+    rank: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Build a cast vote record for a single contest, from a list of (contest selection, rank) pairs. A rank of 0 is left out.
+    fn cvr(id: &str, contest: &str, selections: &[(&str, u32)], overvotes: u32) -> String {
+        let selections: Vec<String> = selections
+            .iter()
+            .map(|(selection, rank)| {
+                let rank = if *rank > 0 { format!(r#", "Rank": {}"#, rank) } else { String::new() };
+                format!(
+                    r#"{{"ContestSelectionId": "{}", "SelectionPosition": [{{"HasIndication": "yes", "IsAllocable": "yes", "NumberVotes": 1{}}}]}}"#,
+                    selection, rank
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"UniqueId": "{}", "CurrentSnapshotId": "{}-s", "CVRSnapshot": [{{"@id": "{}-s", "CVRContest": [{{"ContestId": "{}", "Overvotes": {}, "CVRContestSelection": [{}]}}]}}]}}"#,
+            id,
+            id,
+            id,
+            contest,
+            overvotes,
+            selections.join(", ")
+        )
+    }
+
+    fn report(cvrs: &[String]) -> String {
+        format!(
+            r#"{{
+                "@type": "CVR.CastVoteRecordReport",
+                "Election": [{{
+                    "@id": "e",
+                    "Candidate": [
+                        {{"@id": "c-a", "Name": "Alice"}},
+                        {{"@id": "c-b", "Name": "Bob"}},
+                        {{"@id": "c-c", "Name": "Carlos"}}
+                    ],
+                    "Contest": [
+                        {{
+                            "@id": "mayor",
+                            "Name": "Mayor",
+                            "VoteVariation": "rcv",
+                            "VotesAllowed": 3,
+                            "ContestSelection": [
+                                {{"@id": "m-a", "CandidateIds": ["c-a"]}},
+                                {{"@id": "m-b", "CandidateIds": ["c-b"]}},
+                                {{"@id": "m-c", "CandidateIds": ["c-c"]}}
+                            ]
+                        }},
+                        {{
+                            "@id": "measure",
+                            "Name": "Measure A",
+                            "ContestSelection": [
+                                {{"@id": "yes", "Selection": "Yes"}},
+                                {{"@id": "no", "Selection": "No"}}
+                            ]
+                        }}
+                    ]
+                }}],
+                "CVR": [{}]
+            }}"#,
+            cvrs.join(", ")
+        )
+    }
+
+    #[test]
+    fn cvr_parse() -> Result<(), TallyError> {
+        let cvrs = vec![
+            cvr("1", "mayor", &[("m-a", 1), ("m-b", 2)], 0),
+            cvr("2", "mayor", &[("m-a", 1), ("m-c", 2)], 0),
+            cvr("3", "mayor", &[("m-b", 1), ("m-a", 2)], 0),
+            cvr("4", "mayor", &[("m-c", 1), ("m-b", 2)], 0),
+            cvr("5", "mayor", &[("m-a", 1), ("m-b", 2), ("m-c", 2)], 1),
+            cvr("6", "mayor", &[("m-b", 1), ("m-b", 2)], 0),
+            cvr("7", "mayor", &[("m-x", 1)], 0),
+            cvr("8", "mayor", &[("m-a", 1)], 0),
+            cvr("9", "measure", &[("yes", 0)], 0),
+            cvr("10", "measure", &[("yes", 0), ("no", 0)], 1),
+            cvr("11", "measure", &[("no", 0)], 0),
+            cvr("12", "measure", &[("yes", 0)], 0),
+        ];
+        let contests = parse(&report(&cvrs))?;
+        assert_eq!(contests.len(), 2);
+
+        let mayor = &contests[0];
+        assert_eq!(mayor.name, "Mayor");
+        assert_eq!(mayor.method, Method::RankedChoice);
+        assert_eq!(mayor.candidates, vec!["Alice", "Bob", "Carlos"]);
+        assert_eq!(mayor.ballots.len(), 7);
+        assert_eq!(
+            mayor.ballots[0].selections,
+            vec![
+                Selection {
+                    candidate: "Alice".to_string(),
+                    rank: Some(1),
+                    votes: 1
+                },
+                Selection {
+                    candidate: "Bob".to_string(),
+                    rank: Some(2),
+                    votes: 1
+                },
+            ]
+        );
+        let rejected: Vec<(&str, String)> = mayor
+            .rejected
+            .iter()
+            .map(|(ballot, e)| (ballot.id.as_str(), e.to_string()))
+            .collect();
+        assert_eq!(rejected, vec![("7", TallyError::UnknownCandidate.to_string())]);
+
+        // An overvoted ballot is counted up to the overvoted rank, and a repeated candidate keeps their first rank.
+        let selections = |id: &str| -> Vec<(String, Option<u32>)> {
+            let ballot = mayor.ballots.iter().find(|ballot| ballot.id == id).unwrap();
+            ballot.selections.iter().map(|selection| (selection.candidate.clone(), selection.rank)).collect()
+        };
+        assert_eq!(selections("5"), vec![("Alice".to_string(), Some(1))]);
+        assert_eq!(selections("6"), vec![("Bob".to_string(), Some(1))]);
+        assert_eq!(mayor.tally()?.winners().into_unranked(), vec!["Alice".to_string()]);
+
+        // A contest without a vote variation or ranks is a plurality contest.
+        let measure = &contests[1];
+        assert_eq!(measure.method, Method::Plurality);
+        assert_eq!(measure.candidates, vec!["Yes", "No"]);
+        assert_eq!(measure.ballots.len(), 3);
+        assert_eq!(measure.rejected.len(), 1);
+        assert_eq!(measure.rejected[0].0.overvotes, 1);
+        match measure.tally()? {
+            ContestTally::Plurality(tally) => assert_eq!(tally.totals(), vec![("Yes".to_string(), 2), ("No".to_string(), 1)]),
+            _ => panic!("expected a plurality tally"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn cvr_selection_positions() -> Result<(), TallyError> {
+        // A single contest selection marked at two ranks keeps the first rank.
+        let positions = r#"{"ContestSelectionId": "m-a", "SelectionPosition": [
+            {"HasIndication": "yes", "IsAllocable": "yes", "NumberVotes": 1, "Rank": 1},
+            {"HasIndication": "yes", "IsAllocable": "yes", "NumberVotes": 1, "Rank": 3}
+        ]}"#;
+        let cvrs = vec![
+            cvr("1", "mayor", &[("m-b", 2)], 0).replace(r#""CVRContestSelection": ["#, &format!(r#""CVRContestSelection": [{}, "#, positions)),
+        ];
+        let contests = parse(&report(&cvrs))?;
+        assert_eq!(
+            contests[0].ballots[0].selections,
+            vec![
+                Selection {
+                    candidate: "Alice".to_string(),
+                    rank: Some(1),
+                    votes: 1
+                },
+                Selection {
+                    candidate: "Bob".to_string(),
+                    rank: Some(2),
+                    votes: 1
+                },
+            ]
+        );
+
+        // Ranked contests don't limit the number of ranks to the default of one vote allowed.
+        let input = report(&[cvr("1", "mayor", &[("m-a", 1), ("m-b", 2), ("m-c", 3)], 0)]).replace(r#""VotesAllowed": 3,"#, "");
+        let contests = parse(&input)?;
+        assert_eq!(contests[0].votes_allowed, 1);
+        assert_eq!(contests[0].ballots.len(), 1);
+        assert_eq!(contests[0].ballots[0].selections.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cvr_cross_contest_selection() -> Result<(), TallyError> {
+        // Selection ids only refer to selections in their own contest, so these are unknown rather than write-ins.
+        let cvrs = vec![
+            cvr("1", "measure", &[("m-a", 0)], 0),
+            cvr("2", "mayor", &[("yes", 1)], 0),
+            cvr("3", "measure", &[("no", 0)], 0),
+        ];
+        let contests = parse(&report(&cvrs))?;
+        for (contest, rejected) in contests.iter().zip(&["2", "1"]) {
+            assert_eq!(contest.rejected.len(), 1);
+            assert_eq!(contest.rejected[0].0.id, *rejected);
+            assert!(matches!(contest.rejected[0].1, TallyError::UnknownCandidate));
+        }
+        match contests[1].tally()? {
+            ContestTally::Plurality(tally) => assert_eq!(tally.totals(), vec![("No".to_string(), 1), ("Yes".to_string(), 0)]),
+            _ => panic!("expected a plurality tally"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn cvr_errors() {
+        assert!(match parse("{\n\"CVR\": [\n") {
+            Err(TallyError::ParseError { line, .. }) => line == 3,
+            _ => false,
+        });
+        let cvrs = vec![cvr("1", "council", &[("m-a", 1)], 0)];
+        assert!(parse(&report(&cvrs)).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::TallyError;
+use alloc::string::String;
+
+/// The BLT format is the common interchange format for single transferable vote (STV) elections.
+/// It is used by OpenSTV, the Electoral Reform Society and Droop, among others.
+///
+/// A BLT file starts with the number of candidates and the number of seats, optionally followed by a line of withdrawn
+/// candidates, each written as a negative candidate number. Then come the ballots, one per line: a weight followed by
+/// candidate numbers in order of preference, ending with `0`. Candidates that are ranked equally are joined with `=`,
+/// and a skipped rank is written as `-`. The list of ballots ends with a line containing a single `0`, followed by
+/// the quoted name of each candidate and finally the quoted title of the election.
+///
+/// ```text
+/// 4 2
+/// -4
+/// 3 1 2 0
+/// 4 2 1 3 0
+/// 2 3 0
+/// 1 1=2 3 0
+/// 0
+/// "Alice"
+/// "Bob"
+/// "Carlos"
+/// "Dave"
+/// "Example election"
+/// ```
+///
+/// Ballots may also start with an identifier in parentheses, such as `(a12) 1 1 2 0`, which is ignored.
+///
+/// # Example
+/// ```
+///    use tallystick::formats::blt;
+///    use tallystick::stv;
+///    use tallystick::Quota;
+///
+///    let input = "4 2\n-4\n3 1 2 0\n4 2 1 3 0\n2 3 0\n1 1=2 3 0\n0\n\"Alice\"\n\"Bob\"\n\"Carlos\"\n\"Dave\"\n\"Example election\"\n";
+///    let election = blt::parse::<f64>(input).unwrap();
+///    assert_eq!(election.title, "Example election");
+///    assert_eq!(election.withdrawn, vec!["Dave"]);
+///
+///    let mut tally = stv::DefaultTally::new(election.seats, Quota::Droop);
+///    tally.add_ballot_box(&election.ballots).unwrap();
+///    let winners = tally.winners().into_unranked();
+///    println!("The winners are {:?}", winners);
+///
+///    // Any set of ballots can be written back to BLT.
+///    let output = election.to_string();
+///    assert_eq!(blt::parse::<f64>(&output).unwrap().ballots.total(), 10.0);
+/// ```
+pub mod blt;
+
+/// ABIF, the Aggregated Ballot Information Format, is a text format for ranked, rated and approval ballots.
+///
+/// Each ballot line starts with the number of identical ballots, followed by the candidates in order of preference.
+/// Candidates separated by `>` are ranked in order and candidates separated by `=` are tied. A candidate may be given a rating
+/// with `/`, and candidates may be declared with a full name using `=token:[Full Name]`. Lines starting with `{` hold metadata,
+/// of which the `title` is kept, and `#` starts a comment.
+///
+/// ```text
+/// {"title": "Example election"}
+/// =A:[Alice Smith]
+/// =B:[Bob]
+/// =C:[Carlos]
+/// 27: A/5 > B/3 > C/0
+/// 12: B/4 = C/4 > A/1
+/// 10: C/5, A/2, B/2
+/// ```
+///
+/// If a ballot only separates candidates with `,`, the order they are listed in has no meaning. They are ranked by their
+/// ratings if every candidate is rated, and are otherwise all tied.
+///
+/// The same file can be tallied by any method: ratings feed [`ScoreTally`](../score/struct.ScoreTally.html), and the ranks feed the ranked tallies.
+///
+/// # Example
+/// ```
+///    use tallystick::formats::abif;
+///    use tallystick::condorcet::CondorcetTally;
+///    use tallystick::score::ScoreTally;
+///
+///    let input = "=A:[Alice Smith]\n27: A/5 > Bob/3 > Carlos/0\n12: Bob/4 = Carlos/4 > A/1\n10: Carlos/5, A/2, Bob/2\n";
+///    // Tied ranks require a fractional count type.
+///    let election = abif::parse::<f64>(input).unwrap();
+///
+///    let mut tally = ScoreTally::<String, f64>::new(1);
+///    for (scores, count) in election.scores() {
+///        tally.add_weighted(scores, count).unwrap();
+///    }
+///    assert_eq!(tally.winners().into_unranked(), vec!["Alice Smith".to_string()]);
+///
+///    let mut tally = CondorcetTally::<String, f64>::new(1);
+///    tally.add_ballot_box(&election.ballot_box().unwrap()).unwrap();
+///    assert_eq!(tally.winners().into_unranked(), vec!["Alice Smith".to_string()]);
+///
+///    // Any set of ballots can be written back as ABIF.
+///    println!("{}", election);
+/// ```
+pub mod abif;
+
+/// Cast vote records in the [NIST SP 1500-103](https://doi.org/10.6028/NIST.SP.1500-103) JSON format, as published by US jurisdictions.
+///
+/// A cast vote record report lists the contests on the ballot and how each ballot was marked in each contest.
+/// [`parse()`](cvr/fn.parse.html) extracts every contest, with the selections, ranks, overvotes and undervotes on each ballot,
+/// and checks each ballot against the rules of its contest. Ballots that fail validation are reported separately,
+/// and the remaining ballots can be tallied with the tally that matches the contest's voting method.
+///
+/// This module requires the `cvr` feature.
+///
+/// # Example
+/// ```
+///    use tallystick::formats::cvr;
+///
+///    let input = r#"{
+///        "Election": [{
+///            "Candidate": [{"@id": "c-a", "Name": "Alice"}, {"@id": "c-b", "Name": "Bob"}],
+///            "Contest": [{
+///                "@id": "mayor",
+///                "Name": "Mayor",
+///                "VoteVariation": "rcv",
+///                "VotesAllowed": 2,
+///                "ContestSelection": [{"@id": "s-a", "CandidateIds": ["c-a"]}, {"@id": "s-b", "CandidateIds": ["c-b"]}]
+///            }]
+///        }],
+///        "CVR": [{
+///            "CVRSnapshot": [{
+///                "@id": "snapshot",
+///                "CVRContest": [{
+///                    "ContestId": "mayor",
+///                    "CVRContestSelection": [
+///                        {"ContestSelectionId": "s-b", "SelectionPosition": [{"HasIndication": "yes", "Rank": 1}]},
+///                        {"ContestSelectionId": "s-a", "SelectionPosition": [{"HasIndication": "yes", "Rank": 2}]}
+///                    ]
+///                }]
+///            }]
+///        }]
+///    }"#;
+///
+///    for contest in cvr::parse(input).unwrap() {
+///        let winners = contest.tally().unwrap().winners().into_unranked();
+///        println!("{}: {:?} won, {} ballots rejected", contest.name, winners, contest.rejected.len());
+///    }
+/// ```
+#[cfg(feature = "cvr")]
+pub mod cvr;
+
+/// [PrefLib](https://www.preflib.org) is a library of real-world preference data used for research on voting methods.
+///
+/// PrefLib files contain one of five types of preference data, identified by their file extension:
+/// strict orders over all candidates (`.soc`) or some of the candidates (`.soi`), orders with ties over all
+/// candidates (`.toc`) or some of the candidates (`.toi`), and categorical preferences (`.cat`).
+/// Order data is read into a [`BallotBox`](../../struct.BallotBox.html) that can be added to any ranked tally.
+/// Categorical data is also available as approval votes, where the first category is approved.
+///
+/// # Example
+/// ```
+///    use tallystick::formats::preflib;
+///    use tallystick::approval::DefaultApprovalTally;
+///    use tallystick::condorcet::DefaultCondorcetTally;
+///
+///    let input = [
+///        "# DATA TYPE: soi",
+///        "# NUMBER ALTERNATIVES: 3",
+///        "# ALTERNATIVE NAME 1: Alice",
+///        "# ALTERNATIVE NAME 2: Bob",
+///        "# ALTERNATIVE NAME 3: Carlos",
+///        "# NUMBER VOTERS: 5",
+///        "# NUMBER UNIQUE ORDERS: 2",
+///        "3: 1,2,3",
+///        "2: 2",
+///    ];
+///    let election = preflib::parse::<u64>(&input.join("\n")).unwrap();
+///
+///    let mut tally = DefaultCondorcetTally::new(1);
+///    tally.add_ballot_box(&election.ballots).unwrap();
+///    assert_eq!(tally.winners().into_unranked(), vec!["Alice".to_string()]);
+///
+///    let mut tally = DefaultApprovalTally::new(1);
+///    tally.add_ballot_box(&election.approvals()).unwrap();
+///    assert_eq!(tally.winners().into_unranked(), vec!["Bob".to_string()]);
+///
+///    // Any set of ballots can be written back as PrefLib data.
+///    println!("{}", election);
+/// ```
+pub mod preflib;
+
+// Create a parse error at the given 1-based line number.
+fn parse_error<M: Into<String>>(line: usize, message: M) -> TallyError {
+    TallyError::ParseError {
+        line: line,
+        message: message.into(),
+    }
+}
@@ -0,0 +1,597 @@
+use hashbrown::HashMap;
+use num_traits::cast::FromPrimitive;
+use core::fmt;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use core::str::FromStr;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::BallotBox;
+use crate::COUNT_OVERFLOW_PANIC;
+use crate::Numeric;
+use crate::RankedBallot;
+use crate::TallyError;
+
+use super::parse_error;
+
+/// The type of preference data stored in a PrefLib file.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DataType {
+    /// Strict orders over all candidates (`.soc`).
+    Soc,
+
+    /// Strict orders over some of the candidates (`.soi`).
+    Soi,
+
+    /// Orders with ties over all candidates (`.toc`).
+    Toc,
+
+    /// Orders with ties over some of the candidates (`.toi`).
+    Toi,
+
+    /// Categorical preferences, where each voter places every candidate into one of several ordered categories (`.cat`).
+    Cat,
+}
+
+impl DataType {
+    /// Get the data type for a file extension, such as `soc` or `.toi`. Returns `None` if the extension is not a PrefLib data type.
+    pub fn from_extension(extension: &str) -> Option<DataType> {
+        match extension.trim_start_matches('.').to_lowercase().as_str() {
+            "soc" => Some(DataType::Soc),
+            "soi" => Some(DataType::Soi),
+            "toc" => Some(DataType::Toc),
+            "toi" => Some(DataType::Toi),
+            "cat" => Some(DataType::Cat),
+            _ => None,
+        }
+    }
+
+    /// Get the file extension for this data type, without a leading dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            DataType::Soc => "soc",
+            DataType::Soi => "soi",
+            DataType::Toc => "toc",
+            DataType::Toi => "toi",
+            DataType::Cat => "cat",
+        }
+    }
+
+    /// Check if this data type allows tied candidates.
+    pub fn allows_ties(self) -> bool {
+        match self {
+            DataType::Toc | DataType::Toi | DataType::Cat => true,
+            DataType::Soc | DataType::Soi => false,
+        }
+    }
+
+    /// Check if this data type requires every candidate to appear in every preference.
+    pub fn is_complete(self) -> bool {
+        match self {
+            DataType::Soc | DataType::Toc | DataType::Cat => true,
+            DataType::Soi | DataType::Toi => false,
+        }
+    }
+}
+
+/// An election read from, or to be written to, a PrefLib file.
+///
+/// Orders are stored as a [`BallotBox`](../../struct.BallotBox.html), which can be added to any ranked tally with `add_ballot_box()`.
+/// Categorical preferences are also added to `ballots`, with each non-empty category as a rank, so they can be tallied the same way.
+///
+/// Generics:
+/// - `T`: The candidate type. Parsed elections use `String`.
+/// - `C`: The count type used for the number of voters with each preference.
+#[derive(Debug, Clone)]
+pub struct Election<T, C = u64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// The title of the election. Empty if the file does not have a title.
+    pub title: String,
+
+    /// The type of preference data.
+    pub data_type: DataType,
+
+    /// All candidates, in the order of their PrefLib alternative number.
+    pub candidates: Vec<T>,
+
+    /// The preferences of all voters as ranked ballots.
+    pub ballots: BallotBox<T, C>,
+
+    /// The names of the categories for categorical data, from most to least preferred. Empty for order data.
+    pub categories: Vec<String>,
+
+    /// Categorical preferences, each with the candidates placed in each category and the number of voters. Empty for order data.
+    pub categorical: Vec<(Vec<Vec<T>>, C)>,
+}
+
+impl<T, C> Election<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new election from a set of ranked ballots.
+    ///
+    /// Candidates are listed in the order they were first seen in `ballots`. The data type is the most specific
+    /// order type that describes the ballots.
+    pub fn new(title: &str, ballots: BallotBox<T, C>) -> Self {
+        let candidates = ballots.candidates();
+        let ties = ballots.has_ties();
+        let complete = ballots
+            .interned()
+            .iter()
+            .all(|(ballot, _)| ballot.num_candidates() == candidates.len());
+        let data_type = match (ties, complete) {
+            (false, true) => DataType::Soc,
+            (false, false) => DataType::Soi,
+            (true, true) => DataType::Toc,
+            (true, false) => DataType::Toi,
+        };
+        return Election {
+            title: title.to_string(),
+            data_type: data_type,
+            candidates: candidates,
+            ballots: ballots,
+            categories: Vec::new(),
+            categorical: Vec::new(),
+        };
+    }
+
+    /// Get approval votes for use with [`ApprovalTally`](../../approval/struct.ApprovalTally.html).
+    ///
+    /// For categorical data, the candidates in the first category are approved.
+    /// For order data, every ranked candidate is approved, which is how `ApprovalTally::add_ballot_box()` treats ranked ballots.
+    pub fn approvals(&self) -> BallotBox<T, C> {
+        if self.data_type != DataType::Cat {
+            return self.ballots.clone();
+        }
+        let mut approvals = BallotBox::new();
+        for (categories, count) in self.categorical.iter() {
+            if let Some(approved) = categories.first() {
+                // Every categorical vote was also added to `ballots`, so the total can't overflow here.
+                approvals.push_unchecked(approved, *count).expect(COUNT_OVERFLOW_PANIC);
+            }
+        }
+        return approvals;
+    }
+}
+
+/// Parse an election from the contents of a PrefLib file.
+///
+/// Both the current PrefLib format, with `# KEY: value` metadata lines, and the older format, which starts with the
+/// number of alternatives, are supported. Files in the older format do not declare a data type, so it is inferred from the preferences.
+pub fn parse<C>(input: &str) -> Result<Election<String, C>, TallyError>
+where
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + FromStr, // Vote count type
+{
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .peekable();
+
+    let mut title = String::new();
+    let mut data_type = None;
+    let mut num_candidates = None;
+    let mut names: HashMap<usize, String> = HashMap::new();
+    let mut num_categories = None;
+    let mut category_names: HashMap<usize, String> = HashMap::new();
+    let legacy;
+
+    match lines.peek().cloned() {
+        None => return Err(parse_error(1, "missing header")),
+        Some((_, line)) if line.starts_with('#') => {
+            legacy = false;
+            while let Some((line_num, line)) = lines.peek().cloned() {
+                if !line.starts_with('#') {
+                    break;
+                }
+                lines.next();
+                let mut parts = line[1..].splitn(2, ':');
+                let key = parts.next().unwrap_or_default().trim().to_uppercase();
+                let value = parts.next().unwrap_or_default().trim();
+                if key == "TITLE" {
+                    title = value.to_string();
+                } else if key == "DATA TYPE" {
+                    match DataType::from_extension(value) {
+                        Some(value) => data_type = Some(value),
+                        None => return Err(parse_error(line_num, format!("unknown data type '{}'", value))),
+                    }
+                } else if key == "NUMBER ALTERNATIVES" {
+                    num_candidates = Some(parse_number(line_num, value)?);
+                } else if key == "NUMBER CATEGORIES" {
+                    num_categories = Some(parse_number(line_num, value)?);
+                } else if let Some(number) = key.strip_prefix("ALTERNATIVE NAME ") {
+                    names.insert(parse_number(line_num, number)?, value.to_string());
+                } else if let Some(number) = key.strip_prefix("CATEGORY NAME ") {
+                    category_names.insert(parse_number(line_num, number)?, value.to_string());
+                }
+            }
+        }
+        Some((line_num, line)) => {
+            // The legacy header lists the number of alternatives, then each alternative as `number,name`,
+            // then the number of voters, the sum of the preference counts and the number of unique preferences.
+            legacy = true;
+            lines.next();
+            let count = parse_number(line_num, line)?;
+            for _ in 0..count {
+                let (line_num, line) = match lines.next() {
+                    Some(line) => line,
+                    None => return Err(parse_error(line_num, "missing alternative names")),
+                };
+                let mut parts = line.splitn(2, ',');
+                let number = parse_number(line_num, parts.next().unwrap_or_default())?;
+                names.insert(number, parts.next().unwrap_or_default().trim().to_string());
+            }
+            if lines.next().is_none() {
+                return Err(parse_error(line_num, "missing number of voters"));
+            }
+            num_candidates = Some(count);
+        }
+    }
+
+    let num_candidates = match num_candidates {
+        Some(num_candidates) => num_candidates,
+        None => return Err(parse_error(1, "missing number of alternatives")),
+    };
+    let candidates: Vec<String> = (1..=num_candidates)
+        .map(|i| names.remove(&i).unwrap_or_else(|| i.to_string()))
+        .collect();
+    let num_categories = match (data_type, num_categories) {
+        (Some(DataType::Cat), Some(num_categories)) => num_categories,
+        (Some(DataType::Cat), None) => return Err(parse_error(1, "missing number of categories")),
+        _ => 0,
+    };
+    let categories: Vec<String> = (1..=num_categories)
+        .map(|i| category_names.remove(&i).unwrap_or_else(|| i.to_string()))
+        .collect();
+
+    // Preferences, each as a count followed by candidate numbers grouped by rank or category.
+    let mut preferences: Vec<(usize, Vec<Vec<usize>>, C)> = Vec::new();
+    for (line_num, line) in lines {
+        let separator = if legacy { ',' } else { ':' };
+        let mut parts = line.splitn(2, separator);
+        let count = parts.next().unwrap_or_default().trim();
+        let count: C = count
+            .parse()
+            .map_err(|_| parse_error(line_num, format!("invalid count '{}'", count)))?;
+        let groups = parse_groups(line_num, parts.next().unwrap_or_default(), num_candidates)?;
+        preferences.push((line_num, groups, count));
+    }
+
+    let mut ties = false;
+    let mut complete = true;
+    for (line_num, groups, _) in preferences.iter() {
+        let num_marked: usize = groups.iter().map(|group| group.len()).sum();
+        let mut marked: Vec<usize> = groups.iter().flatten().cloned().collect();
+        marked.sort();
+        marked.dedup();
+        if marked.len() != num_marked {
+            return Err(parse_error(*line_num, "preference lists the same candidate more than once"));
+        }
+        ties |= groups.iter().any(|group| group.len() > 1);
+        complete &= num_marked == num_candidates;
+
+        if let Some(data_type) = data_type {
+            if data_type == DataType::Cat && groups.len() != num_categories {
+                return Err(parse_error(*line_num, format!("expected {} categories", num_categories)));
+            }
+            if !data_type.allows_ties() && groups.iter().any(|group| group.len() > 1) {
+                return Err(parse_error(*line_num, "strict order contains tied candidates"));
+            }
+            if data_type.is_complete() && num_marked != num_candidates {
+                return Err(parse_error(*line_num, "complete order does not list every candidate"));
+            }
+        }
+    }
+    let data_type = match data_type {
+        Some(data_type) => data_type,
+        None => match (ties, complete) {
+            (false, true) => DataType::Soc,
+            (false, false) => DataType::Soi,
+            (true, true) => DataType::Toc,
+            (true, false) => DataType::Toi,
+        },
+    };
+
+    let mut ballots = BallotBox::with_capacity(num_candidates, preferences.len());
+    let mut categorical = Vec::new();
+    for (_, groups, count) in preferences {
+        let groups: Vec<Vec<String>> = groups
+            .into_iter()
+            .map(|group| group.into_iter().map(|candidate| candidates[candidate - 1].clone()).collect())
+            .collect();
+        ballots.add_ranked_weighted(RankedBallot::from(groups.clone()), count)?;
+        if data_type == DataType::Cat {
+            categorical.push((groups, count));
+        }
+    }
+
+    return Ok(Election {
+        title: title,
+        data_type: data_type,
+        candidates: candidates,
+        ballots: ballots,
+        categories: categories,
+        categorical: categorical,
+    });
+}
+
+// Write the election in the current PrefLib format.
+// Order data is written from `ballots`, and categorical data from `categorical`.
+// Candidates that appear in preferences but not in `candidates` are listed after all other candidates.
+impl<T, C> fmt::Display for Election<T, C>
+where
+    T: Eq + Clone + Hash + fmt::Display,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + fmt::Display, // Vote count type
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut preferences: Vec<(Vec<Vec<&T>>, C)> = Vec::new();
+        if self.data_type == DataType::Cat {
+            for (categories, count) in self.categorical.iter() {
+                preferences.push((categories.iter().map(|category| category.iter().collect()).collect(), *count));
+            }
+        } else {
+            let ids = self.ballots.candidate_ids();
+            for (ballot, count) in self.ballots.interned() {
+                preferences.push((
+                    ballot
+                        .ranks()
+                        .iter()
+                        .map(|rank| rank.iter().map(|id| &ids[*id]).collect())
+                        .collect(),
+                    *count,
+                ));
+            }
+        }
+
+        let mut candidates: Vec<&T> = Vec::with_capacity(self.candidates.len());
+        let mut numbers: HashMap<&T, usize> = HashMap::with_capacity(self.candidates.len());
+        let marked = preferences.iter().flat_map(|(groups, _)| groups.iter().flatten().cloned());
+        for candidate in self.candidates.iter().chain(marked) {
+            if !numbers.contains_key(candidate) {
+                candidates.push(candidate);
+                numbers.insert(candidate, candidates.len());
+            }
+        }
+
+        let mut total = C::zero();
+        for (_, count) in preferences.iter() {
+            total += *count;
+        }
+
+        if !self.title.is_empty() {
+            writeln!(f, "# TITLE: {}", self.title)?;
+        }
+        writeln!(f, "# DATA TYPE: {}", self.data_type.extension())?;
+        writeln!(f, "# NUMBER ALTERNATIVES: {}", candidates.len())?;
+        for (i, candidate) in candidates.iter().enumerate() {
+            writeln!(f, "# ALTERNATIVE NAME {}: {}", i + 1, candidate)?;
+        }
+        if self.data_type == DataType::Cat {
+            writeln!(f, "# NUMBER CATEGORIES: {}", self.categories.len())?;
+            for (i, category) in self.categories.iter().enumerate() {
+                writeln!(f, "# CATEGORY NAME {}: {}", i + 1, category)?;
+            }
+        }
+        writeln!(f, "# NUMBER VOTERS: {}", total)?;
+        if self.data_type == DataType::Cat {
+            writeln!(f, "# NUMBER UNIQUE PREFERENCES: {}", preferences.len())?;
+        } else {
+            writeln!(f, "# NUMBER UNIQUE ORDERS: {}", preferences.len())?;
+        }
+
+        for (groups, count) in preferences {
+            let groups: Vec<String> = groups
+                .iter()
+                .map(|group| {
+                    let group: Vec<String> = group.iter().map(|candidate| numbers[*candidate].to_string()).collect();
+                    if group.len() == 1 {
+                        group[0].clone()
+                    } else {
+                        format!("{{{}}}", group.join(","))
+                    }
+                })
+                .collect();
+            writeln!(f, "{}: {}", count, groups.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(line: usize, token: &str) -> Result<usize, TallyError> {
+    token
+        .trim()
+        .parse()
+        .map_err(|_| parse_error(line, format!("invalid number '{}'", token.trim())))
+}
+
+// Parse a comma separated list of candidate numbers, where tied candidates are grouped in braces, eg `1,{2,3},4`.
+// An empty group `{}` is kept, since categorical preferences may leave a category empty.
+fn parse_groups(line: usize, list: &str, num_candidates: usize) -> Result<Vec<Vec<usize>>, TallyError> {
+    let mut groups = Vec::new();
+    let mut rest = list.trim();
+    while !rest.is_empty() {
+        let group;
+        if rest.starts_with('{') {
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => return Err(parse_error(line, "unterminated '{'")),
+            };
+            group = &rest[1..end];
+            rest = rest[end + 1..].trim_start();
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            group = &rest[..end];
+            rest = &rest[end..];
+        }
+
+        let mut candidates = Vec::new();
+        for candidate in group
+            .split(',')
+            .map(|candidate| candidate.trim())
+            .filter(|candidate| !candidate.is_empty())
+        {
+            match candidate.parse::<usize>() {
+                Ok(candidate) if candidate >= 1 && candidate <= num_candidates => candidates.push(candidate),
+                _ => return Err(parse_error(line, format!("invalid candidate '{}'", candidate))),
+            }
+        }
+        groups.push(candidates);
+
+        if rest.starts_with(',') {
+            rest = rest[1..].trim_start();
+            if rest.is_empty() {
+                return Err(parse_error(line, "unexpected ',' at the end of the preference"));
+            }
+        } else if !rest.is_empty() {
+            return Err(parse_error(line, format!("unexpected '{}'", rest)));
+        }
+    }
+    return Ok(groups);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::DefaultApprovalTally;
+    use crate::schulze::{SchulzeTally, Variant};
+
+    #[test]
+    fn preflib_parse() -> Result<(), TallyError> {
+        let input = "
+            # FILE NAME: 00001-00000001.toi
+            # TITLE: Example election
+            # DATA TYPE: toi
+            # NUMBER ALTERNATIVES: 4
+            # ALTERNATIVE NAME 1: Alice
+            # ALTERNATIVE NAME 2: Bob
+            # ALTERNATIVE NAME 3: Carlos
+            # ALTERNATIVE NAME 4: Dave
+            # NUMBER VOTERS: 10
+            # NUMBER UNIQUE ORDERS: 3
+            5: 1,2,{3,4}
+            3: 2,1
+            2: {3,4}
+        ";
+        let election = parse::<f64>(input)?;
+        assert_eq!(election.title, "Example election");
+        assert_eq!(election.data_type, DataType::Toi);
+        assert_eq!(election.candidates, vec!["Alice", "Bob", "Carlos", "Dave"]);
+        assert_eq!(election.ballots.len(), 3);
+        assert_eq!(election.ballots.total(), 10.0);
+
+        let mut tally = SchulzeTally::<String, f64>::new(1, Variant::Winning);
+        tally.add_ballot_box(&election.ballots)?;
+        assert_eq!(tally.winners().into_unranked(), vec!["Alice".to_string()]);
+
+        // The legacy format, without a data type.
+        let input = "3\n1,Alice\n2,Bob\n3,Carlos\n5,5,2\n3,1,2,3\n2,3,2,1\n";
+        let election = parse::<u64>(input)?;
+        assert_eq!(election.data_type, DataType::Soc);
+        assert_eq!(election.candidates, vec!["Alice", "Bob", "Carlos"]);
+        assert_eq!(
+            election.ballots.iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    RankedBallot::from(vec!["Alice".to_string(), "Bob".to_string(), "Carlos".to_string()]),
+                    3
+                ),
+                (
+                    RankedBallot::from(vec!["Carlos".to_string(), "Bob".to_string(), "Alice".to_string()]),
+                    2
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn preflib_categorical() -> Result<(), TallyError> {
+        let input = "
+            # DATA TYPE: cat
+            # NUMBER ALTERNATIVES: 3
+            # ALTERNATIVE NAME 1: Alice
+            # ALTERNATIVE NAME 2: Bob
+            # ALTERNATIVE NAME 3: Carlos
+            # NUMBER CATEGORIES: 2
+            # CATEGORY NAME 1: Approved
+            # CATEGORY NAME 2: Not approved
+            # NUMBER VOTERS: 6
+            # NUMBER UNIQUE PREFERENCES: 3
+            3: {1,2},3
+            2: 3,{1,2}
+            1: {},{1,2,3}
+        ";
+        let election = parse::<u64>(input)?;
+        assert_eq!(election.data_type, DataType::Cat);
+        assert_eq!(election.categories, vec!["Approved", "Not approved"]);
+        assert_eq!(election.categorical.len(), 3);
+        assert_eq!(election.categorical[2].0, vec![vec![], vec!["Alice", "Bob", "Carlos"]]);
+
+        let mut tally = DefaultApprovalTally::new(1);
+        tally.add_ballot_box(&election.approvals())?;
+        assert_eq!(
+            tally.totals(),
+            vec![("Alice".to_string(), 3), ("Bob".to_string(), 3), ("Carlos".to_string(), 2)]
+        );
+
+        // Writing and then parsing an election preserves it.
+        let output = election.to_string();
+        assert!(output.contains("3: {1,2},3\n"));
+        assert!(output.contains("1: {},{1,2,3}\n"));
+        assert_eq!(parse::<u64>(&output)?.to_string(), output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn preflib_errors() {
+        let line = |input: &str| match parse::<u64>(input) {
+            Err(TallyError::ParseError { line, .. }) => line,
+            _ => panic!("expected a parse error"),
+        };
+
+        let header = "# DATA TYPE: soc\n# NUMBER ALTERNATIVES: 3\n";
+        assert_eq!(line(""), 1);
+        assert_eq!(line("# DATA TYPE: abc\n"), 1);
+        assert_eq!(line("# DATA TYPE: soc\n1: 1,2,3\n"), 1);
+        assert_eq!(line(&format!("{}1: 1,2,4\n", header)), 3);
+        assert_eq!(line(&format!("{}1: 1,2,2\n", header)), 3);
+        assert_eq!(line(&format!("{}1: 1,{{2,3}}\n", header)), 3);
+        assert_eq!(line(&format!("{}1: 1,2\n", header)), 3);
+        assert_eq!(line(&format!("{}x: 1,2,3\n", header)), 3);
+        assert_eq!(line(&format!("{}1: 1,2,3,\n", header)), 3);
+        assert_eq!(line("# DATA TYPE: toi\n# NUMBER ALTERNATIVES: 3\n1: 1,{2,3\n"), 3);
+        assert_eq!(
+            line("# DATA TYPE: cat\n# NUMBER ALTERNATIVES: 3\n# NUMBER CATEGORIES: 2\n1: {1,2,3}\n"),
+            4
+        );
+    }
+
+    #[test]
+    fn preflib_write() -> Result<(), TallyError> {
+        let mut ballots = BallotBox::<&str, u64>::new();
+        ballots.add_weighted(vec!["Alice", "Bob"], 3)?;
+        ballots.add_ranked(RankedBallot::from(vec![vec!["Carlos", "Bob"]]))?;
+        let election = Election::new("Example", ballots);
+        assert_eq!(election.data_type, DataType::Toi);
+
+        let output = election.to_string();
+        assert_eq!(
+            output,
+            "# TITLE: Example\n# DATA TYPE: toi\n# NUMBER ALTERNATIVES: 3\n# ALTERNATIVE NAME 1: Alice\n\
+             # ALTERNATIVE NAME 2: Bob\n# ALTERNATIVE NAME 3: Carlos\n# NUMBER VOTERS: 4\n# NUMBER UNIQUE ORDERS: 2\n\
+             3: 1,2\n1: {3,2}\n"
+        );
+        assert_eq!(parse::<u64>(&output)?.to_string(), output);
+
+        Ok(())
+    }
+}
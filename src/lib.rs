@@ -2,14 +2,59 @@
 //!
 //! ## Compatibility
 //!
-//! The `tallystick` crate currently needs nightly rust. It will move to stable when [trait specialization](https://github.com/rust-lang/rust/issues/31844) is stabilized.
+//! The `tallystick` crate builds on stable rust.
+//!
+//! The tallies only need `core` and `alloc`. Disabling the default `std` feature builds the crate with `#![no_std]`, for
+//! targets without the standard library. Without `std`, `build_graph()` is not available and the `parallel`,
+//! `cvr`, `cli` and `wasm-bindgen` features cannot be used.
+//!
+//! The crate builds for `wasm32-unknown-unknown`. The `wasm-bindgen` feature adds a JavaScript API in the
+//! [`wasm`](wasm/index.html) module.
+//!
+//! ## Determinism
+//!
+//! Tallying the same votes in the same order always produces the same output. Candidates are listed in the order they
+//! were first seen by a tally, and candidates that are tied (in `totals()`, `ranked()` or `winners()`) are listed in that same order.
+//! Since the order candidates are first seen depends on the order votes are added, output can be made independent of
+//! the order of the votes in two ways:
+//!
+//! - Declare candidates up-front by creating a tally with `with_candidates()` and a [`Candidates`](struct.Candidates.html)
+//!   registry. Candidates and ties are then listed in the declared order, followed by any write-in candidates.
+//! - If the candidate type implements `Ord`, call `sort_candidates()` after adding votes. Candidates and ties are then
+//!   listed in `Ord` order. With a registry, the declared candidates keep their order and only write-ins are sorted.
+//!   Candidates first seen after calling `sort_candidates()` are listed after the sorted candidates.
+//!   `merge()` sorts candidates this way after merging, so that tallies can be merged in any order.
+//!
+//! When using a float count type, sums may still differ in their least significant digits if votes are reordered;
+//! use an integer or rational count type for exact results. Rational count types such as `num_rational::Rational64`
+//! require the `rational` feature. Where election rules mandate a fixed number of decimal places, use
+//! [`FixedDecimal`](struct.FixedDecimal.html).
+//!
+//! ## Overflow
+//!
+//! Vote counts never silently wrap. Adding a vote that would overflow the count type (for example a `u16` count
+//! going past `u16::MAX`) returns [`TallyError::CountOverflow`](enum.TallyError.html#variant.CountOverflow).
+//! Methods that can't return an error, such as `merge()`, panic instead. Float count types don't overflow, but lose precision
+//! as they grow.
+//!
+//! ## Serialization
+//!
+//! With the `serde` feature enabled, every tally, [`BallotBox`](struct.BallotBox.html), [`RankedWinners`](struct.RankedWinners.html)
+//! and [`Quota`](enum.Quota.html) implements `Serialize` and `Deserialize`. A partial count can be saved and resumed later by
+//! deserializing it and continuing to add votes. Deserializing a tally whose state is inconsistent returns an error.
 //!
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(rust_2018_idioms)]
 #![warn(missing_docs)]
-#![feature(nll)]
-#![feature(specialization)]
-#![feature(crate_visibility_modifier)]
+// Explicit returns, `field: field` initialisation and `assert_eq!(.., true)` in tests are the house style.
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+#![cfg_attr(test, allow(clippy::bool_assert_comparison))]
+
+extern crate alloc;
+#[cfg(all(test, not(feature = "std")))]
+#[macro_use]
+extern crate std;
 
 /// Plurality voting is an electoral system in which each voter is allowed to vote for only one candidate
 /// and the candidate who polls the most among their counterparts (a plurality) is elected. It may be called
@@ -37,11 +82,28 @@ pub mod plurality;
 /// candidates. The winner is the most-approved candidate.
 pub mod approval;
 
+/// Proportional approval voting methods elect a committee from approval votes, so that groups of voters are represented
+/// in proportion to their size. Includes Proportional Approval Voting (PAV), sequential and reverse sequential PAV,
+/// Satisfaction Approval Voting (SAV) and Chamberlin–Courant.
+pub mod approval_pr;
+
+/// Phragmén's methods elect a committee from approval votes by spreading the load of each elected candidate as evenly
+/// as possible between the voters who approve of them. Includes Phragmén's sequential method and leximax-Phragmén.
+pub mod phragmen;
+
+/// Participatory budgeting selects projects with a total cost that fits within a budget, using approval or score votes.
+/// Includes the Method of Equal Shares, and greedy selection by utility or by utility per cost.
+pub mod pb;
+
 /// Score voting or "range voting" is an electoral system in which voters give each candidate a score,
 /// the scores are summed, and the candidate with the highest total is elected. It has been described
 /// by various other names including "evaluative voting", "utilitarian voting", and "the point system".
 pub mod score;
 
+/// Proportional score voting elects several winners from score votes, reducing the weight of votes whose candidates are elected.
+/// Includes Reweighted Range Voting, Sequentially Spent Score, and Allocated Score (STAR-PR).
+pub mod score_pr;
+
 /// The single transferable vote (STV) is a ranked choice voting system.
 /// Under STV, a voter has a single vote that is initially allocated to their most preferred candidate. Votes are totalled and a quota
 /// (the number of votes required to win) derived. If a candidate achieves quota, the candidate is elected and any surplus vote
@@ -85,14 +147,50 @@ pub mod schulze;
 /// ```
 pub mod borda;
 
+/// Bucklin voting is a ranked voting system that adds each vote's next choice to the count in every round,
+/// until a candidate has a majority. Includes the approval based Majority Choice Approval variant.
+pub mod bucklin;
+
+/// Coombs' method is a ranked voting system that eliminates candidates one round at a time, like instant runoff voting.
+/// Each round, the candidate ranked last by the most voters is eliminated, until a candidate has a majority of first preferences.
+pub mod coombs;
+
+/// Shared types for runoff methods, which count votes in rounds and eliminate candidates until a winner is found.
+pub mod runoff;
+
+/// Readers and writers for ballot file formats used by other election software.
+pub mod formats;
+
+/// JavaScript bindings, for tallying votes in the browser or in Node.js when compiled to WebAssembly with `wasm-bindgen`.
+///
+/// This module requires the `wasm-bindgen` feature.
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
 // Common Data Structures
 // ----------------------
 mod result;
 pub use crate::result::RankedWinners;
 
+mod ballot;
+pub use crate::ballot::BallotBox;
+pub use crate::ballot::RankedBallot;
+
+mod candidates;
+pub use crate::candidates::Candidates;
+
+mod policy;
+pub use crate::policy::BallotPolicy;
+pub use crate::policy::Duplicates;
+pub use crate::policy::SkippedRanks;
+pub use crate::policy::UnknownCandidates;
+
 mod quota;
 pub use crate::quota::Quota;
 
+mod fixed;
+pub use crate::fixed::FixedDecimal;
+
 mod traits;
 pub use crate::traits::Numeric;
 
@@ -112,3 +210,352 @@ pub(crate) fn check_duplicate<T: PartialEq>(slice: &[T]) -> Result<(), TallyErro
   }
   Ok(())
 }
+
+// Add a weight to a vote count, returning an error instead of overflowing.
+pub(crate) fn add_count<C: Numeric + Copy>(count: &mut C, weight: C) -> Result<(), TallyError> {
+  *count = (*count).checked_add(weight).ok_or(TallyError::CountOverflow)?;
+  Ok(())
+}
+
+// Get the order that sorts a list of candidates, leaving the first `declared` candidates where they are.
+// `order[i]` is the current position of the candidate that belongs at position `i`.
+pub(crate) fn sorted_order<T: Ord>(candidates: &[T], declared: usize) -> alloc::vec::Vec<usize> {
+  let declared = core::cmp::min(declared, candidates.len());
+  let mut order: alloc::vec::Vec<usize> = (0..candidates.len()).collect();
+  order[declared..].sort_by(|a, b| candidates[*a].cmp(&candidates[*b]));
+  return order;
+}
+
+// Merging and computing results can't return an error, so they panic rather than silently overflow.
+pub(crate) const COUNT_OVERFLOW_PANIC: &str = "tallystick: vote count overflowed the count type, use a larger count type";
+
+// We often convert `C` (vote count types) to and from small integers.
+// If we can't successfully convert a small integer into `C`, we panic since converting from a small integer is trivial.
+pub(crate) const C_FROM_PANIC: &str = "Cannot convert integer to C, this is likely caused by a bug in the ToPrimitive impl for the count type.";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use alloc::vec::Vec;
+  use rand::rngs::StdRng;
+  use rand::seq::SliceRandom;
+  use rand::{Rng, SeedableRng};
+  use core::fmt::Debug;
+
+  const CANDIDATES: [&str; 6] = ["Alice", "Bob", "Carlos", "Dave", "Erin", "Frank"];
+
+  // Random ranked votes, with a fixed seed so that failures can be reproduced.
+  fn random_votes(num_votes: usize) -> Vec<Vec<&'static str>> {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut votes = Vec::with_capacity(num_votes);
+    for _ in 0..num_votes {
+      let mut vote = CANDIDATES.to_vec();
+      vote.shuffle(&mut rng);
+      vote.truncate(rng.gen_range(1, CANDIDATES.len() + 1));
+      votes.push(vote);
+    }
+    return votes;
+  }
+
+  // Run `tally` many times with shuffled votes, and check that it always produces the same output.
+  fn assert_deterministic<R, F>(tally: F)
+  where
+    R: PartialEq + Debug,
+    F: Fn(&[Vec<&'static str>]) -> R,
+  {
+    let mut votes = random_votes(500);
+    let expected = tally(&votes);
+
+    let mut rng = StdRng::seed_from_u64(2);
+    for _ in 0..50 {
+      votes.shuffle(&mut rng);
+      assert_eq!(tally(&votes), expected);
+    }
+  }
+
+  #[test]
+  fn deterministic_output() {
+    let all = CANDIDATES.to_vec();
+
+    assert_deterministic(|votes| {
+      let mut tally = plurality::DefaultPluralityTally::with_candidates(2, Candidates::new(all.clone()));
+      for vote in votes {
+        tally.add_ref(&vote[0]).unwrap();
+      }
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = approval::DefaultApprovalTally::with_candidates(2, Candidates::new(all.clone()));
+      for vote in votes {
+        tally.add_ref(vote).unwrap();
+      }
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = score::DefaultScoreTally::with_candidates(2, Candidates::new(all.clone()));
+      for vote in votes {
+        tally.add(vote.iter().enumerate().map(|(i, candidate)| (*candidate, i as u64 % 3)).collect()).unwrap();
+      }
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let candidates = Candidates::new(all.clone());
+      let mut tally = borda::DefaultBordaTally::with_candidates(2, borda::Variant::ModifiedClassicBorda, candidates);
+      for vote in votes {
+        tally.add_ref(vote).unwrap();
+      }
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = condorcet::DefaultCondorcetTally::with_candidates(2, Candidates::new(all.clone()));
+      for vote in votes {
+        tally.add_ref(&vote[..2.min(vote.len())]).unwrap();
+      }
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let candidates = Candidates::new(all.clone());
+      let mut tally = schulze::DefaultSchulzeTally::with_candidates(2, schulze::Variant::Winning, candidates);
+      for vote in votes {
+        tally.add_ref(vote).unwrap();
+      }
+      (
+        tally.candidates(),
+        tally.totals(),
+        tally.strongest_paths(),
+        tally.ranked(),
+        tally.winners(),
+      )
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = stv::Tally::<&str, u64>::with_candidates(3, Quota::Droop, Candidates::new(all.clone()));
+      for vote in votes {
+        tally.add_ref(vote).unwrap();
+      }
+      tally.winners()
+    });
+  }
+
+  #[test]
+  fn deterministic_output_sorted() {
+    assert_deterministic(|votes| {
+      let mut tally = plurality::DefaultPluralityTally::new(2);
+      for vote in votes {
+        tally.add_ref(&vote[0]).unwrap();
+      }
+      tally.sort_candidates();
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = approval::DefaultApprovalTally::new(2);
+      for vote in votes {
+        tally.add_ref(vote).unwrap();
+      }
+      tally.sort_candidates();
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = score::DefaultScoreTally::new(2);
+      for vote in votes {
+        tally.add(vote.iter().enumerate().map(|(i, candidate)| (*candidate, i as u64 % 3)).collect()).unwrap();
+      }
+      tally.sort_candidates();
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = borda::DefaultBordaTally::new(2, borda::Variant::ModifiedClassicBorda);
+      for vote in votes {
+        tally.add_ref(vote).unwrap();
+      }
+      tally.sort_candidates();
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = condorcet::DefaultCondorcetTally::new(2);
+      for vote in votes {
+        tally.add_ref(&vote[..2.min(vote.len())]).unwrap();
+      }
+      tally.sort_candidates();
+      (tally.candidates(), tally.totals(), tally.ranked(), tally.winners())
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = schulze::DefaultSchulzeTally::new(2, schulze::Variant::Winning);
+      for vote in votes {
+        tally.add_ref(vote).unwrap();
+      }
+      tally.sort_candidates();
+      (
+        tally.candidates(),
+        tally.totals(),
+        tally.strongest_paths(),
+        tally.ranked(),
+        tally.winners(),
+      )
+    });
+
+    assert_deterministic(|votes| {
+      let mut tally = stv::Tally::<&str, u64>::new(3, Quota::Droop);
+      for vote in votes {
+        tally.add_ref(vote).unwrap();
+      }
+      tally.sort_candidates();
+      tally.winners()
+    });
+  }
+
+  // Split votes between two tallies, and check that merging them either way round gives the same output.
+  fn assert_merge_commutes<T, R, N, A, M, O>(new: N, add: A, merge: M, output: O)
+  where
+    R: PartialEq + Debug,
+    N: Fn() -> T,
+    A: Fn(&mut T, &[&'static str]),
+    M: Fn(&mut T, T),
+    O: Fn(&T) -> R,
+  {
+    let votes = random_votes(200);
+    let (first, second) = votes.split_at(votes.len() / 2);
+    let tally = |votes: &[Vec<&'static str>]| {
+      let mut tally = new();
+      // Add the votes backwards, so that each tally sees the candidates in a different order.
+      for vote in votes.iter().rev() {
+        add(&mut tally, vote);
+      }
+      tally
+    };
+
+    let mut forward = tally(first);
+    merge(&mut forward, tally(second));
+    let mut backward = tally(second);
+    merge(&mut backward, tally(first));
+    assert_eq!(output(&forward), output(&backward));
+  }
+
+  #[test]
+  fn merge_order_independent() {
+    assert_merge_commutes(
+      || plurality::DefaultPluralityTally::new(2),
+      |tally, vote| tally.add_ref(&vote[0]).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.totals(), tally.ranked(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || approval::DefaultApprovalTally::new(2),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.totals(), tally.ranked(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || score::DefaultScoreTally::new(2),
+      |tally, vote| tally.add(vote.iter().enumerate().map(|(i, candidate)| (*candidate, i as u64 % 3)).collect()).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.totals(), tally.ranked(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || borda::DefaultBordaTally::new(2, borda::Variant::ModifiedClassicBorda),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.totals(), tally.ranked(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || condorcet::DefaultCondorcetTally::new(2),
+      |tally, vote| tally.add_ref(&vote[..2.min(vote.len())]).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.totals(), tally.ranked(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || condorcet::DefaultCondorcetTally::with_candidates(2, Candidates::new(vec!["Frank", "Erin"]).write_ins(true)),
+      |tally, vote| tally.add_ref(&vote[..2.min(vote.len())]).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.totals(), tally.ranked(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || schulze::DefaultSchulzeTally::new(2, schulze::Variant::Winning),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.totals(), tally.strongest_paths(), tally.ranked(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || stv::Tally::<&str, u64>::new(3, Quota::Droop),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.rounds(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || stv::Tally::<&str, u64>::new(1, Quota::Droop),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.rounds(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || coombs::DefaultCoombsTally::new(2),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.rounds(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || bucklin::DefaultBucklinTally::new(2, bucklin::Variant::Bucklin),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.rounds(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || approval_pr::DefaultApprovalPrTally::new(2, approval_pr::Variant::Proportional),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || phragmen::DefaultPhragmenTally::new(2, phragmen::Variant::Sequential),
+      |tally, vote| tally.add_ref(vote).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.winners()),
+    );
+    assert_merge_commutes(
+      || score_pr::DefaultScorePrTally::new(2, 2.0, score_pr::Variant::SequentiallySpent),
+      |tally, vote| tally.add(vote.iter().enumerate().map(|(i, candidate)| (*candidate, (i % 3) as f64)).collect()).unwrap(),
+      |tally, other| tally.merge(other),
+      |tally| (tally.candidates(), tally.rounds(), tally.winners()),
+    );
+  }
+
+  #[test]
+  fn sorted_ties() {
+    let mut tally = plurality::DefaultPluralityTally::new(1);
+    tally.add("Carlos").unwrap();
+    tally.add("Bob").unwrap();
+    tally.add("Alice").unwrap();
+    tally.sort_candidates();
+    assert_eq!(tally.candidates(), vec!["Alice", "Bob", "Carlos"]);
+    assert_eq!(tally.winners().into_unranked(), vec!["Alice", "Bob", "Carlos"]);
+
+    // Declared candidates keep their order, and write-ins are sorted after them.
+    let candidates = Candidates::new(vec!["Frank", "Erin"]).write_ins(true);
+    let mut tally = condorcet::DefaultCondorcetTally::with_candidates(1, candidates);
+    tally.add(vec!["Dave", "Frank"]).unwrap();
+    tally.add(vec!["Carlos", "Erin"]).unwrap();
+    tally.sort_candidates();
+    assert_eq!(tally.candidates(), vec!["Frank", "Erin", "Carlos", "Dave"]);
+    assert_eq!(
+      tally.totals(),
+      vec![
+        (("Frank", "Erin"), 1),
+        (("Erin", "Frank"), 1),
+        (("Carlos", "Frank"), 1),
+        (("Carlos", "Erin"), 1),
+        (("Dave", "Frank"), 1),
+        (("Dave", "Erin"), 1),
+      ]
+    );
+  }
+}
@@ -0,0 +1,730 @@
+//! The `tallystick` command-line tool tallies a file of ballots with any of the supported methods.
+//!
+//! It requires the `cli` feature: `cargo install tallystick --features cli`. Run `tallystick --help` for usage.
+
+// Explicit returns and `field: field` initialisation are the house style.
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use clap::{crate_version, App, Arg, ArgMatches};
+use num_traits::cast::{FromPrimitive, ToPrimitive};
+use serde_json::{json, Value};
+use std::fmt;
+use std::fs;
+use std::ops::AddAssign;
+use std::path::Path;
+use std::process;
+
+use tallystick::approval::ApprovalTally;
+use tallystick::borda::{self, BordaTally};
+use tallystick::condorcet::CondorcetTally;
+use tallystick::formats::{abif, blt, preflib};
+use tallystick::plurality::PluralityTally;
+use tallystick::schulze::{self, SchulzeTally};
+use tallystick::score::ScoreTally;
+use tallystick::runoff::Round;
+use tallystick::stv;
+use tallystick::{BallotBox, FixedDecimal, Numeric, Quota, RankedBallot, RankedWinners};
+
+const FORMATS: &[&str] = &["csv", "blt", "preflib", "abif", "json"];
+const METHODS: &[&str] = &["plurality", "approval", "score", "borda", "condorcet", "schulze", "stv"];
+
+const AFTER_HELP: &str = "INPUT FORMATS:
+    csv      One ballot per row, listing candidates in order of preference. For the score method, the first row
+             names the candidates and each following row gives a score for each of them.
+    blt      The BLT format used by OpenSTV and other STV software. The number of seats is read from the file.
+    preflib  PrefLib data (.soc, .soi, .toc, .toi or .cat).
+    abif     The Aggregated Ballot Information Format, with rankings and ratings.
+    json     An array of ballots. Each ballot is an array of candidates in order of preference, an array of ranks
+             (each an array of tied candidates), or an object mapping candidates to scores. A ballot may also be
+             written as {\"ballot\": <ballot>, \"weight\": <number>}.
+
+The format is detected from the file extension unless --format is given.
+
+Votes are counted with integers when every weight and score is a whole number, or with decimals truncated to 9
+places for stv, the dowdall borda variant, the ratio schulze variant and tied rankings. Other ballots are counted with
+floating point numbers. In JSON output, decimal counts that are not whole numbers are written as strings.";
+
+// Ballots loaded from a file.
+// Weights and scores are loaded as `f64`, and converted to the count type chosen for the tally.
+#[derive(Default)]
+struct Ballots {
+    ranked: BallotBox<String, f64>,
+    approvals: Option<BallotBox<String, f64>>, // Approval votes, if they differ from the ranked ballots
+    scores: Vec<(Vec<(String, f64)>, f64)>,
+    seats: Option<u32>, // The number of seats, if the file specifies it
+}
+
+// How to count the ballots.
+struct Options<'a> {
+    method: &'a str,
+    variant: Option<&'a str>,
+    quota: Option<&'a str>,
+    seats: Option<u32>,
+}
+
+// The result of a tally.
+struct Outcome {
+    winners: RankedWinners<String>,
+    totals: Totals,
+    rounds: Vec<Round<String, Count>>,
+}
+
+enum Totals {
+    None,
+    Candidates(Vec<(String, Count)>),
+    Pairwise(Vec<((String, String), Count)>),
+}
+
+// The count type used when a candidate can get part of a vote.
+type Decimal = FixedDecimal<9>;
+
+// A vote count, in the count type used for the tally.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Count {
+    Integer(u64),
+    Decimal(Decimal),
+    Float(f64),
+}
+
+impl From<u64> for Count {
+    fn from(count: u64) -> Self {
+        return Count::Integer(count);
+    }
+}
+
+impl From<Decimal> for Count {
+    fn from(count: Decimal) -> Self {
+        return Count::Decimal(count);
+    }
+}
+
+impl From<f64> for Count {
+    fn from(count: f64) -> Self {
+        return Count::Float(count);
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Count::Integer(count) => write!(f, "{}", count),
+            Count::Decimal(count) => write!(f, "{}", count.to_string().trim_end_matches('0').trim_end_matches('.')),
+            Count::Float(count) => write!(f, "{}", count),
+        }
+    }
+}
+
+impl Count {
+    // Whole numbers are written as JSON numbers, and decimals as strings such as "3.5" so that they stay exact.
+    fn to_json(self) -> Value {
+        match self {
+            Count::Integer(count) => json!(count),
+            Count::Decimal(count) if count.floor() == count => json!(count.to_i64()),
+            Count::Decimal(_) => json!(self.to_string()),
+            Count::Float(count) => json!(count),
+        }
+    }
+}
+
+fn main() {
+    let matches = App::new("tallystick")
+        .version(crate_version!())
+        .about("Tally a file of ballots")
+        .after_help(AFTER_HELP)
+        .arg(Arg::with_name("FILE").help("The ballot file to tally").required(true))
+        .arg(
+            Arg::with_name("method")
+                .long("method")
+                .short("m")
+                .help("The voting method")
+                .takes_value(true)
+                .possible_values(METHODS)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("seats")
+                .long("seats")
+                .short("s")
+                .help("The number of winners [default: 1, or the number of seats in a BLT file]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("variant")
+                .long("variant")
+                .help("The borda (borda, classic, dowdall, modified) or schulze (winning, margin, ratio, losing) variant")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("quota")
+                .long("quota")
+                .help("The stv quota (droop, hare, hagenbach, imperiali)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .short("f")
+                .help("The input format")
+                .takes_value(true)
+                .possible_values(FORMATS),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .help("The output format")
+                .takes_value(true)
+                .possible_values(&["table", "json"])
+                .default_value("table"),
+        )
+        .get_matches();
+
+    if let Err(e) = run(&matches) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+// Load, count and print the ballots given on the command line.
+fn run(matches: &ArgMatches<'_>) -> Result<(), String> {
+    let path = matches.value_of("FILE").unwrap();
+    let format = match matches.value_of("format") {
+        Some(format) => format,
+        None => detect_format(path)?,
+    };
+    let seats = match matches.value_of("seats") {
+        Some(seats) => Some(seats.parse().map_err(|_| format!("invalid number of seats '{}'", seats))?),
+        None => None,
+    };
+    let options = Options {
+        method: matches.value_of("method").unwrap(),
+        variant: matches.value_of("variant"),
+        quota: matches.value_of("quota"),
+        seats: seats,
+    };
+
+    let input = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+    let ballots = load(format, &input, options.method == "score")?;
+    let outcome = count(&options, &ballots)?;
+    match matches.value_of("output") {
+        Some("json") => println!("{}", to_json(&options, &outcome)),
+        _ => print!("{}", to_table(&outcome)),
+    }
+    return Ok(());
+}
+
+// Detect the input format from the file extension.
+fn detect_format(path: &str) -> Result<&'static str, String> {
+    let extension = Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or("");
+    if preflib::DataType::from_extension(extension).is_some() {
+        return Ok("preflib");
+    }
+    match extension.to_lowercase().as_str() {
+        "csv" => Ok("csv"),
+        "blt" => Ok("blt"),
+        "abif" => Ok("abif"),
+        "json" => Ok("json"),
+        _ => Err(format!("could not detect the format of {}, use --format to specify it", path)),
+    }
+}
+
+// Load ballots in the given format.
+fn load(format: &str, input: &str, scored: bool) -> Result<Ballots, String> {
+    let mut ballots = Ballots::default();
+    match format {
+        "csv" if scored => {
+            let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(input.as_bytes());
+            let candidates: Vec<String> = reader
+                .headers()
+                .map_err(|e| e.to_string())?
+                .iter()
+                .map(|candidate| candidate.trim().to_string())
+                .collect();
+            for record in reader.records() {
+                let record = record.map_err(|e| e.to_string())?;
+                let mut scores = Vec::new();
+                for (candidate, score) in candidates.iter().zip(record.iter().map(|score| score.trim())) {
+                    if !score.is_empty() {
+                        let score = score.parse().map_err(|_| format!("invalid score '{}'", score))?;
+                        scores.push((candidate.clone(), score));
+                    }
+                }
+                ballots.scores.push((scores, 1.0));
+            }
+        }
+        "csv" => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(input.as_bytes());
+            for record in reader.records() {
+                let record = record.map_err(|e| e.to_string())?;
+                let vote: Vec<String> = record
+                    .iter()
+                    .map(|candidate| candidate.trim())
+                    .filter(|candidate| !candidate.is_empty())
+                    .map(|candidate| candidate.to_string())
+                    .collect();
+                ballots.ranked.add(vote).map_err(|e| e.to_string())?;
+            }
+        }
+        "blt" => {
+            let election = blt::parse::<f64>(input).map_err(|e| e.to_string())?;
+            ballots.ranked = election.ballots;
+            ballots.seats = Some(election.seats);
+        }
+        "preflib" => {
+            let election = preflib::parse::<f64>(input).map_err(|e| e.to_string())?;
+            if election.data_type == preflib::DataType::Cat {
+                ballots.approvals = Some(election.approvals());
+            }
+            ballots.ranked = election.ballots;
+        }
+        "abif" => {
+            let election = abif::parse::<f64>(input).map_err(|e| e.to_string())?;
+            ballots.ranked = election.ballot_box().map_err(|e| e.to_string())?;
+            ballots.scores = election.scores();
+        }
+        "json" => {
+            let json: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+            let json = match json {
+                Value::Array(json) => json,
+                _ => return Err("JSON ballots must be an array".to_string()),
+            };
+            for ballot in json {
+                load_json_ballot(&mut ballots, &ballot)?;
+            }
+        }
+        _ => return Err(format!("unknown format '{}'", format)),
+    }
+    return Ok(ballots);
+}
+
+// Load a single JSON ballot.
+fn load_json_ballot(ballots: &mut Ballots, ballot: &Value) -> Result<(), String> {
+    let invalid = || format!("invalid ballot {}", ballot);
+
+    // A weighted ballot, eg `{"ballot": ["Alice", "Bob"], "weight": 3}`
+    let (ballot, weight) = match (ballot.get("ballot"), ballot.get("weight")) {
+        (Some(inner), Some(weight)) => (inner, weight.as_f64().ok_or_else(invalid)?),
+        (Some(inner), None) => (inner, 1.0),
+        _ => (ballot, 1.0),
+    };
+
+    match ballot {
+        // Scores, eg `{"Alice": 5, "Bob": 3}`
+        Value::Object(scores) => {
+            let mut vote = Vec::with_capacity(scores.len());
+            for (candidate, score) in scores {
+                vote.push((candidate.clone(), score.as_f64().ok_or_else(invalid)?));
+            }
+            ballots.scores.push((vote, weight));
+        }
+        // A ranked ballot, eg `["Alice", "Bob"]` or `[["Alice", "Bob"], ["Carlos"]]`
+        Value::Array(ranks) => {
+            let mut vote = RankedBallot::new();
+            for rank in ranks {
+                match rank {
+                    Value::String(candidate) => vote.push(candidate.clone()),
+                    Value::Array(tied) => {
+                        let tied: Option<Vec<String>> = tied.iter().map(|candidate| candidate.as_str().map(String::from)).collect();
+                        vote.push_tied(tied.ok_or_else(invalid)?);
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+            ballots.ranked.add_ranked_weighted(vote, weight).map_err(|e| e.to_string())?;
+        }
+        _ => return Err(invalid()),
+    }
+    Ok(())
+}
+
+// Tally the ballots.
+fn count(options: &Options<'_>, ballots: &Ballots) -> Result<Outcome, String> {
+    if options.variant.is_some() && options.method != "borda" && options.method != "schulze" {
+        return Err("--variant can only be used with the borda and schulze methods".to_string());
+    }
+    if options.quota.is_some() && options.method != "stv" {
+        return Err("--quota can only be used with the stv method".to_string());
+    }
+
+    // Count with integers when every weight and score is a whole number, using decimals if a candidate can get part of a
+    // vote. Decimals are used instead of fractions, since the denominators of fractions overflow on large elections.
+    let whole = |value: f64| value >= 0.0 && value.fract() == 0.0;
+    let exact = ballots.ranked.iter().all(|(_, weight)| whole(weight))
+        && ballots.approvals.iter().all(|approvals| approvals.iter().all(|(_, weight)| whole(weight)))
+        && ballots.scores.iter().all(|(scores, weight)| whole(*weight) && scores.iter().all(|(_, score)| whole(*score)));
+    let ties = ballots.ranked.has_ties();
+    let fractional = match options.method {
+        "stv" => true,
+        "borda" => ties || options.variant == Some("dowdall"),
+        "schulze" => ties || options.variant == Some("ratio"),
+        "plurality" | "condorcet" => ties,
+        _ => false,
+    };
+    return match (exact, fractional) {
+        (true, false) => tally::<u64>(options, ballots),
+        (true, true) => tally::<Decimal>(options, ballots),
+        (false, _) => tally::<f64>(options, ballots),
+    };
+}
+
+// Tally the ballots with the given count type.
+fn tally<C>(options: &Options<'_>, ballots: &Ballots) -> Result<Outcome, String>
+where
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+    Count: From<C>,
+{
+    let seats = options.seats.or(ballots.seats).unwrap_or(1);
+    let ranked = &convert::<C>(&ballots.ranked)?;
+    let outcome = match options.method {
+        "plurality" => {
+            let mut tally = PluralityTally::<String, C>::new(seats);
+            tally.add_ballot_box(ranked).map_err(|e| e.to_string())?;
+            Outcome {
+                winners: tally.winners(),
+                totals: Totals::Candidates(convert_totals(tally.totals())),
+                rounds: Vec::new(),
+            }
+        }
+        "approval" => {
+            let approvals = match &ballots.approvals {
+                Some(approvals) => convert::<C>(approvals)?,
+                None => ranked.clone(),
+            };
+            let mut tally = ApprovalTally::<String, C>::new(seats);
+            tally
+                .add_ballot_box(&approvals)
+                .map_err(|e| e.to_string())?;
+            Outcome {
+                winners: tally.winners(),
+                totals: Totals::Candidates(convert_totals(tally.totals())),
+                rounds: Vec::new(),
+            }
+        }
+        "score" => {
+            if ballots.scores.is_empty() {
+                return Err("the score method requires ballots with scores".to_string());
+            }
+            let mut tally = ScoreTally::<String, C>::new(seats);
+            for (scores, weight) in ballots.scores.iter() {
+                let scores = scores
+                    .iter()
+                    .map(|(candidate, score)| Ok((candidate.clone(), to_count::<C>(*score)?)))
+                    .collect::<Result<Vec<(String, C)>, String>>()?;
+                tally.add_weighted(scores, to_count(*weight)?).map_err(|e| e.to_string())?;
+            }
+            Outcome {
+                winners: tally.winners(),
+                totals: Totals::Candidates(convert_totals(tally.totals())),
+                rounds: Vec::new(),
+            }
+        }
+        "borda" => {
+            let variant = match options.variant.unwrap_or("borda") {
+                "borda" => borda::Variant::Borda,
+                "classic" => borda::Variant::ClassicBorda,
+                "dowdall" => borda::Variant::Dowdall,
+                "modified" => borda::Variant::ModifiedClassicBorda,
+                variant => return Err(format!("unknown borda variant '{}'", variant)),
+            };
+            let mut tally = BordaTally::<String, C>::new(seats, variant);
+            tally.add_ballot_box(ranked).map_err(|e| e.to_string())?;
+            Outcome {
+                winners: tally.winners(),
+                totals: Totals::Candidates(convert_totals(tally.totals())),
+                rounds: Vec::new(),
+            }
+        }
+        "condorcet" => {
+            let mut tally = CondorcetTally::<String, C>::new(seats);
+            tally.add_ballot_box(ranked).map_err(|e| e.to_string())?;
+            Outcome {
+                winners: tally.winners(),
+                totals: Totals::Pairwise(convert_totals(tally.totals())),
+                rounds: Vec::new(),
+            }
+        }
+        "schulze" => {
+            let variant = match options.variant.unwrap_or("winning") {
+                "winning" => schulze::Variant::Winning,
+                "margin" => schulze::Variant::Margin,
+                "ratio" => schulze::Variant::Ratio,
+                "losing" => schulze::Variant::Losing,
+                variant => return Err(format!("unknown schulze variant '{}'", variant)),
+            };
+            let mut tally = SchulzeTally::<String, C>::new(seats, variant);
+            tally.add_ballot_box(ranked).map_err(|e| e.to_string())?;
+            Outcome {
+                winners: tally.winners(),
+                totals: Totals::Pairwise(convert_totals(tally.totals())),
+                rounds: Vec::new(),
+            }
+        }
+        "stv" => {
+            let quota = match options.quota.unwrap_or("droop") {
+                "droop" => Quota::Droop,
+                "hare" => Quota::Hare,
+                "hagenbach" => Quota::Hagenbach,
+                "imperiali" => Quota::Imperiali,
+                quota => return Err(format!("unknown stv quota '{}'", quota)),
+            };
+            let mut tally = stv::Tally::<String, C>::new(seats, quota);
+            tally.add_ballot_box(ranked).map_err(|e| e.to_string())?;
+            let rounds = tally.rounds().into_iter().map(|round| Round {
+                totals: convert_totals(round.totals),
+                elected: round.elected,
+                eliminated: round.eliminated,
+            });
+            Outcome {
+                winners: tally.winners(),
+                totals: Totals::None,
+                rounds: rounds.collect(),
+            }
+        }
+        method => return Err(format!("unknown method '{}'", method)),
+    };
+    return Ok(outcome);
+}
+
+// Convert ballots loaded with `f64` weights to another count type.
+fn convert<C>(ballots: &BallotBox<String, f64>) -> Result<BallotBox<String, C>, String>
+where
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    let mut converted = BallotBox::with_capacity(ballots.candidates().len(), ballots.len());
+    for (ballot, weight) in ballots.iter() {
+        converted.add_ranked_weighted(ballot, to_count(weight)?).map_err(|e| e.to_string())?;
+    }
+    return Ok(converted);
+}
+
+// Convert a weight or score to a count type.
+fn to_count<C: FromPrimitive>(value: f64) -> Result<C, String> {
+    return C::from_f64(value).ok_or_else(|| format!("{} is too large to count", value));
+}
+
+// Convert a list of totals to `Count`s.
+fn convert_totals<K, C>(totals: Vec<(K, C)>) -> Vec<(K, Count)>
+where
+    Count: From<C>,
+{
+    return totals.into_iter().map(|(key, count)| (key, Count::from(count))).collect();
+}
+
+// Format the outcome as a human-readable table.
+fn to_table(outcome: &Outcome) -> String {
+    let mut table = String::from("Winners\n");
+    for (winner, rank) in outcome.winners.iter() {
+        table.push_str(&format!("  {:>3}  {}\n", rank + 1, winner));
+    }
+
+    let rows: Vec<(String, Count)> = match &outcome.totals {
+        Totals::None => Vec::new(),
+        Totals::Candidates(totals) => totals.clone(),
+        Totals::Pairwise(totals) => totals.iter().map(|((a, b), votes)| (format!("{} over {}", a, b), *votes)).collect(),
+    };
+    if !rows.is_empty() {
+        table.push_str("\nTotals\n");
+        push_rows(&mut table, &rows);
+    }
+
+    for (number, round) in outcome.rounds.iter().enumerate() {
+        table.push_str(&format!("\nRound {}\n", number + 1));
+        push_rows(&mut table, &round.totals);
+        if !round.elected.is_empty() {
+            table.push_str(&format!("  Elected: {}\n", round.elected.join(", ")));
+        }
+        if !round.eliminated.is_empty() {
+            table.push_str(&format!("  Eliminated: {}\n", round.eliminated.join(", ")));
+        }
+    }
+    return table;
+}
+
+// Add aligned rows of labels and vote counts to a table.
+fn push_rows(table: &mut String, rows: &[(String, Count)]) {
+    let width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    for (label, votes) in rows {
+        table.push_str(&format!("  {:width$}  {}\n", label, votes, width = width));
+    }
+}
+
+// Format the outcome as JSON.
+fn to_json(options: &Options<'_>, outcome: &Outcome) -> String {
+    let winners: Vec<Value> = outcome
+        .winners
+        .iter()
+        .map(|(winner, rank)| json!({"candidate": winner, "rank": rank + 1}))
+        .collect();
+    let totals: Value = match &outcome.totals {
+        Totals::None => Value::Null,
+        Totals::Candidates(totals) => totals
+            .iter()
+            .map(|(candidate, votes)| json!({"candidate": candidate, "votes": votes.to_json()}))
+            .collect(),
+        Totals::Pairwise(totals) => totals
+            .iter()
+            .map(|((a, b), votes)| json!({"candidate": a, "over": b, "votes": votes.to_json()}))
+            .collect(),
+    };
+    let rounds: Vec<Value> = outcome
+        .rounds
+        .iter()
+        .map(|round| {
+            let totals: Vec<Value> = round
+                .totals
+                .iter()
+                .map(|(candidate, votes)| json!({"candidate": candidate, "votes": votes.to_json()}))
+                .collect();
+            json!({"totals": totals, "elected": round.elected, "eliminated": round.eliminated})
+        })
+        .collect();
+    let output = json!({
+        "method": options.method,
+        "winners": winners,
+        "totals": totals,
+        "rounds": rounds,
+    });
+    return serde_json::to_string_pretty(&output).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(method: &str) -> Options<'_> {
+        Options {
+            method: method,
+            variant: None,
+            quota: None,
+            seats: None,
+        }
+    }
+
+    #[test]
+    fn cli_csv() -> Result<(), String> {
+        let ballots = load("csv", "Alice,Bob,Carlos\nBob,Alice\n\"Carlos\", Alice\nAlice\n", false)?;
+        assert_eq!(ballots.ranked.total(), 4.0);
+        let outcome = count(&options("plurality"), &ballots)?;
+        assert_eq!(outcome.winners.into_unranked(), vec!["Alice".to_string()]);
+
+        let ballots = load("csv", "Alice,Bob,Carlos\n5,3,\n1,4,2\n", true)?;
+        let outcome = count(&options("score"), &ballots)?;
+        assert_eq!(
+            to_table(&outcome),
+            "Winners\n    1  Bob\n\nTotals\n  Bob     7\n  Alice   6\n  Carlos  2\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn cli_json() -> Result<(), String> {
+        let input = r#"[["Alice", "Bob"], {"ballot": ["Bob", ["Carlos", "Alice"]], "weight": 2}, {"Alice": 3, "Bob": 1}]"#;
+        let ballots = load("json", input, false)?;
+        assert_eq!(ballots.ranked.total(), 3.0);
+        assert_eq!(ballots.scores.len(), 1);
+
+        let mut schulze = options("schulze");
+        schulze.variant = Some("margin");
+        let outcome = count(&schulze, &ballots)?;
+        assert_eq!(outcome.winners.into_unranked(), vec!["Bob".to_string()]);
+
+        let mut stv = options("stv");
+        stv.quota = Some("hare");
+        let outcome = count(&stv, &ballots)?;
+        let json: Value = serde_json::from_str(&to_json(&stv, &outcome)).unwrap();
+        assert_eq!(json["winners"], json!([{"candidate": "Bob", "rank": 1}]));
+
+        assert!(load("json", r#"[[1, 2]]"#, false).is_err());
+        assert!(count(&stv, &Ballots::default()).is_ok());
+        assert!(count(&options("score"), &ballots).is_ok());
+        stv.variant = Some("margin");
+        assert!(count(&stv, &ballots).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cli_rounds() -> Result<(), String> {
+        let input = "Alice,Bob\nAlice,Bob\nAlice,Bob\nAlice,Bob\nAlice,Carlos\nBob\nCarlos\n";
+        let ballots = load("csv", input, false)?;
+        let mut stv = options("stv");
+        stv.seats = Some(2);
+        let outcome = count(&stv, &ballots)?;
+
+        // Alice's surplus is transferred as a decimal, since her votes can't be split into whole numbers.
+        assert_eq!(
+            to_table(&outcome),
+            "Winners\n    1  Alice\n    2  Bob\n\n\
+             Round 1\n  Alice   5\n  Bob     1\n  Carlos  1\n  Elected: Alice\n\n\
+             Round 2\n  Bob     2.6\n  Carlos  1.4\n  Eliminated: Carlos\n\n\
+             Round 3\n  Bob  2.6\n  Elected: Bob\n"
+        );
+        let json: Value = serde_json::from_str(&to_json(&stv, &outcome)).unwrap();
+        assert_eq!(json["rounds"][0]["totals"][0], json!({"candidate": "Alice", "votes": 5}));
+        assert_eq!(json["rounds"][1]["totals"][0], json!({"candidate": "Bob", "votes": "2.6"}));
+        assert_eq!(json["rounds"][1]["eliminated"], json!(["Carlos"]));
+
+        // Integers are used when no fractions are needed, and floats for fractional weights.
+        let outcome = count(&options("plurality"), &ballots)?;
+        assert!(matches!(outcome.totals, Totals::Candidates(ref totals) if totals[0] == ("Alice".to_string(), Count::Integer(5))));
+        let ballots = load("json", r#"[{"ballot": ["Alice"], "weight": 0.5}]"#, false)?;
+        let outcome = count(&options("plurality"), &ballots)?;
+        assert!(matches!(outcome.totals, Totals::Candidates(ref totals) if totals[0] == ("Alice".to_string(), Count::Float(0.5))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cli_large_stv() -> Result<(), String> {
+        // Surpluses are transferred several times without overflowing, and the winners agree with a floating point count.
+        let candidates: Vec<String> = (0..20).map(|c| format!("C{}", c)).collect();
+        let mut ballots = Ballots::default();
+        let mut seed: u64 = 12345;
+        for _ in 0..50021 {
+            let mut vote: Vec<String> = Vec::new();
+            while vote.len() < 8 {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                // Favour the first few candidates, so that several of them are elected with a surplus.
+                let candidate = &candidates[((seed >> 33) % 400) as usize * ((seed >> 13) % 20) as usize / 400];
+                if !vote.contains(candidate) {
+                    vote.push(candidate.clone());
+                }
+            }
+            ballots.ranked.add(vote).unwrap();
+        }
+
+        let mut stv = options("stv");
+        stv.seats = Some(7);
+        stv.quota = Some("droop");
+        let outcome = count(&stv, &ballots)?;
+        assert_eq!(outcome.winners.len(), 7);
+        let surpluses = outcome
+            .rounds
+            .iter()
+            .filter(|round| !round.elected.is_empty() && round.eliminated.is_empty())
+            .count();
+        assert!(surpluses >= 3);
+        let float = tally::<f64>(&stv, &ballots)?;
+        assert_eq!(outcome.winners.into_unranked(), float.winners.into_unranked());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cli_formats() -> Result<(), String> {
+        assert_eq!(detect_format("ballots.blt")?, "blt");
+        assert_eq!(detect_format("ballots.TOI")?, "preflib");
+        assert!(detect_format("ballots.txt").is_err());
+
+        // The number of seats is taken from BLT files.
+        let ballots = load("blt", "3 2\n4 1 2 0\n3 2 0\n2 3 0\n0\n\"Alice\"\n\"Bob\"\n\"Carlos\"\n", false)?;
+        let outcome = count(&options("stv"), &ballots)?;
+        assert_eq!(outcome.winners.len(), 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,496 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::check_duplicate;
+use super::result::RankedWinners;
+use super::Numeric;
+use super::TallyError;
+use super::{add_count, COUNT_OVERFLOW_PANIC};
+
+/// Specifies how projects are selected within the budget.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Variant {
+    /// The Method of Equal Shares. The budget is split equally between voters. Each round, the project is selected that
+    /// its voters can afford while paying the least per unit of utility, with each voter paying in proportion to their
+    /// utility for the project, or all of their remaining share of the budget if that is less. The count ends when
+    /// no remaining project can be afforded by its voters.
+    ///
+    /// This usually leaves part of the budget unspent, so a [`Completion`](enum.Completion.html) method is used to spend the rest.
+    EqualShares(Completion),
+
+    /// Greedy by utility. Projects are selected in order of their total utility, skipping projects that don't fit in
+    /// the remaining budget. With approval votes, this selects the most approved projects.
+    GreedyUtility,
+
+    /// Greedy by cost-utility. Projects are selected in order of their total utility divided by their cost, skipping
+    /// projects that don't fit in the remaining budget.
+    GreedyCostUtility,
+}
+
+/// Specifies how the Method of Equal Shares spends the part of the budget that it leaves unspent.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Completion {
+    /// The unspent part of the budget is left unspent.
+    None,
+
+    /// The count is repeated, increasing each voter's share of the budget by `1` each time, for as long as the selected
+    /// projects fit in the real budget. The count stops early if the selected projects leave no room for any other project.
+    Add1,
+
+    /// Projects are selected in order of their total utility, as with [`Variant::GreedyUtility`](enum.Variant.html#variant.GreedyUtility),
+    /// until no remaining project fits in the unspent part of the budget.
+    Utilitarian,
+}
+
+/// A participatory budgeting tally using `f64` floats to count votes and costs.
+/// `DefaultPbTally` is generally preferred over `PbTally`.
+/// Since this is an alias, refer to [`PbTally`](struct.PbTally.html) for method documentation.
+///
+/// # Example
+/// ```
+///    use tallystick::pb::{DefaultPbTally, Variant, Completion};
+///
+///    let projects = vec![("Park", 100.0), ("Library", 200.0), ("Pool", 150.0), ("Road", 50.0)];
+///    let mut tally = DefaultPbTally::new(300.0, projects, Variant::EqualShares(Completion::Utilitarian));
+///    tally.add_weighted(vec!["Library", "Park"], 6.0).unwrap();
+///    tally.add_weighted(vec!["Pool", "Road"], 4.0).unwrap();
+///
+///    // Both groups of voters get projects they want, in proportion to their size.
+///    let funded = tally.winners().into_unranked();
+///    assert_eq!(funded, vec!["Road", "Park", "Pool"]);
+/// ```
+pub type DefaultPbTally<T> = PbTally<T, f64>;
+
+/// A generic participatory budgeting tally, which selects projects with a total cost that fits within a budget.
+///
+/// Votes can be approval votes, like those for an [`ApprovalTally`](../approval/struct.ApprovalTally.html), which give
+/// a utility of `1` to every approved project. They can also be score votes, like those for a
+/// [`ScoreTally`](../score/struct.ScoreTally.html), which give each scored project a utility equal to its score.
+///
+/// Generics:
+/// - `T`: The project type.
+/// - `C`: The count type, which is also used for costs, utilities and the budget. `f64` is recommended. The Method of Equal Shares
+///   splits the budget between voters, so a float, a [`rational`](https://rust-num.github.io/num/num_rational/index.html)
+///   (with the `rational` feature) or a [`FixedDecimal`](../struct.FixedDecimal.html) must be used with
+///   [`Variant::EqualShares`](enum.Variant.html#variant.EqualShares).
+///
+/// # Example
+/// ```
+///    use tallystick::pb::{PbTally, Variant};
+///
+///    // Costs in whole dollars can be counted with an integer count type when using a greedy variant.
+///    let projects = vec![("Park", 100), ("Library", 200), ("Pool", 150), ("Road", 50)];
+///    let mut tally = PbTally::<&str, u64>::new(300, projects, Variant::GreedyUtility);
+///    tally.add_weighted(vec!["Library", "Park"], 6).unwrap();
+///    tally.add_weighted(vec!["Pool", "Road"], 4).unwrap();
+///
+///    // The majority gets every project it wants.
+///    assert_eq!(tally.selected(), vec![("Park", 100), ("Library", 200)]);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PbTally<T, C = f64>
+where
+    T: Eq + Clone + Hash,                                       // Project
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    projects: Vec<(T, C)>,              // Projects with their costs, in the order they were declared
+    ballots: Vec<(Vec<(usize, C)>, C)>, // Votes as the utility of each project, with their weight
+    utilities: Vec<C>,                  // Total utility of each project, so that counting can't overflow
+    total: C,                           // Total weight of all votes
+    budget: C,
+    variant: Variant,
+}
+
+impl<T, C> PbTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Project
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new `PbTally` with the given budget, for the given projects and their costs, using the given variant.
+    ///
+    /// Ties between projects are broken in favour of the project listed first in `projects`.
+    /// Projects that no voter wants are never selected.
+    ///
+    /// This method will panic if using [`Variant::EqualShares`](enum.Variant.html#variant.EqualShares) with an integer based
+    /// vote-count type.
+    pub fn new(budget: C, projects: Vec<(T, C)>, variant: Variant) -> Self {
+        if let Variant::EqualShares(_) = variant {
+            if !C::fraction() {
+                panic!("tallystick::pb::Variant::EqualShares cannot be used with an integer count type. Please use a float or a rational.")
+            }
+        }
+        return PbTally {
+            utilities: vec![C::zero(); projects.len()],
+            projects: projects,
+            ballots: Vec::new(),
+            total: C::zero(),
+            budget: budget,
+            variant: variant,
+        };
+    }
+
+    /// Add a new approval vote, giving a utility of `1` to every project in `selection`.
+    ///
+    /// An error is returned if `selection` contains duplicate or unknown projects.
+    pub fn add(&mut self, selection: Vec<T>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, C::one())
+    }
+
+    /// Add an approval vote by reference.
+    pub fn add_ref(&mut self, selection: &[T]) -> Result<(), TallyError> {
+        self.add_weighted_ref(selection, C::one())
+    }
+
+    /// Add a weighted approval vote.
+    pub fn add_weighted(&mut self, selection: Vec<T>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, weight)
+    }
+
+    /// Add a weighted approval vote by reference.
+    pub fn add_weighted_ref(&mut self, selection: &[T], weight: C) -> Result<(), TallyError> {
+        check_duplicate(selection)?;
+        let utilities: Vec<(T, C)> = selection.iter().map(|project| (project.clone(), C::one())).collect();
+        self.add_scores_weighted_ref(&utilities, weight)
+    }
+
+    /// Add a new score vote, giving each project in `selection` a utility equal to its score.
+    ///
+    /// An error is returned if `selection` scores the same project more than once, scores an unknown project,
+    /// or gives a project a negative score.
+    pub fn add_scores(&mut self, selection: Vec<(T, C)>) -> Result<(), TallyError> {
+        self.add_scores_weighted_ref(&selection, C::one())
+    }
+
+    /// Add a score vote by reference.
+    pub fn add_scores_ref(&mut self, selection: &[(T, C)]) -> Result<(), TallyError> {
+        self.add_scores_weighted_ref(selection, C::one())
+    }
+
+    /// Add a weighted score vote.
+    pub fn add_scores_weighted(&mut self, selection: Vec<(T, C)>, weight: C) -> Result<(), TallyError> {
+        self.add_scores_weighted_ref(&selection, weight)
+    }
+
+    /// Add a weighted score vote by reference.
+    pub fn add_scores_weighted_ref(&mut self, selection: &[(T, C)], weight: C) -> Result<(), TallyError> {
+        let mut utilities = Vec::with_capacity(selection.len());
+        for (project, utility) in selection.iter() {
+            let id = self.projects.iter().position(|(candidate, _)| candidate == project).ok_or(TallyError::UnknownCandidate)?;
+            if *utility < C::zero() {
+                return Err(TallyError::ScoreOutOfRange);
+            }
+            if utilities.iter().any(|(other, _)| *other == id) {
+                return Err(TallyError::VoteHasDuplicateCandidates);
+            }
+            utilities.push((id, *utility));
+        }
+
+        // Check the totals before counting the vote, so that the vote isn't partially counted.
+        let total = self.total.checked_add(weight).ok_or(TallyError::CountOverflow)?;
+        let mut totals = Vec::with_capacity(utilities.len());
+        for (project, utility) in utilities.iter() {
+            let weighted = weight.checked_mul(*utility).ok_or(TallyError::CountOverflow)?;
+            totals.push(self.utilities[*project].checked_add(weighted).ok_or(TallyError::CountOverflow)?);
+        }
+
+        for ((project, _), project_total) in utilities.iter().zip(totals) {
+            self.utilities[*project] = project_total;
+        }
+        self.total = total;
+        self.ballots.push((utilities, weight));
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Both tallies must be for the same projects, listed in the same order. The budget and variant for `other` are ignored.
+    ///
+    /// # Panics
+    /// This method will panic if `other` has different projects or costs, or if the merged total weight of all votes, or the
+    /// total utility of a project, overflows the count type.
+    pub fn merge(&mut self, other: Self) {
+        assert!(self.projects == other.projects, "tallystick::pb::PbTally can only be merged with a tally for the same projects");
+        add_count(&mut self.total, other.total).expect(COUNT_OVERFLOW_PANIC);
+        for (utility, other_utility) in self.utilities.iter_mut().zip(other.utilities) {
+            add_count(utility, other_utility).expect(COUNT_OVERFLOW_PANIC);
+        }
+        self.ballots.extend(other.ballots);
+    }
+
+    /// Get a list of all projects, in the order they were declared.
+    pub fn candidates(&self) -> Vec<T> {
+        return self.projects.iter().map(|(project, _)| project.clone()).collect();
+    }
+
+    /// Get the selected projects, ranked in the order they were selected.
+    pub fn winners(&self) -> RankedWinners<T> {
+        let selected = self.select();
+        let mut winners = RankedWinners::new(selected.len() as u32);
+        for (rank, project) in selected.into_iter().enumerate() {
+            winners.push(self.projects[project].0.clone(), rank as u32);
+        }
+        return winners;
+    }
+
+    /// Get the selected projects with their costs, in the order they were selected.
+    pub fn selected(&self) -> Vec<(T, C)> {
+        return self.select().into_iter().map(|project| self.projects[project].clone()).collect();
+    }
+
+    // Select projects, returning their ids in the order they were selected.
+    fn select(&self) -> Vec<usize> {
+        // Without any votes, no project is wanted. This also avoids dividing the budget by zero.
+        if self.total == C::zero() {
+            return Vec::new();
+        }
+        match self.variant {
+            Variant::EqualShares(completion) => {
+                let share = self.budget / self.total;
+                let mut selected = self.equal_shares(share);
+                match completion {
+                    Completion::None => {}
+                    Completion::Add1 => {
+                        let mut share = share;
+                        while !self.exhausted(&selected) {
+                            share += C::one();
+                            let next = self.equal_shares(share);
+                            if self.cost(&next) > self.budget {
+                                break;
+                            }
+                            selected = next;
+                        }
+                    }
+                    Completion::Utilitarian => self.greedy(&mut selected, false),
+                }
+                return selected;
+            }
+            Variant::GreedyUtility => {
+                let mut selected = Vec::new();
+                self.greedy(&mut selected, false);
+                return selected;
+            }
+            Variant::GreedyCostUtility => {
+                let mut selected = Vec::new();
+                self.greedy(&mut selected, true);
+                return selected;
+            }
+        }
+    }
+
+    // Run the Method of Equal Shares, giving each voter the given share of the budget.
+    fn equal_shares(&self, share: C) -> Vec<usize> {
+        let mut budgets: Vec<C> = self.ballots.iter().map(|(_, weight)| weight.checked_mul(share).expect(COUNT_OVERFLOW_PANIC)).collect();
+        let mut selected = Vec::new();
+        loop {
+            // Find the project that can be afforded at the lowest price per unit of utility.
+            let mut best: Option<(usize, C)> = None;
+            for project in (0..self.projects.len()).filter(|project| !selected.contains(project)) {
+                if let Some(rate) = self.rate(project, &budgets) {
+                    if best.is_none_or(|(_, best_rate)| rate < best_rate) {
+                        best = Some((project, rate));
+                    }
+                }
+            }
+            let (project, rate) = match best {
+                Some(best) => best,
+                None => return selected,
+            };
+
+            // Each voter pays the rate for each unit of utility, or their remaining budget if that is less.
+            for ((utilities, weight), budget) in self.ballots.iter().zip(budgets.iter_mut()) {
+                if let Some((_, utility)) = utilities.iter().find(|(id, _)| *id == project) {
+                    let payment = rate * *weight * *utility;
+                    *budget = if payment < *budget { *budget - payment } else { C::zero() };
+                }
+            }
+            selected.push(project);
+        }
+    }
+
+    // Find the lowest price per unit of utility at which the voters who want a project can afford it, or `None` if they can't.
+    fn rate(&self, project: usize, budgets: &[C]) -> Option<C> {
+        // Each voter can pay at most their remaining budget, which caps the rate that they can pay.
+        let mut supporters: Vec<(C, C, C)> = Vec::new();
+        let mut utility = C::zero();
+        for ((utilities, weight), budget) in self.ballots.iter().zip(budgets) {
+            if let Some((_, voter_utility)) = utilities.iter().find(|(id, _)| *id == project) {
+                if *voter_utility > C::zero() {
+                    let weighted = *weight * *voter_utility;
+                    supporters.push((*budget / weighted, *budget, weighted));
+                    utility += weighted;
+                }
+            }
+        }
+        supporters.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        // Voters whose cap is below the rate pay all of their budget, and the rest is shared by the remaining voters.
+        let mut remaining = self.projects[project].1;
+        for (cap, budget, weighted) in supporters {
+            let rate = remaining / utility;
+            if rate <= cap {
+                return Some(rate);
+            }
+            remaining = remaining - budget;
+            utility = utility - weighted;
+        }
+        return None;
+    }
+
+    // Select the remaining projects in order of their total utility, or their total utility per unit of cost, while they fit in the budget.
+    fn greedy(&self, selected: &mut Vec<usize>, per_cost: bool) {
+        // Sort by total utility, comparing utility per cost by cross multiplying so that integer counts aren't truncated.
+        let utilities = &self.utilities;
+        let mut order: Vec<usize> = (0..self.projects.len()).collect();
+        order.sort_by(|a, b| {
+            let (utility_a, utility_b) = if per_cost {
+                let utility_a = utilities[*a].checked_mul(self.projects[*b].1).expect(COUNT_OVERFLOW_PANIC);
+                let utility_b = utilities[*b].checked_mul(self.projects[*a].1).expect(COUNT_OVERFLOW_PANIC);
+                (utility_a, utility_b)
+            } else {
+                (utilities[*a], utilities[*b])
+            };
+            return utility_b.partial_cmp(&utility_a).unwrap_or(Ordering::Equal);
+        });
+
+        let mut spent = self.cost(selected);
+        for project in order {
+            let cost = self.projects[project].1;
+            if utilities[project] > C::zero() && !selected.contains(&project) && spent + cost <= self.budget {
+                selected.push(project);
+                spent += cost;
+            }
+        }
+    }
+
+    // Check if no remaining project that any voter wants fits in the unspent part of the budget.
+    fn exhausted(&self, selected: &[usize]) -> bool {
+        let spent = self.cost(selected);
+        let mut remaining = (0..self.projects.len()).filter(|project| self.utilities[*project] > C::zero() && !selected.contains(project));
+        return remaining.all(|project| spent + self.projects[project].1 > self.budget);
+    }
+
+    // Get the total cost of a set of projects.
+    fn cost(&self, selected: &[usize]) -> C {
+        let mut cost = C::zero();
+        for project in selected {
+            add_count(&mut cost, self.projects[*project].1).expect(COUNT_OVERFLOW_PANIC);
+        }
+        return cost;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Six voters want the park and the library, and four want the pool and the road.
+    fn tally(variant: Variant) -> DefaultPbTally<&'static str> {
+        let projects = vec![("Park", 100.0), ("Library", 200.0), ("Pool", 150.0), ("Road", 50.0)];
+        let mut tally = DefaultPbTally::new(300.0, projects, variant);
+        tally.add_weighted(vec!["Library", "Park"], 6.0).unwrap();
+        tally.add_weighted(vec!["Pool", "Road"], 4.0).unwrap();
+        return tally;
+    }
+
+    #[test]
+    fn pb_equal_shares() {
+        // Each voter has 30. The road costs each of its voters 12.5, then the park costs each of its voters 16.67.
+        // Neither group can then afford another project.
+        let tally = tally(Variant::EqualShares(Completion::None));
+        assert_eq!(tally.selected(), vec![("Road", 50.0), ("Park", 100.0)]);
+
+        // The library and the pool only become affordable once each voter has 50, which would go over the budget.
+        let tally = self::tally(Variant::EqualShares(Completion::Add1));
+        assert_eq!(tally.selected(), vec![("Road", 50.0), ("Park", 100.0)]);
+
+        let tally = self::tally(Variant::EqualShares(Completion::Utilitarian));
+        assert_eq!(tally.winners().into_vec(), vec![("Road", 0), ("Park", 1), ("Pool", 2)]);
+    }
+
+    #[test]
+    fn pb_add1() {
+        // Three voters want the park, and one voter wants the library. Each voter has 25, which can't pay for either project.
+        let projects = vec![("Park", 100.0), ("Library", 30.0)];
+        let mut tally = DefaultPbTally::new(100.0, projects, Variant::EqualShares(Completion::None));
+        tally.add_weighted(vec!["Park"], 3.0).unwrap();
+        tally.add(vec!["Library"]).unwrap();
+        assert_eq!(tally.selected(), vec![]);
+
+        // Once each voter has 30 the library is funded, which leaves too little of the budget for the park.
+        tally.variant = Variant::EqualShares(Completion::Add1);
+        assert_eq!(tally.selected(), vec![("Library", 30.0)]);
+
+        // With a budget of 130, each voter starts with 32.5. Both projects are funded once each voter has 33.5.
+        tally.budget = 130.0;
+        assert_eq!(tally.selected(), vec![("Library", 30.0), ("Park", 100.0)]);
+    }
+
+    #[test]
+    fn pb_greedy() {
+        let tally = tally(Variant::GreedyUtility);
+        assert_eq!(tally.selected(), vec![("Park", 100.0), ("Library", 200.0)]);
+
+        // The road has the most utility per cost, then the park. The library then doesn't fit, but the pool does.
+        let tally = self::tally(Variant::GreedyCostUtility);
+        assert_eq!(tally.selected(), vec![("Road", 50.0), ("Park", 100.0), ("Pool", 150.0)]);
+    }
+
+    #[test]
+    fn pb_scores() -> Result<(), TallyError> {
+        let projects = vec![("Park", 100), ("Library", 200), ("Pool", 150)];
+        let mut tally = PbTally::<&str, u64>::new(300, projects, Variant::GreedyCostUtility);
+        tally.add_scores(vec![("Park", 1), ("Library", 5)])?;
+        tally.add_scores_weighted(vec![("Pool", 2)], 2)?;
+        assert_eq!(tally.selected(), vec![("Pool", 150), ("Park", 100)]);
+
+        // Projects that no voter wants are not funded, even if they fit in the budget.
+        let mut tally = PbTally::<&str, f64>::new(300.0, vec![("Park", 100.0), ("Road", 50.0)], Variant::EqualShares(Completion::Add1));
+        assert_eq!(tally.selected(), vec![]);
+        tally.add(vec!["Park"])?;
+        assert_eq!(tally.selected(), vec![("Park", 100.0)]);
+
+        assert!(tally.add(vec!["Pool"]).is_err());
+        assert!(tally.add(vec!["Park", "Park"]).is_err());
+        assert!(tally.add_scores(vec![("Park", 1.0), ("Park", 2.0)]).is_err());
+        assert!(tally.add_scores(vec![("Park", -1.0)]).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "rational")]
+    #[test]
+    fn pb_rational() -> Result<(), TallyError> {
+        use num_rational::Rational64;
+
+        let r = Rational64::from_integer;
+        let mut tally = PbTally::<&str, Rational64>::new(r(100), vec![("Park", r(60)), ("Road", r(50))], Variant::EqualShares(Completion::Add1));
+        assert_eq!(tally.selected(), vec![]);
+
+        // Each voter has 100/3, so the two voters who want the park can afford it.
+        tally.add_weighted(vec!["Park"], r(2))?;
+        tally.add(vec!["Road"])?;
+        assert_eq!(tally.selected(), vec![("Park", r(60))]);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "same projects")]
+    fn pb_merge_different_projects() {
+        let mut tally = tally(Variant::GreedyUtility);
+        tally.merge(DefaultPbTally::new(300.0, vec![("Park", 100.0)], Variant::GreedyUtility));
+    }
+
+    #[test]
+    #[should_panic]
+    fn pb_integer_equal_shares() {
+        PbTally::<&str, u64>::new(300, vec![("Park", 100)], Variant::EqualShares(Completion::None));
+    }
+}
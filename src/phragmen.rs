@@ -0,0 +1,524 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::approval_pr::Committees;
+use super::check_duplicate;
+use super::result::RankedWinners;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
+use super::TallyError;
+use super::{add_count, sorted_order, COUNT_OVERFLOW_PANIC, C_FROM_PANIC};
+
+/// Specifies the Phragmén method used to elect a committee.
+///
+/// Phragmén's methods treat each elected candidate as a load of `1` that must be shared between the voters who approve
+/// of them. A committee is proportional when the load is spread evenly, so that no group of voters carries much more
+/// of the load than any other.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Variant {
+    /// Phragmén's sequential method. Winners are elected one at a time. Each round, the candidate is elected that
+    /// would leave their voters with the smallest load, if all of their voters had the same load:
+    ///
+    /// ```(1 + sum of the current loads of their voters) / number of voters```
+    ///
+    /// The load of every voter who approves of the elected candidate is then set to this new load.
+    Sequential,
+
+    /// Leximax-Phragmén, a refinement of max-Phragmén. The committee is elected that allows the load to be distributed
+    /// so that the highest load carried by any voter is as small as possible. If several committees tie, the committee
+    /// whose second highest load is smallest is elected, and so on.
+    ///
+    /// Every possible committee is considered, so this is only practical for small elections: there are
+    /// `c! / (w! * (c - w)!)` committees of `w` winners from `c` candidates.
+    Leximax,
+}
+
+/// A Phragmén tally using `f64` floats to count votes.
+/// `DefaultPhragmenTally` is generally preferred over `PhragmenTally`.
+/// Since this is an alias, refer to [`PhragmenTally`](struct.PhragmenTally.html) for method documentation.
+///
+/// # Example
+/// ```
+///    use tallystick::phragmen::DefaultPhragmenTally;
+///    use tallystick::phragmen::Variant;
+///
+///    // Electing two validators.
+///    let mut tally = DefaultPhragmenTally::new(2, Variant::Sequential);
+///    tally.add_weighted(vec!["Alice", "Bob"], 6.0).unwrap();
+///    tally.add_weighted(vec!["Carlos"], 4.0).unwrap();
+///
+///    let winners = tally.winners().into_unranked();
+///    assert_eq!(winners, vec!["Alice", "Carlos"]);
+///
+///    // Voters who approve of Alice and Bob share the load of Alice, and voters who approve of Carlos share the load of Carlos.
+///    assert_eq!(tally.loads(), vec![(vec!["Alice", "Bob"], 1.0 / 6.0), (vec!["Carlos"], 1.0 / 4.0)]);
+/// ```
+pub type DefaultPhragmenTally<T> = PhragmenTally<T, f64>;
+
+/// A generic Phragmén tally, which elects a committee from approval votes.
+///
+/// Votes are the same as for an [`ApprovalTally`](../approval/struct.ApprovalTally.html): each vote approves of any number of candidates.
+/// Both variants satisfy proportional justified representation.
+///
+/// Generics:
+/// - `T`: The candidate type.
+/// - `C`: The count type. Loads are fractional, so a float, a [`rational`](https://rust-num.github.io/num/num_rational/index.html)
+///   (with the `rational` feature) or a [`FixedDecimal`](../struct.FixedDecimal.html) must be used.
+///
+/// # Example
+/// ```
+///    use tallystick::phragmen::PhragmenTally;
+///    use tallystick::phragmen::Variant;
+///
+///    let mut tally = PhragmenTally::<&str, f64>::new(2, Variant::Leximax);
+///    tally.add_weighted(vec!["Alice", "Bob"], 1.0).unwrap();
+///    tally.add_weighted(vec!["Bob"], 4.0).unwrap();
+///    tally.add_weighted(vec!["Alice", "Carlos"], 9.0).unwrap();
+///
+///    let winners = tally.winners().into_unranked();
+///    assert_eq!(winners, vec!["Alice", "Bob"]);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PhragmenTally<T, C = f64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    ballots: BallotBox<T, C>, // Identical votes are stored once, with their total weight
+    num_winners: u32,
+    variant: Variant,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    registry: Option<Candidates<T>>, // The declared candidates, if any
+}
+
+impl<T, C> PhragmenTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new `PhragmenTally` with the given number of winners, using the given variant.
+    ///
+    /// This method will panic if used with an integer based vote-count type.
+    pub fn new(num_winners: u32, variant: Variant) -> Self {
+        return Self::with_capacity(num_winners, variant, 0, 0);
+    }
+
+    /// Create a new `PhragmenTally` with the given number of winners, number of expected candidates, and number of expected unique votes.
+    pub fn with_capacity(num_winners: u32, variant: Variant, expected_candidates: usize, expected_votes: usize) -> Self {
+        if !C::fraction() {
+            panic!("tallystick::phragmen::PhragmenTally cannot be used with an integer count type. Please use a float or a rational.")
+        }
+        return PhragmenTally {
+            ballots: BallotBox::with_capacity(expected_candidates, expected_votes),
+            num_winners: num_winners,
+            variant: variant,
+            registry: None,
+        };
+    }
+
+    /// Create a new `PhragmenTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// Every standing candidate can be elected, even if no votes approve of them.
+    /// See [`Candidates`](../struct.Candidates.html) for details.
+    pub fn with_candidates(num_winners: u32, variant: Variant, candidates: Candidates<T>) -> Self {
+        let mut tally = Self::with_capacity(num_winners, variant, candidates.standing().len(), 0);
+        tally.ballots.declare(candidates.standing());
+        tally.registry = Some(candidates);
+        return tally;
+    }
+
+    /// Add a new vote, approving of every candidate in `selection`.
+    ///
+    /// An error is returned if `selection` contains duplicate candidates, or if the tally was created with
+    /// [`with_candidates()`](#method.with_candidates) and `selection` contains an unknown candidate.
+    pub fn add(&mut self, selection: Vec<T>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, C::one())
+    }
+
+    /// Add a vote by reference.
+    pub fn add_ref(&mut self, selection: &[T]) -> Result<(), TallyError> {
+        self.add_weighted_ref(selection, C::one())
+    }
+
+    /// Add a weighted vote.
+    pub fn add_weighted(&mut self, selection: Vec<T>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, weight)
+    }
+
+    /// Add a weighted vote by reference.
+    pub fn add_weighted_ref(&mut self, selection: &[T], weight: C) -> Result<(), TallyError> {
+        match &self.registry {
+            Some(registry) => {
+                check_duplicate(selection)?;
+                self.ballots.add_weighted_ref(&registry.filter(selection)?, weight)
+            }
+            None => self.ballots.add_weighted_ref(selection, weight),
+        }
+    }
+
+    /// Add all votes in a `BallotBox`. Every candidate marked on a vote is approved, regardless of rank.
+    ///
+    /// An error will only be returned if the tally was created with [`with_candidates()`](#method.with_candidates)
+    /// and the ballot box contains an unknown candidate.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+        if let Some(registry) = &self.registry {
+            registry.check_all(ballot_box.candidate_ids())?;
+        }
+        for (vote, weight) in ballot_box.iter() {
+            let approved: Vec<T> = vote.candidates().cloned().collect();
+            self.add_weighted_ref(&approved, weight)?;
+        }
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Tallies can be merged in any order to get the same result. After merging, candidates are sorted as if by
+    /// [`sort_candidates()`](#method.sort_candidates), so their order doesn't depend on merge order either.
+    /// The variant and number of winners for `other` are ignored.
+    ///
+    /// # Panics
+    /// This method will panic if the merged total weight of all votes overflows the count type.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.ballots.merge(other.ballots);
+        self.sort_candidates();
+    }
+
+    /// Get a list of all candidates seen by this tally.
+    /// Candidates are returned in the order they were first seen.
+    pub fn candidates(&self) -> Vec<T> {
+        return self.ballots.candidates();
+    }
+
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+        let order = sorted_order(self.ballots.candidate_ids(), declared);
+        self.ballots.reorder(&order);
+    }
+
+    /// Get the elected committee as a ranked list of winners.
+    ///
+    /// For [`Variant::Sequential`](enum.Variant.html#variant.Sequential), winners are ranked in the order they were elected,
+    /// and if several candidates tie for the last seat they are all returned with the same rank. Other ties are broken in
+    /// favour of the candidates that were seen first.
+    ///
+    /// For [`Variant::Leximax`](enum.Variant.html#variant.Leximax), the committee is elected as a whole, so every winner
+    /// has a rank of `0`. If several committees tie, the committee that includes the candidates seen first is elected.
+    ///
+    /// Candidates that no voter approves of can't carry a load, so they are only elected if there are not enough other
+    /// candidates. They are then all tied for the remaining seats, and are ranked after the other winners.
+    pub fn winners(&self) -> RankedWinners<T> {
+        return self.count().0;
+    }
+
+    /// Get the load carried by voters once the committee is elected.
+    ///
+    /// Each unique vote is returned with the load carried by each voter who cast it, in the order the votes were first seen.
+    /// The load of a weighted vote is shared between its voters, so a vote with a weight of `2` carries twice the load returned.
+    /// The loads of all voters add up to the number of winners who are approved by at least one voter.
+    ///
+    /// For [`Variant::Sequential`](enum.Variant.html#variant.Sequential), if several candidates tie for the last seat,
+    /// the loads are those from electing the first of the tied candidates.
+    pub fn loads(&self) -> Vec<(Vec<T>, C)> {
+        let loads = self.count().1;
+        let votes = self.ballots.iter().map(|(vote, _)| vote.candidates().cloned().collect());
+        return votes.zip(loads).collect();
+    }
+
+    // Elect the committee, returning the winners and the load of a voter for each unique vote.
+    fn count(&self) -> (RankedWinners<T>, Vec<C>) {
+        let candidates = self.ballots.candidate_ids();
+        let mut winners = RankedWinners::new(self.num_winners);
+        let mut loads = vec![C::zero(); self.ballots.len()];
+        let mut elected = vec![false; candidates.len()];
+        let mut rank = 0;
+        let seats = self.num_winners as usize;
+
+        match self.variant {
+            Variant::Sequential => {
+                while winners.len() < seats {
+                    let mut best = Vec::new();
+                    let mut best_load = C::zero();
+                    for (candidate, is_elected) in elected.iter().enumerate() {
+                        if *is_elected {
+                            continue;
+                        }
+                        if let Some(load) = self.next_load(candidate, &loads) {
+                            if best.is_empty() || load < best_load {
+                                best = vec![candidate];
+                                best_load = load;
+                            } else if load == best_load {
+                                best.push(candidate);
+                            }
+                        }
+                    }
+                    if best.is_empty() {
+                        break;
+                    }
+
+                    // Candidates tied for the last seat are all winners.
+                    let tied = if winners.len() + 1 == seats { best.len() } else { 1 };
+                    for candidate in best.iter().take(tied) {
+                        winners.push(candidates[*candidate].clone(), rank);
+                    }
+                    elected[best[0]] = true;
+                    for (load, (vote, _)) in loads.iter_mut().zip(self.ballots.interned()) {
+                        if vote.candidates().any(|candidate| *candidate == best[0]) {
+                            *load = best_load;
+                        }
+                    }
+                    rank += 1;
+                }
+            }
+            Variant::Leximax => {
+                let approved: Vec<usize> = (0..candidates.len()).filter(|candidate| self.support(&[*candidate]) > C::zero()).collect();
+                let mut best: Option<(Vec<usize>, Vec<C>)> = None;
+                for committee in Committees::new(approved.len(), seats.min(approved.len())) {
+                    let committee: Vec<usize> = committee.iter().map(|i| approved[*i]).collect();
+                    let committee_loads = self.leximax_loads(&committee);
+                    let better = match &best {
+                        Some((_, best_loads)) => self.compare_loads(&committee_loads, best_loads) == Ordering::Less,
+                        None => true,
+                    };
+                    if better {
+                        best = Some((committee, committee_loads));
+                    }
+                }
+                if let Some((committee, committee_loads)) = best {
+                    for candidate in committee {
+                        elected[candidate] = true;
+                        winners.push(candidates[candidate].clone(), 0);
+                    }
+                    loads = committee_loads;
+                    rank = 1;
+                }
+            }
+        }
+
+        // Candidates that no voter approves of are tied for any remaining seats.
+        if winners.len() < seats {
+            for (candidate, is_elected) in candidates.iter().zip(elected) {
+                if !is_elected {
+                    winners.push(candidate.clone(), rank);
+                }
+            }
+        }
+        return (winners, loads);
+    }
+
+    // Get the load that electing a candidate would leave their voters with, or `None` if no voter approves of them.
+    fn next_load(&self, candidate: usize, loads: &[C]) -> Option<C> {
+        let mut support = C::zero();
+        let mut load = C::one();
+        for ((vote, weight), voter_load) in self.ballots.interned().iter().zip(loads) {
+            if vote.candidates().any(|id| *id == candidate) {
+                add_count(&mut support, *weight).expect(COUNT_OVERFLOW_PANIC);
+                add_count(&mut load, weight.checked_mul(*voter_load).expect(COUNT_OVERFLOW_PANIC)).expect(COUNT_OVERFLOW_PANIC);
+            }
+        }
+        if support == C::zero() {
+            return None;
+        }
+        return Some(load / support);
+    }
+
+    // Get the total weight of the votes that approve of at least one of the given candidates.
+    fn support(&self, candidates: &[usize]) -> C {
+        let mut support = C::zero();
+        for (vote, weight) in self.ballots.interned() {
+            if vote.candidates().any(|id| candidates.contains(id)) {
+                add_count(&mut support, *weight).expect(COUNT_OVERFLOW_PANIC);
+            }
+        }
+        return support;
+    }
+
+    // Distribute the load of a committee between voters so that the loads are as even as possible, returning the load
+    // of a voter for each unique vote. Every member of the committee must be approved by at least one voter.
+    //
+    // The group of members whose voters would carry the highest load is the group with the most members per voter.
+    // Those voters carry exactly the load of those members, and no more, so they are set aside and the remaining members
+    // are distributed between the remaining voters in the same way.
+    fn leximax_loads(&self, committee: &[usize]) -> Vec<C> {
+        let ballots = self.ballots.interned();
+        let mut loads = vec![C::zero(); ballots.len()];
+        let mut loaded = vec![false; ballots.len()];
+        let mut remaining = committee.to_vec();
+        while !remaining.is_empty() {
+            // Find the largest group of members with the highest load per voter.
+            let mut densest: Option<(C, Vec<usize>)> = None;
+            for size in (1..=remaining.len()).rev() {
+                for group in Committees::new(remaining.len(), size) {
+                    let group: Vec<usize> = group.iter().map(|i| remaining[*i]).collect();
+                    let mut support = C::zero();
+                    for ((vote, weight), loaded) in ballots.iter().zip(loaded.iter()) {
+                        if !*loaded && vote.candidates().any(|id| group.contains(id)) {
+                            add_count(&mut support, *weight).expect(COUNT_OVERFLOW_PANIC);
+                        }
+                    }
+                    let load = C::from_usize(size).expect(C_FROM_PANIC) / support;
+                    if densest.as_ref().is_none_or(|(densest_load, _)| load > *densest_load) {
+                        densest = Some((load, group));
+                    }
+                }
+            }
+
+            let (load, group) = densest.unwrap();
+            for ((vote, _), (voter_load, loaded)) in ballots.iter().zip(loads.iter_mut().zip(loaded.iter_mut())) {
+                if !*loaded && vote.candidates().any(|id| group.contains(id)) {
+                    *voter_load = load;
+                    *loaded = true;
+                }
+            }
+            remaining.retain(|candidate| !group.contains(candidate));
+        }
+        return loads;
+    }
+
+    // Compare the loads of two committees, from the highest load down. The committee whose voters carry less load is less.
+    fn compare_loads(&self, a: &[C], b: &[C]) -> Ordering {
+        let a = self.load_levels(a);
+        let b = self.load_levels(b);
+        for ((load_a, weight_a), (load_b, weight_b)) in a.iter().zip(b.iter()) {
+            // A higher load is worse. At the same load, the committee that puts it on fewer voters is better.
+            let ordering = load_a.partial_cmp(load_b).unwrap_or(Ordering::Equal);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            let ordering = weight_a.partial_cmp(weight_b).unwrap_or(Ordering::Equal);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        return Ordering::Equal;
+    }
+
+    // Group the loads of every unique vote into each distinct load and the total weight of the votes that carry it, from the highest load down.
+    fn load_levels(&self, loads: &[C]) -> Vec<(C, C)> {
+        let mut levels: Vec<(C, C)> = Vec::new();
+        for (load, (_, weight)) in loads.iter().zip(self.ballots.interned()) {
+            match levels.iter_mut().find(|(level, _)| level == load) {
+                Some((_, total)) => add_count(total, *weight).expect(COUNT_OVERFLOW_PANIC),
+                None => levels.push((*load, *weight)),
+            }
+        }
+        levels.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+        return levels;
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T, C> PhragmenTally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send + Sync, // Vote count type
+{
+    /// Create a new `PhragmenTally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and collected into per-thread ballot boxes, which are then merged.
+    /// Requires the `parallel` feature.
+    pub fn from_par_iter<I>(num_winners: u32, variant: Variant, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let mut tally = Self::new(num_winners, variant);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// See [`BallotBox::par_add_all()`](../struct.BallotBox.html#method.par_add_all) for details.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        self.ballots.par_add_filtered(votes, self.registry.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One voter approves of Alice and Bob, four approve of Bob and nine approve of Alice and Carlos.
+    fn tally(variant: Variant) -> DefaultPhragmenTally<&'static str> {
+        let mut tally = DefaultPhragmenTally::new(2, variant);
+        tally.add_weighted(vec!["Alice", "Bob"], 1.0).unwrap();
+        tally.add_weighted(vec!["Bob"], 4.0).unwrap();
+        tally.add_weighted(vec!["Alice", "Carlos"], 9.0).unwrap();
+        return tally;
+    }
+
+    #[test]
+    fn phragmen_sequential() {
+        // Alice is elected first with a load of 1/10, then Carlos with a load of (1 + 9/10) / 9, which is less than Bob's (1 + 1/10) / 5.
+        let tally = tally(Variant::Sequential);
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Carlos", 1)]);
+        assert_eq!(
+            tally.loads(),
+            vec![(vec!["Alice", "Bob"], 0.1), (vec!["Bob"], 0.0), (vec!["Alice", "Carlos"], (1.0 + 9.0 * 0.1) / 9.0)]
+        );
+    }
+
+    #[test]
+    fn phragmen_leximax() {
+        // Electing Alice and Carlos gives all ten of their voters a load of 2/10, but electing Alice and Bob gives a load of 2/10
+        // to only five voters, and 1/9 to the other nine.
+        let tally = tally(Variant::Leximax);
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Bob", 0)]);
+        assert_eq!(tally.loads(), vec![(vec!["Alice", "Bob"], 0.2), (vec!["Bob"], 0.2), (vec!["Alice", "Carlos"], 1.0 / 9.0)]);
+
+        // Electing Alice and Bob, or Alice and Carlos, both give a highest load of 1, but electing Alice and Carlos puts it on fewer voters.
+        let mut tally = DefaultPhragmenTally::new(2, Variant::Leximax);
+        tally.add(vec!["Alice"]).unwrap();
+        tally.add(vec!["Bob"]).unwrap();
+        tally.add_weighted(vec!["Carlos"], 2.0).unwrap();
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Carlos", 0)]);
+        assert_eq!(tally.loads().iter().map(|(_, load)| *load).collect::<Vec<_>>(), vec![1.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn phragmen_unapproved() -> Result<(), TallyError> {
+        // Dave is standing, but no voter approves of him.
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos", "Dave"]);
+        for variant in [Variant::Sequential, Variant::Leximax].iter() {
+            let mut tally = PhragmenTally::<&str, f64>::with_candidates(3, *variant, candidates.clone());
+            tally.add(vec!["Alice"])?;
+            tally.add(vec!["Bob"])?;
+            assert!(tally.add(vec!["Erin"]).is_err());
+
+            // Carlos and Dave tie for the last seat.
+            let winners = tally.winners();
+            assert_eq!(winners.overflow(), Some(vec!["Carlos", "Dave"]));
+            assert_eq!(tally.loads(), vec![(vec!["Alice"], 1.0), (vec!["Bob"], 1.0)]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn phragmen_integer() {
+        PhragmenTally::<&str, u64>::new(2, Variant::Sequential);
+    }
+}
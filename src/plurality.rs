@@ -1,11 +1,24 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
 use hashbrown::HashMap;
-use num_traits::cast::NumCast;
-use num_traits::Num;
-use std::hash::Hash;
-use std::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "serde")]
+use core::convert::TryFrom;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use super::result::CountedCandidates;
 use super::result::RankedWinners;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
+use super::TallyError;
+use super::{add_count, sorted_order, COUNT_OVERFLOW_PANIC};
 
 /// A plurality tally using `u64` integers to count votes.
 /// `DefaultPluralityTally` is generally preferred over `PluralityTally`.
@@ -48,19 +61,61 @@ pub type DefaultPluralityTally<T> = PluralityTally<T, u64>;
 ///    tally.add("Carol");                // A vote for Carol with an implicit weight of `1.0`
 ///    let winners = tally.winners();
 /// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "PluralityState<T, C>"))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "T: Deserialize<'de>, C: Deserialize<'de>")))]
 pub struct PluralityTally<T, C = u64>
 where
-    T: Eq + Clone + Hash,                             // Candidate type
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // Count type
+    T: Eq + Clone + Hash,                                       // Candidate type
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Count type
 {
-    running_total: HashMap<T, C>,
+    running_total: Vec<(T, C)>, // Candidates in the order they were first seen, with their vote counts
+    #[cfg_attr(feature = "serde", serde(skip))]
+    candidates: HashMap<T, usize>, // Map candidates to their position in `running_total`
+    num_winners: u32,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) registry: Option<Candidates<T>>, // The declared candidates, if any
+}
+
+// The serialized state of a `PluralityTally`. The candidate index is rebuilt when it is deserialized.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Eq + Clone + Hash + Deserialize<'de>, C: Deserialize<'de>"))]
+struct PluralityState<T: Eq + Clone + Hash, C> {
+    running_total: Vec<(T, C)>,
     num_winners: u32,
+    #[serde(default)]
+    registry: Option<Candidates<T>>,
+}
+
+#[cfg(feature = "serde")]
+impl<T, C> TryFrom<PluralityState<T, C>> for PluralityTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate type
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Count type
+{
+    type Error = TallyError;
+
+    fn try_from(state: PluralityState<T, C>) -> Result<Self, TallyError> {
+        let mut candidates = HashMap::with_capacity(state.running_total.len());
+        for (id, (candidate, _)) in state.running_total.iter().enumerate() {
+            if candidates.insert(candidate.clone(), id).is_some() {
+                return Err(TallyError::InvalidState);
+            }
+        }
+        return Ok(PluralityTally {
+            running_total: state.running_total,
+            candidates: candidates,
+            num_winners: state.num_winners,
+            registry: state.registry,
+        });
+    }
 }
 
 impl<T, C> PluralityTally<T, C>
 where
-    T: Eq + Clone + Hash,                             // Candidate type
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // Count type
+    T: Eq + Clone + Hash,                                       // Candidate type
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Count type
 {
     /// Create a new `PluralityTally` with the given number of winners.
     ///
@@ -68,53 +123,206 @@ where
     /// (See [`winners()`](#method.winners) for more information on ties.)
     pub fn new(num_winners: u32) -> Self {
         return PluralityTally {
-            running_total: HashMap::new(),
+            running_total: Vec::new(),
+            candidates: HashMap::new(),
             num_winners: num_winners,
+            registry: None,
         };
     }
 
     /// Create a new `PluralityTally` with the given number of winners, and number of expected candidates.
     pub fn with_capacity(num_winners: u32, expected_candidates: usize) -> Self {
         return PluralityTally {
-            running_total: HashMap::with_capacity(expected_candidates),
+            running_total: Vec::with_capacity(expected_candidates),
+            candidates: HashMap::with_capacity(expected_candidates),
             num_winners: num_winners,
+            registry: None,
         };
     }
 
+    /// Create a new `PluralityTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// Every standing candidate is reported, even if they receive no votes. Votes for withdrawn candidates are ignored,
+    /// and votes for unknown candidates are rejected unless write-ins are accepted.
+    /// See [`Candidates`](../struct.Candidates.html) for details.
+    pub fn with_candidates(num_winners: u32, candidates: Candidates<T>) -> Self {
+        let mut tally = Self::with_capacity(num_winners, candidates.standing().len());
+        for candidate in candidates.standing() {
+            tally.count(candidate, C::zero()).unwrap(); // Safe to unwrap here since adding zero can't overflow.
+        }
+        tally.registry = Some(candidates);
+        return tally;
+    }
+
     /// Add a new vote
-    pub fn add(&mut self, vote: T) {
-        self.add_weighted(vote, C::one());
+    ///
+    /// An error will only be returned if the tally was created with [`with_candidates()`](#method.with_candidates)
+    /// and `vote` is for an unknown candidate.
+    pub fn add(&mut self, vote: T) -> Result<(), TallyError> {
+        self.add_weighted_ref(&vote, C::one())
     }
 
     /// Add a vote by reference.
-    pub fn add_ref(&mut self, vote: &T) {
-        self.add_weighted_ref(vote, C::one());
+    pub fn add_ref(&mut self, vote: &T) -> Result<(), TallyError> {
+        self.add_weighted_ref(vote, C::one())
     }
 
     /// Add a weighted vote.
     /// By default takes a weight as a `usize` integer, but can be customized by using `PluralityTally` with a custom vote type.
-    pub fn add_weighted(&mut self, vote: T, weight: C) {
-        *self.running_total.entry(vote).or_insert(C::zero()) += weight;
+    pub fn add_weighted(&mut self, vote: T, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&vote, weight)
     }
 
     /// Add a weighted vote by reference.
-    pub fn add_weighted_ref(&mut self, vote: &T, weight: C) {
-        if self.running_total.contains_key(&vote) {
-            if let Some(x) = self.running_total.get_mut(&vote) {
-                *x += weight;
+    pub fn add_weighted_ref(&mut self, vote: &T, weight: C) -> Result<(), TallyError> {
+        if let Some(registry) = &self.registry {
+            if !registry.check(vote)? {
+                return Ok(());
+            }
+        }
+        self.count(vote, weight)
+    }
+
+    // Create an empty tally that checks votes against the same registry, to count part of the votes separately.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn fork(&self) -> Self {
+        let mut tally = Self::new(0);
+        tally.registry = self.registry.clone();
+        return tally;
+    }
+
+    // Add a weighted vote for a candidate, without checking it against the registry.
+    pub(crate) fn count(&mut self, vote: &T, weight: C) -> Result<(), TallyError> {
+        match self.candidates.get(vote) {
+            Some(id) => add_count(&mut self.running_total[*id].1, weight)?,
+            None => {
+                self.candidates.insert(vote.clone(), self.running_total.len());
+                self.running_total.push((vote.clone(), weight));
+            }
+        }
+        Ok(())
+    }
+
+    /// Add all votes in a `BallotBox`, counting each vote for its first choice.
+    ///
+    /// If the first choice of a vote is a tie, the vote is split evenly between the tied candidates.
+    /// This requires a float or rational count type, otherwise an error is returned.
+    /// If the tally was created with [`with_candidates()`](#method.with_candidates), withdrawn candidates are skipped
+    /// and the vote is counted for the next choice.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+        // Withdrawn candidates are skipped, so that the vote is counted for the next choice.
+        let candidates = ballot_box.candidate_ids();
+        let mut standing = vec![true; candidates.len()];
+        if let Some(registry) = &self.registry {
+            for (id, candidate) in candidates.iter().enumerate() {
+                standing[id] = registry.check(candidate)?;
+            }
+        }
+
+        let mut first_choices: Vec<(Vec<usize>, C)> = Vec::with_capacity(ballot_box.len());
+        for (ballot, weight) in ballot_box.interned() {
+            let ranks = ballot.ranks().iter();
+            let first_choice = ranks
+                .map(|rank| rank.iter().filter(|id| standing[**id]).cloned().collect::<Vec<usize>>())
+                .find(|rank| !rank.is_empty());
+            if let Some(first_choice) = first_choice {
+                if first_choice.len() > 1 && !C::fraction() {
+                    return Err(TallyError::TiedVoteRequiresFractionalCount);
+                }
+                first_choices.push((first_choice, *weight));
+            }
+        }
+
+        for (first_choice, weight) in first_choices {
+            let weight = weight / C::from_usize(first_choice.len()).unwrap();
+            for id in first_choice.iter() {
+                self.count(&candidates[*id], weight)?;
             }
-        } else {
-            self.running_total.insert(vote.clone(), weight);
+        }
+
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// This is useful for combining tallies that were counted separately, such as precinct level tallies.
+    /// Tallies can be merged in any order to get the same result. After merging, candidates are sorted as if by
+    /// [`sort_candidates()`](#method.sort_candidates), so the order of `candidates()`, and of tied candidates in `ranked()`
+    /// and `winners()`, doesn't depend on merge order either. Floating point count types may still accumulate rounding
+    /// differences depending on merge order; use an integer or rational count type for exact results.
+    /// The number of winners for `other` is ignored.
+    ///
+    /// If this tally was created with [`with_candidates()`](#method.with_candidates), votes in `other` for withdrawn
+    /// candidates are ignored, as they are when votes are added.
+    ///
+    /// # Example
+    /// ```
+    ///    use tallystick::plurality::DefaultPluralityTally;
+    ///
+    ///    let mut precinct_1 = DefaultPluralityTally::new(1);
+    ///    precinct_1.add_weighted("Alice", 10);
+    ///    precinct_1.add_weighted("Bob", 5);
+    ///
+    ///    let mut precinct_2 = DefaultPluralityTally::new(1);
+    ///    precinct_2.add_weighted("Bob", 7);
+    ///
+    ///    precinct_1.merge(precinct_2);
+    ///    assert_eq!(precinct_1.totals(), vec![("Bob", 12), ("Alice", 10)]);
+    /// ```
+    ///
+    /// # Panics
+    /// This method will panic if a merged vote count overflows the count type, or if `other` has votes for a candidate
+    /// that is unknown to this tally's candidates. In that case, this tally is left unchanged.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.merge_counts(other);
+        self.sort_candidates();
+    }
+
+    // Merge another tally into this one, placing candidates first seen by `other` after the candidates already seen.
+    pub(crate) fn merge_counts(&mut self, other: Self) {
+        let mut running_total = other.running_total;
+        if let Some(registry) = &self.registry {
+            let mut checked = Vec::with_capacity(running_total.len());
+            for (candidate, votecount) in running_total {
+                match registry.check(&candidate) {
+                    Ok(true) => checked.push((candidate, votecount)),
+                    Ok(false) => {}
+                    // Unknown candidates without any votes, such as candidates declared by `other`, are left out.
+                    Err(_) if votecount == C::zero() => {}
+                    Err(_) => panic!("tallystick::plurality::PluralityTally::merge() found votes for an unknown candidate"),
+                }
+            }
+            running_total = checked;
+        }
+        for (candidate, votecount) in running_total {
+            self.count(&candidate, votecount).expect(COUNT_OVERFLOW_PANIC);
         }
     }
 
     /// Get a list of all candidates seen by this tally.
-    /// Candidates are returned in no particular order.
+    /// Candidates are returned in the order they were first seen.
     pub fn candidates(&self) -> Vec<T> {
-        return self.running_total.keys().map(|k| k.clone()).collect();
+        return self.running_total.iter().map(|(candidate, _)| candidate.clone()).collect();
     }
 
-    /// Get a ranked list of winners. Winners with the same rank are tied.
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+        let order = sorted_order(&self.candidates(), declared);
+        self.running_total = order.iter().map(|old| self.running_total[*old].clone()).collect();
+        for (id, (candidate, _)) in self.running_total.iter().enumerate() {
+            self.candidates.insert(candidate.clone(), id);
+        }
+    }
+
+    /// Get a ranked list of winners. Winners with the same rank are tied, and are listed in the order they were first seen.
     /// The number of winners might be greater than the requested `num_winners` if there is a tie.
     ///
     /// # Example
@@ -137,8 +345,8 @@ where
     ///    }
     ///    // Prints:
     ///    //   Alice has a rank of 0
-    ///    //   Bob has a rank of 1
     ///    //   Cir has a rank of 1
+    ///    //   Bob has a rank of 1
     /// ```
     pub fn winners(&self) -> RankedWinners<T> {
         return self.get_counted().into_ranked(self.num_winners);
@@ -146,13 +354,15 @@ where
 
     /// Get vote totals for this tally.
     ///
+    /// Totals are sorted from most to fewest votes. Candidates with the same number of votes are listed in the order they were first seen.
+    ///
     /// # Example
     /// ```
     ///    use tallystick::plurality::DefaultPluralityTally;
     ///
     ///    let mut tally = DefaultPluralityTally::new(1);
-    ///    for _ in 0..30 { tally.add("Alice") }
-    ///    for _ in 0..10 { tally.add("Bob") }
+    ///    for _ in 0..30 { tally.add("Alice").unwrap() }
+    ///    for _ in 0..10 { tally.add("Bob").unwrap() }
     ///
     ///    for (candidate, num_votes) in tally.totals().iter() {
     ///       println!("{} got {} votes", candidate, num_votes);
@@ -165,7 +375,7 @@ where
         return self.get_counted().into_vec();
     }
 
-    /// Get a ranked list of all candidates. Candidates with the same rank are tied.
+    /// Get a ranked list of all candidates. Candidates with the same rank are tied, and are listed in the order they were first seen.
     /// Candidates are ranked in ascending order. The highest ranked candidate has a rank of `0`.
     ///
     /// # Example
@@ -173,9 +383,9 @@ where
     ///    use tallystick::plurality::DefaultPluralityTally;
     ///
     ///    let mut tally = DefaultPluralityTally::new(1);
-    ///    for _ in 0..50 { tally.add("Alice") }
-    ///    for _ in 0..40 { tally.add("Bob") }
-    ///    for _ in 0..30 { tally.add("Carlos") }
+    ///    for _ in 0..50 { tally.add("Alice").unwrap() }
+    ///    for _ in 0..40 { tally.add("Bob").unwrap() }
+    ///    for _ in 0..30 { tally.add("Carlos").unwrap() }
     ///    
     ///    for (candidate, rank) in tally.ranked().iter() {
     ///       println!("{} has a rank of {}", candidate, rank);
@@ -189,8 +399,8 @@ where
         return self.get_counted().into_ranked(0).into_vec();
     }
 
-    // Get the running total as CountedCandidates.
-    crate fn get_counted(&self) -> CountedCandidates<T, C> {
+    // Get the running total as CountedCandidates, in the order candidates were first seen.
+    pub(crate) fn get_counted(&self) -> CountedCandidates<T, C> {
         let mut counted = CountedCandidates::new();
         for (candidate, votecount) in self.running_total.iter() {
             counted.push(candidate.clone(), *votecount);
@@ -199,20 +409,79 @@ where
     }
 }
 
+#[cfg(feature = "parallel")]
+impl<T, C> PluralityTally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                       // Candidate type
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send + Sync, // Count type
+{
+    /// Create a new `PluralityTally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and counted into per-thread tallies, which are then merged.
+    /// Requires the `parallel` feature.
+    ///
+    /// # Example
+    /// ```
+    ///    use rayon::prelude::*;
+    ///    use tallystick::plurality::DefaultPluralityTally;
+    ///
+    ///    let votes: Vec<&str> = vec!["Alice", "Bob", "Alice"];
+    ///    let tally = DefaultPluralityTally::from_par_iter(1, votes).unwrap();
+    ///    assert_eq!(tally.totals(), vec![("Alice", 2), ("Bob", 1)]);
+    /// ```
+    pub fn from_par_iter<I>(num_winners: u32, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let mut tally = Self::new(num_winners);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// Votes are split across threads and counted into per-thread tallies, which are then merged into this tally.
+    /// If any vote is for an unknown candidate, an error is returned and this tally is left unchanged.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let tally = votes
+            .into_par_iter()
+            .try_fold(
+                || self.fork(),
+                |mut tally, vote| {
+                    tally.add(vote)?;
+                    Ok(tally)
+                },
+            )
+            .try_reduce(
+                || self.fork(),
+                |mut tally, other| {
+                    tally.merge_counts(other);
+                    Ok(tally)
+                },
+            )?;
+        self.merge_counts(tally);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn plurality_test() {
+    fn plurality_test() -> Result<(), TallyError> {
         // Election between Alice, Bob, and Cir
         let mut tally = DefaultPluralityTally::new(2);
-        tally.add("Alice");
-        tally.add("Cir");
-        tally.add("Bob");
-        tally.add("Alice");
-        tally.add("Alice");
-        tally.add("Bob");
+        tally.add("Alice")?;
+        tally.add("Cir")?;
+        tally.add("Bob")?;
+        tally.add("Alice")?;
+        tally.add("Alice")?;
+        tally.add("Bob")?;
 
         assert_eq!(tally.candidates().len(), 3);
         assert_eq!(tally.totals(), vec![("Alice", 3), ("Bob", 2), ("Cir", 1)]);
@@ -226,14 +495,14 @@ mod tests {
 
         // Election for the most popular integer
         let mut tally = DefaultPluralityTally::new(1);
-        tally.add(99);
-        tally.add(100);
-        tally.add(99);
-        tally.add(99);
-        tally.add(1);
-        tally.add(1);
-        tally.add(2);
-        tally.add(0);
+        tally.add(99)?;
+        tally.add(100)?;
+        tally.add(99)?;
+        tally.add(99)?;
+        tally.add(1)?;
+        tally.add(1)?;
+        tally.add(2)?;
+        tally.add(0)?;
 
         let winners = tally.winners();
 
@@ -247,11 +516,139 @@ mod tests {
         let mut tally = DefaultPluralityTally::with_capacity(1, 2);
         let candidate_id_1 = 123;
         let candidate_id_2 = 456;
-        tally.add_ref(&candidate_id_1);
-        tally.add_ref(&candidate_id_2);
+        tally.add_ref(&candidate_id_1)?;
+        tally.add_ref(&candidate_id_2)?;
 
         let winners = tally.winners();
         assert_eq!(winners.contains(&candidate_id_1), true);
         assert_eq!(winners.contains(&candidate_id_2), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn plurality_ballot_box() -> Result<(), TallyError> {
+        let mut ballot_box = BallotBox::new();
+        ballot_box.add_weighted(vec!["Alice", "Bob"], 3)?;
+        ballot_box.add(vec!["Bob"])?;
+        ballot_box.add(vec!["Bob", "Alice"])?;
+
+        let mut tally = DefaultPluralityTally::new(1);
+        tally.add_ballot_box(&ballot_box)?;
+        assert_eq!(tally.totals(), vec![("Alice", 3), ("Bob", 2)]);
+
+        // A tied first choice is split, which requires a fractional count type
+        ballot_box.add_ranked(crate::RankedBallot::from(vec![vec!["Alice", "Bob"]]))?;
+        assert!(tally.add_ballot_box(&ballot_box).is_err());
+
+        let mut ballot_box_f64 = BallotBox::<&str, f64>::new();
+        ballot_box_f64.add_ranked(crate::RankedBallot::from(vec![vec!["Alice", "Bob"]]))?;
+        let mut tally = PluralityTally::<&str, f64>::new(1);
+        tally.add_ballot_box(&ballot_box_f64)?;
+        assert_eq!(tally.winners().len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn plurality_candidates() -> Result<(), TallyError> {
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos", "Dave"]).withdrawn(vec!["Carlos"]);
+        let mut tally = DefaultPluralityTally::with_candidates(1, candidates);
+        tally.add("Bob")?;
+        tally.add("Carlos")?;
+        assert!(tally.add("Erin").is_err());
+        assert_eq!(tally.totals(), vec![("Bob", 1), ("Alice", 0), ("Dave", 0)]);
+        assert_eq!(tally.ranked(), vec![("Bob", 0), ("Alice", 1), ("Dave", 1)]);
+
+        // A vote for a withdrawn candidate counts for the next choice.
+        let mut ballot_box = BallotBox::new();
+        ballot_box.add(vec!["Carlos", "Dave"])?;
+        tally.add_ballot_box(&ballot_box)?;
+        assert_eq!(tally.totals(), vec![("Bob", 1), ("Dave", 1), ("Alice", 0)]);
+
+        ballot_box.add(vec!["Erin"])?;
+        assert!(tally.add_ballot_box(&ballot_box).is_err());
+        assert_eq!(tally.totals(), vec![("Bob", 1), ("Dave", 1), ("Alice", 0)]);
+
+        // Votes merged from a tally without candidates are checked the same way.
+        let mut other = DefaultPluralityTally::new(1);
+        other.add("Carlos")?;
+        other.add("Alice")?;
+        tally.merge(other);
+        assert_eq!(tally.totals(), vec![("Alice", 1), ("Bob", 1), ("Dave", 1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown candidate")]
+    fn plurality_merge_unknown() {
+        let mut tally = DefaultPluralityTally::with_candidates(1, Candidates::new(vec!["Alice", "Bob"]));
+        let mut other = DefaultPluralityTally::new(1);
+        other.add("Erin").unwrap();
+        tally.merge(other);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn plurality_parallel() -> Result<(), TallyError> {
+        let votes: Vec<u32> = (0..7).flat_map(|c| vec![c; 500 * (c as usize + 1)]).collect();
+
+        let mut tally = DefaultPluralityTally::new(1);
+        for vote in votes.iter() {
+            tally.add_ref(vote)?;
+        }
+
+        let par_tally = DefaultPluralityTally::from_par_iter(1, votes)?;
+        assert_eq!(par_tally.totals(), tally.totals());
+        assert_eq!(par_tally.winners(), tally.winners());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn plurality_serde() -> Result<(), TallyError> {
+        // Checkpoint a partial count, then resume it.
+        let mut tally = DefaultPluralityTally::new(1);
+        tally.add_weighted("Alice", 5)?;
+        tally.add_weighted("Bob", 7)?;
+        let checkpoint = serde_json::to_string(&tally).unwrap();
+        assert_eq!(checkpoint, r#"{"running_total":[["Alice",5],["Bob",7]],"num_winners":1}"#);
+
+        let mut resumed: DefaultPluralityTally<String> = serde_json::from_str(&checkpoint).unwrap();
+        resumed.add_weighted("Alice".to_string(), 3)?;
+        assert_eq!(resumed.totals(), vec![("Alice".to_string(), 8), ("Bob".to_string(), 7)]);
+
+        let winners = serde_json::to_string(&resumed.winners()).unwrap();
+        assert_eq!(winners, r#"{"winners":[["Alice",0]],"num_winners":1}"#);
+
+        // A candidate listed twice is rejected.
+        let invalid = r#"{"running_total":[["Alice",5],["Alice",7]],"num_winners":1}"#;
+        assert!(serde_json::from_str::<DefaultPluralityTally<String>>(invalid).is_err());
+
+        // The registry is kept when resuming a count.
+        let mut tally = DefaultPluralityTally::with_candidates(1, Candidates::new(vec!["Alice", "Bob"]));
+        tally.add("Bob")?;
+        let checkpoint = serde_json::to_string(&tally).unwrap();
+        let mut resumed: DefaultPluralityTally<String> = serde_json::from_str(&checkpoint).unwrap();
+        assert!(resumed.add("Carlos".to_string()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn plurality_overflow() -> Result<(), TallyError> {
+        let mut tally = PluralityTally::<&str, u8>::new(1);
+        tally.add_weighted("Alice", 200)?;
+        tally.add_weighted("Bob", 100)?;
+        assert!(matches!(tally.add_weighted("Alice", 100), Err(TallyError::CountOverflow)));
+        assert_eq!(tally.totals(), vec![("Alice", 200), ("Bob", 100)]);
+
+        let mut ballot_box = BallotBox::<&str, u8>::new();
+        ballot_box.add_weighted(vec!["Bob"], 200)?;
+        assert!(matches!(tally.add_ballot_box(&ballot_box), Err(TallyError::CountOverflow)));
+
+        Ok(())
     }
 }
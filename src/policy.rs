@@ -0,0 +1,422 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use hashbrown::HashSet;
+use num_traits::cast::FromPrimitive;
+
+use super::add_count;
+use super::Numeric;
+use super::RankedBallot;
+use super::TallyError;
+
+/// How a [`BallotPolicy`](struct.BallotPolicy.html) handles a candidate that is not standing in the election.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum UnknownCandidates {
+    /// Reject the vote with `TallyError::UnknownCandidate`.
+    Reject,
+
+    /// Accept the candidate as a write-in.
+    WriteIn,
+}
+
+/// How a [`BallotPolicy`](struct.BallotPolicy.html) handles a candidate that is marked more than once on the same vote.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Duplicates {
+    /// Reject the vote with `TallyError::VoteHasDuplicateCandidates`.
+    Reject,
+
+    /// Keep the first (most preferred) mark for the candidate and ignore the rest, as most ranked-choice voting rules require.
+    KeepFirst,
+}
+
+/// How a [`BallotPolicy`](struct.BallotPolicy.html) handles an empty rank that is followed by a marked rank.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SkippedRanks {
+    /// Reject the vote with `TallyError::SkippedRank`.
+    Reject,
+
+    /// Ignore the empty rank, moving the later ranks up.
+    Skip,
+}
+
+/// A `BallotPolicy` checks votes against the rules of an election before they are added to a tally.
+///
+/// Tallies only reject votes that they cannot count, such as votes with duplicate candidates. Real elections have more rules:
+/// which candidates are standing, how many candidates a voter may mark or rank, and what range of scores may be given.
+/// A `BallotPolicy` checks each vote against these rules and returns the vote to add to the tally, cleaned up where the rules
+/// allow it. Votes that break a rule are rejected with an error describing the problem, and their total weight is counted
+/// so that it can be reported alongside the result.
+///
+/// A new policy only rejects duplicate candidates. Each rule can then be enabled:
+///
+/// - [`candidates()`](#method.candidates): the candidates standing in the election. Other candidates are rejected
+///   with `TallyError::UnknownCandidate` unless [`unknown_candidates()`](#method.unknown_candidates) accepts them as write-ins.
+/// - [`max_marks()`](#method.max_marks): the most candidates a vote may mark. More is an overvote (`TallyError::Overvote`).
+/// - [`allow_ties()`](#method.allow_ties): whether a ranked vote may mark more than one candidate at the same rank.
+///   If not, doing so is an overvote.
+/// - [`max_ranks()`](#method.max_ranks): the most ranks a ranked vote may use (`TallyError::TooManyRanks`).
+/// - [`skipped_ranks()`](#method.skipped_ranks): whether an empty rank rejects the vote (`TallyError::SkippedRank`) or is skipped.
+/// - [`duplicates()`](#method.duplicates): whether a duplicate candidate rejects the vote (`TallyError::VoteHasDuplicateCandidates`)
+///   or only the first mark for the candidate is kept.
+/// - [`score_range()`](#method.score_range): the lowest and highest score a score vote may give (`TallyError::ScoreOutOfRange`).
+///
+/// # Example
+/// ```
+///    use tallystick::{BallotPolicy, Duplicates};
+///    use tallystick::stv;
+///    use tallystick::Quota;
+///
+///    // Voters may rank up to three candidates. Later rankings of the same candidate are ignored.
+///    let mut policy = BallotPolicy::<&str, f64>::new()
+///        .candidates(vec!["Alice", "Bob", "Carlos", "Dave"])
+///        .max_ranks(3)
+///        .allow_ties(false)
+///        .duplicates(Duplicates::KeepFirst);
+///
+///    let mut tally = stv::DefaultTally::new(1, Quota::Droop);
+///    let votes = vec![
+///        vec![vec!["Alice"], vec!["Bob"], vec!["Alice"]],
+///        vec![vec!["Bob"], vec!["Carlos"]],
+///        vec![vec!["Bob"], vec![], vec!["Alice"]], // A skipped rank
+///        vec![vec!["Alice", "Bob"]],               // An overvote
+///        vec![vec!["Erin"]],                       // An unknown candidate
+///    ];
+///    for vote in votes {
+///        match policy.check_ranked(vote) {
+///            Ok(vote) => tally.add_ranked(vote).unwrap(),
+///            Err(e) => println!("Rejected a vote: {}", e),
+///        }
+///    }
+///
+///    assert_eq!(policy.rejected(), 2.0);
+///    assert_eq!(tally.winners().into_unranked(), vec!["Bob"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BallotPolicy<T, C = u64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    candidates: Option<HashSet<T>>, // The candidates standing in the election, if known
+    unknown_candidates: UnknownCandidates,
+    duplicates: Duplicates,
+    skipped_ranks: SkippedRanks,
+    allow_ties: bool,
+    max_marks: Option<usize>,
+    max_ranks: Option<usize>,
+    score_range: Option<(C, C)>,
+    rejected: C, // Total weight of rejected votes
+}
+
+impl<T, C> BallotPolicy<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new `BallotPolicy` that accepts any vote without duplicate candidates.
+    pub fn new() -> Self {
+        return BallotPolicy {
+            candidates: None,
+            unknown_candidates: UnknownCandidates::Reject,
+            duplicates: Duplicates::Reject,
+            skipped_ranks: SkippedRanks::Skip,
+            allow_ties: true,
+            max_marks: None,
+            max_ranks: None,
+            score_range: None,
+            rejected: C::zero(),
+        };
+    }
+
+    /// Set the candidates standing in the election. By default, any candidate is accepted.
+    pub fn candidates(mut self, candidates: Vec<T>) -> Self {
+        self.candidates = Some(candidates.into_iter().collect());
+        return self;
+    }
+
+    /// Set how candidates that are not standing in the election are handled. Defaults to `UnknownCandidates::Reject`.
+    /// This has no effect unless the candidates are set with [`candidates()`](#method.candidates).
+    pub fn unknown_candidates(mut self, unknown_candidates: UnknownCandidates) -> Self {
+        self.unknown_candidates = unknown_candidates;
+        return self;
+    }
+
+    /// Set how candidates that are marked more than once are handled. Defaults to `Duplicates::Reject`.
+    pub fn duplicates(mut self, duplicates: Duplicates) -> Self {
+        self.duplicates = duplicates;
+        return self;
+    }
+
+    /// Set how empty ranks on ranked votes are handled. Defaults to `SkippedRanks::Skip`.
+    pub fn skipped_ranks(mut self, skipped_ranks: SkippedRanks) -> Self {
+        self.skipped_ranks = skipped_ranks;
+        return self;
+    }
+
+    /// Set whether ranked votes may mark more than one candidate at the same rank. Defaults to `true`.
+    pub fn allow_ties(mut self, allow_ties: bool) -> Self {
+        self.allow_ties = allow_ties;
+        return self;
+    }
+
+    /// Set the maximum number of candidates a vote may mark. By default, there is no limit.
+    pub fn max_marks(mut self, max_marks: usize) -> Self {
+        self.max_marks = Some(max_marks);
+        return self;
+    }
+
+    /// Set the maximum number of ranks a ranked vote may use, including skipped ranks. By default, there is no limit.
+    pub fn max_ranks(mut self, max_ranks: usize) -> Self {
+        self.max_ranks = Some(max_ranks);
+        return self;
+    }
+
+    /// Set the lowest and highest score that a score vote may give a candidate. By default, any score is accepted.
+    pub fn score_range(mut self, min: C, max: C) -> Self {
+        self.score_range = Some((min, max));
+        return self;
+    }
+
+    /// Get the total weight of all rejected votes. If no weights were used, this is the number of votes rejected.
+    pub fn rejected(&self) -> C {
+        return self.rejected;
+    }
+
+    /// Check a vote that lists candidates in order of preference, such as a vote for an approval or STV tally.
+    pub fn check(&mut self, vote: Vec<T>) -> Result<Vec<T>, TallyError> {
+        self.check_weighted(vote, C::one())
+    }
+
+    /// Check a weighted vote that lists candidates in order of preference.
+    /// If the vote is rejected, its weight is added to [`rejected()`](#method.rejected).
+    pub fn check_weighted(&mut self, vote: Vec<T>, weight: C) -> Result<Vec<T>, TallyError> {
+        let result = self.clean(vote.into_iter().map(|candidate| vec![candidate]).collect());
+        let ranks = self.record(result, weight)?;
+        return Ok(ranks.into_iter().flatten().collect());
+    }
+
+    /// Check a ranked vote, given as a list of ranks ordered by preference. Each rank lists the candidates marked at that rank,
+    /// and an empty rank is a skipped rank.
+    pub fn check_ranked(&mut self, ranks: Vec<Vec<T>>) -> Result<RankedBallot<T>, TallyError> {
+        self.check_ranked_weighted(ranks, C::one())
+    }
+
+    /// Check a weighted ranked vote.
+    /// If the vote is rejected, its weight is added to [`rejected()`](#method.rejected).
+    pub fn check_ranked_weighted(&mut self, ranks: Vec<Vec<T>>, weight: C) -> Result<RankedBallot<T>, TallyError> {
+        let result = self.clean(ranks);
+        let ranks = self.record(result, weight)?;
+        return Ok(RankedBallot::from(ranks));
+    }
+
+    /// Check a score vote.
+    pub fn check_scores(&mut self, vote: Vec<(T, C)>) -> Result<Vec<(T, C)>, TallyError> {
+        self.check_scores_weighted(vote, C::one())
+    }
+
+    /// Check a weighted score vote.
+    /// If the vote is rejected, its weight is added to [`rejected()`](#method.rejected).
+    pub fn check_scores_weighted(&mut self, vote: Vec<(T, C)>, weight: C) -> Result<Vec<(T, C)>, TallyError> {
+        let result = self.clean_scores(vote);
+        return self.record(result, weight);
+    }
+
+    // Count the weight of a vote if it was rejected.
+    fn record<V>(&mut self, result: Result<V, TallyError>, weight: C) -> Result<V, TallyError> {
+        if result.is_err() {
+            add_count(&mut self.rejected, weight)?;
+        }
+        return result;
+    }
+
+    // Check a candidate against the candidates standing in the election.
+    fn check_candidate(&self, candidate: &T) -> Result<(), TallyError> {
+        match &self.candidates {
+            Some(candidates) if !candidates.contains(candidate) && self.unknown_candidates == UnknownCandidates::Reject => {
+                return Err(TallyError::UnknownCandidate);
+            }
+            _ => return Ok(()),
+        }
+    }
+
+    // Check a list of ranks, removing skipped ranks and duplicate candidates where allowed.
+    fn clean(&self, mut ranks: Vec<Vec<T>>) -> Result<Vec<Vec<T>>, TallyError> {
+        // Empty ranks after the last marked rank are not skipped ranks.
+        while ranks.last().is_some_and(|rank| rank.is_empty()) {
+            ranks.pop();
+        }
+        if let Some(max_ranks) = self.max_ranks {
+            if ranks.len() > max_ranks {
+                return Err(TallyError::TooManyRanks);
+            }
+        }
+
+        let mut seen: Vec<T> = Vec::new();
+        let mut cleaned = Vec::with_capacity(ranks.len());
+        for rank in ranks {
+            if rank.is_empty() {
+                if self.skipped_ranks == SkippedRanks::Reject {
+                    return Err(TallyError::SkippedRank);
+                }
+                continue;
+            }
+            if rank.len() > 1 && !self.allow_ties {
+                return Err(TallyError::Overvote);
+            }
+            let mut kept = Vec::with_capacity(rank.len());
+            for candidate in rank {
+                self.check_candidate(&candidate)?;
+                if seen.contains(&candidate) {
+                    if self.duplicates == Duplicates::Reject {
+                        return Err(TallyError::VoteHasDuplicateCandidates);
+                    }
+                    continue;
+                }
+                seen.push(candidate.clone());
+                kept.push(candidate);
+            }
+            if !kept.is_empty() {
+                cleaned.push(kept);
+            }
+        }
+
+        if let Some(max_marks) = self.max_marks {
+            if seen.len() > max_marks {
+                return Err(TallyError::Overvote);
+            }
+        }
+        return Ok(cleaned);
+    }
+
+    // Check a score vote, removing duplicate candidates where allowed.
+    fn clean_scores(&self, vote: Vec<(T, C)>) -> Result<Vec<(T, C)>, TallyError> {
+        let mut cleaned: Vec<(T, C)> = Vec::with_capacity(vote.len());
+        for (candidate, score) in vote {
+            self.check_candidate(&candidate)?;
+            if let Some((min, max)) = self.score_range {
+                if score < min || score > max {
+                    return Err(TallyError::ScoreOutOfRange);
+                }
+            }
+            if cleaned.iter().any(|(seen, _)| *seen == candidate) {
+                if self.duplicates == Duplicates::Reject {
+                    return Err(TallyError::VoteHasDuplicateCandidates);
+                }
+                continue;
+            }
+            cleaned.push((candidate, score));
+        }
+
+        if let Some(max_marks) = self.max_marks {
+            if cleaned.len() > max_marks {
+                return Err(TallyError::Overvote);
+            }
+        }
+        return Ok(cleaned);
+    }
+}
+
+impl<T, C> Default for BallotPolicy<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+
+    // Get the name of the error a result failed with, to compare errors in tests.
+    fn error<V>(result: Result<V, TallyError>) -> String {
+        match result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn policy_ranked() {
+        let mut policy = BallotPolicy::<&str, u64>::new();
+        let vote = policy
+            .check_ranked(vec![vec!["Alice"], vec![], vec!["Bob", "Carlos"], vec![]])
+            .unwrap();
+        assert_eq!(vote, RankedBallot::from(vec![vec!["Alice"], vec!["Bob", "Carlos"]]));
+        assert_eq!(
+            error(policy.check_ranked(vec![vec!["Alice"], vec!["Alice"]])),
+            TallyError::VoteHasDuplicateCandidates.to_string()
+        );
+        assert_eq!(policy.rejected(), 1);
+
+        let mut policy = BallotPolicy::<&str, u64>::new()
+            .candidates(vec!["Alice", "Bob", "Carlos"])
+            .skipped_ranks(SkippedRanks::Reject)
+            .allow_ties(false)
+            .max_ranks(2)
+            .duplicates(Duplicates::KeepFirst);
+        let vote = policy.check_ranked(vec![vec!["Alice"], vec!["Alice"]]).unwrap();
+        assert_eq!(vote, RankedBallot::from(vec!["Alice"]));
+        let votes = vec![
+            (vec![vec!["Alice"], vec![], vec!["Bob"]], TallyError::TooManyRanks),
+            (vec![vec![], vec!["Alice"]], TallyError::SkippedRank),
+            (vec![vec!["Alice", "Bob"]], TallyError::Overvote),
+            (vec![vec!["Dave"]], TallyError::UnknownCandidate),
+        ];
+        for (vote, expected) in votes.into_iter() {
+            assert_eq!(
+                error(policy.check_ranked_weighted(vote.clone(), 2)),
+                expected.to_string(),
+                "{:?}",
+                vote
+            );
+        }
+        assert_eq!(policy.rejected(), 8);
+
+        // Empty ranks after the last marked rank are not skipped ranks.
+        assert!(policy.check_ranked(vec![vec!["Alice"], vec![]]).is_ok());
+
+        // Write-ins are accepted, but still count towards the number of marks.
+        let mut policy = BallotPolicy::<&str, u64>::new()
+            .candidates(vec!["Alice", "Bob"])
+            .unknown_candidates(UnknownCandidates::WriteIn)
+            .max_marks(2);
+        assert_eq!(policy.check(vec!["Bob", "Dave"]).unwrap(), vec!["Bob", "Dave"]);
+        assert_eq!(error(policy.check(vec!["Alice", "Bob", "Dave"])), TallyError::Overvote.to_string());
+        assert_eq!(policy.rejected(), 1);
+    }
+
+    #[test]
+    fn policy_scores() {
+        let mut policy = BallotPolicy::<&str, u64>::new().score_range(1, 5);
+        assert_eq!(
+            policy.check_scores(vec![("Alice", 5), ("Bob", 1)]).unwrap(),
+            vec![("Alice", 5), ("Bob", 1)]
+        );
+        assert_eq!(
+            error(policy.check_scores(vec![("Alice", 6)])),
+            TallyError::ScoreOutOfRange.to_string()
+        );
+        assert_eq!(
+            error(policy.check_scores(vec![("Alice", 0)])),
+            TallyError::ScoreOutOfRange.to_string()
+        );
+        assert_eq!(
+            error(policy.check_scores(vec![("Alice", 2), ("Alice", 3)])),
+            TallyError::VoteHasDuplicateCandidates.to_string()
+        );
+        assert_eq!(policy.rejected(), 3);
+
+        let mut policy = policy.duplicates(Duplicates::KeepFirst).max_marks(1);
+        assert_eq!(policy.check_scores(vec![("Alice", 2), ("Alice", 3)]).unwrap(), vec![("Alice", 2)]);
+        assert_eq!(
+            error(policy.check_scores_weighted(vec![("Alice", 2), ("Bob", 3)], 4)),
+            TallyError::Overvote.to_string()
+        );
+        assert_eq!(policy.rejected(), 7);
+    }
+}
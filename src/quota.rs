@@ -1,7 +1,11 @@
 use super::Numeric;
 use num_traits::Num;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// A quota defines how many votes are required to win an election in relation to the total number of votes cast.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Quota<C> {
   /// Droop quota. It is defined as:
   ///
@@ -63,7 +67,7 @@ impl<C: Numeric + Num + Clone> Quota<C> {
   /// For weighted tallies, it should be the sum of all weights.
   ///
   /// # Panics
-  /// This method will panic if `Quota::Hagenbach` is used with an integer (non Real) count type.
+  /// This method will panic if `Quota::Hagenbach` is used with an integer (non-fractional) count type.
   pub fn threshold(&self, total_votes: C, num_winners: C) -> C {
     match self {
       Quota::Droop => (total_votes / (num_winners + C::one())).floor() + C::one(),
@@ -155,6 +159,20 @@ mod tests {
     assert!(Quota::Imperiali.threshold(102.0, 2.0) == 25.50);
   }
 
+  #[cfg(feature = "rational")]
+  #[test]
+  fn quota_rational() {
+    use num_rational::Rational64;
+
+    let r = |numer, denom| Rational64::new(numer, denom);
+    assert!(Quota::Droop.threshold(r(100, 1), r(2, 1)) == r(34, 1));
+    assert!(Quota::Droop.threshold(r(203, 2), r(2, 1)) == r(34, 1)); // 33.833... rounded down, plus one
+    assert!(Quota::Hagenbach.threshold(r(100, 1), r(2, 1)) == r(100, 3));
+    assert!(Quota::Hagenbach.threshold(r(203, 2), r(2, 1)) == r(203, 6));
+    assert!(Quota::Hare.threshold(r(101, 1), r(2, 1)) == r(101, 2));
+    assert!(Quota::Imperiali.threshold(r(101, 1), r(1, 1)) == r(101, 3));
+  }
+
   #[test]
   #[should_panic]
   fn quota_panic_test() {
@@ -1,7 +1,11 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering::Equal;
+use core::ops::RangeBounds;
 use derive_more::{From, Index, IndexMut};
 use num_traits::Num;
-use std::cmp::Ordering::Equal;
-use std::ops::RangeBounds;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 // A RankedWinner is a winner in an election, ranked ascending (starting from zero).
 // A ranked-winner with a lower rank beats a ranked-winner with a higher rank.
@@ -13,6 +17,7 @@ type RankedWinner<T> = (T, u32);
 /// Winners with the same rank are tied.
 // TODO: implement Index, IndexMut
 #[derive(Debug, Eq, PartialEq, From, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RankedWinners<T: Clone> {
   winners: Vec<RankedWinner<T>>,
   num_winners: u32,
@@ -30,7 +35,7 @@ impl<T: Clone + Eq> RankedWinners<T> {
   }
 
   /// Clears the winners, returning all winner-rank pairs as an iterator.
-  pub fn drain<R>(&mut self, range: R) -> std::vec::Drain<'_, RankedWinner<T>>
+  pub fn drain<R>(&mut self, range: R) -> alloc::vec::Drain<'_, RankedWinner<T>>
   where
     R: RangeBounds<usize>,
   {
@@ -136,7 +141,7 @@ impl<T: Clone + Eq> RankedWinners<T> {
 
   // Sort the winners by rank.
   pub(crate) fn sort(&mut self) {
-    self.winners.sort_by(|a, b| a.1.cmp(&b.1));
+    self.winners.sort_by_key(|w| w.1);
   }
 
   // Create winners from a list of ranked candidates
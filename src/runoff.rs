@@ -0,0 +1,117 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::result::CountedCandidates;
+use super::result::RankedWinners;
+use super::Numeric;
+
+/// A single round of a runoff count.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Round<T, C> {
+    /// The votes held by every continuing candidate during this round, in the order they were first seen.
+    /// Without surplus transfers, these are the first preference votes for continuing candidates.
+    pub totals: Vec<(T, C)>,
+
+    /// The candidates elected at the end of this round. In a single winner count, this is only set in the final round,
+    /// to the candidates with the most votes. It only contains more than one candidate if they are tied.
+    pub elected: Vec<T>,
+
+    /// The candidates eliminated at the end of this round. This is empty in the final round.
+    pub eliminated: Vec<T>,
+}
+
+// A runoff method, which counts the votes for the continuing candidates and chooses who to eliminate in each round.
+// Candidates are identified by their position in the list of candidates, and `continuing` marks the candidates that haven't been eliminated.
+pub(crate) trait Elimination<C> {
+    // Count the first preference votes for each candidate. Eliminated candidates should have a count of zero.
+    fn count(&self, continuing: &[bool]) -> Vec<C>;
+
+    // Choose the candidates to eliminate this round, given the first preference votes from `count()`.
+    fn eliminate(&self, continuing: &[bool], totals: &[C]) -> Vec<usize>;
+
+    // Whether the leading candidate, with `votes` of the `active` first preference votes for continuing candidates, is elected.
+    fn elected(&self, votes: C, active: C) -> bool;
+}
+
+// Run a runoff count, eliminating candidates round by round until the leading candidate is elected, or only one candidate is left.
+// If every continuing candidate would be eliminated, the count stops instead.
+pub(crate) fn count<T, C, E>(candidates: &[T], method: &E) -> Vec<Round<T, C>>
+where
+    T: Clone,
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive,
+    E: Elimination<C>,
+{
+    let mut rounds = Vec::new();
+    let mut continuing = vec![true; candidates.len()];
+    let mut num_continuing = candidates.len();
+    while num_continuing > 0 {
+        let totals = method.count(&continuing);
+        let mut round = Round {
+            totals: Vec::with_capacity(num_continuing),
+            elected: Vec::new(),
+            eliminated: Vec::new(),
+        };
+
+        let mut active = C::zero();
+        let mut leader = C::zero();
+        for (candidate, total) in totals.iter().enumerate().filter(|(candidate, _)| continuing[*candidate]) {
+            round.totals.push((candidates[candidate].clone(), *total));
+            active += *total;
+            if *total > leader {
+                leader = *total;
+            }
+        }
+        let finished = num_continuing == 1 || method.elected(leader, active);
+        let eliminated = if finished { Vec::new() } else { method.eliminate(&continuing, &totals) };
+        if eliminated.is_empty() || eliminated.len() >= num_continuing {
+            // The count is over, and the candidates with the most votes are elected.
+            for (candidate, total) in round.totals.iter() {
+                if *total == leader {
+                    round.elected.push(candidate.clone());
+                }
+            }
+            rounds.push(round);
+            break;
+        }
+        for candidate in eliminated {
+            continuing[candidate] = false;
+            num_continuing -= 1;
+            round.eliminated.push(candidates[candidate].clone());
+        }
+        rounds.push(round);
+    }
+    return rounds;
+}
+
+// Rank the candidates from a runoff count. Candidates left in the final round are ranked by their first preference votes,
+// followed by the eliminated candidates, with the candidates eliminated last ranked highest.
+pub(crate) fn winners<T, C>(rounds: Vec<Round<T, C>>, num_winners: u32) -> RankedWinners<T>
+where
+    T: Clone + Eq,
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive,
+{
+    let mut rounds = rounds;
+    let mut ranked = match rounds.pop() {
+        Some(last) => {
+            let mut counted = CountedCandidates::new();
+            for (candidate, total) in last.totals {
+                counted.push(candidate, total);
+            }
+            counted.into_ranked(0).into_vec()
+        }
+        None => Vec::new(),
+    };
+    for round in rounds.into_iter().rev() {
+        let rank = ranked.last().map_or(0, |(_, rank)| rank + 1);
+        for candidate in round.eliminated {
+            ranked.push((candidate, rank));
+        }
+    }
+    return RankedWinners::from_ranked(ranked, num_winners);
+}
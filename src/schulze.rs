@@ -1,16 +1,29 @@
-use hashbrown::HashMap;
-use num_traits::cast::NumCast;
-use num_traits::Num;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "std")]
 use petgraph::Graph;
-use std::hash::Hash;
-use std::ops::AddAssign;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use super::condorcet::CondorcetTally;
 use super::plurality::PluralityTally;
 use super::result::CountedCandidates;
 use super::result::RankedWinners;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
+use super::RankedBallot;
+use super::TallyError;
+use super::COUNT_OVERFLOW_PANIC;
 
 /// Specifies method used to measure the strength of a link in a set of strongest paths. `Winning` variant is recommended.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Variant {
   /// Strength of a link is measured by its support. You should use this variant if you are unsure.
   ///
@@ -25,6 +38,7 @@ pub enum Variant {
   /// The strength of a link is measured by the ratio of its support and opposition.
   ///
   /// When the strength of the link `ef` is measured by ratio, then its strength is the ratio `N[e,f] / N[f,e]` between its support `N[e,f]` and its opposition `N[f,e]`.
+  /// Links without any opposition are stronger than every other link, and are all equally strong.
   Ratio,
 
   /// The strength of a link is measured by its opposition. Not recommended.
@@ -50,7 +64,7 @@ pub enum Variant {
 ///    tally.add(vec!["Notorious RBG", "Judge Dredd"]);
 ///
 ///    let winners = tally.winners().into_unranked();
-///    assert!(winners[0] == "Notorious RBG");
+///    assert!(winners[0] == "Abe Vigoda");
 /// ```
 pub type DefaultSchulzeTally<T> = SchulzeTally<T, u64>;
 
@@ -73,12 +87,13 @@ pub type DefaultSchulzeTally<T> = SchulzeTally<T, u64>;
 ///    tally.add_weighted(vec!["Notorious RBG", "Judge Dredd"], 1.0);
 ///
 ///    let winners = tally.winners().into_unranked();
-///    assert!(winners[0] == "Notorious RBG");
+///    assert!(winners[0] == "Abe Vigoda");
 /// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SchulzeTally<T, C = u64>
 where
-  T: Eq + Clone + Hash,                             // Candidate
-  C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+  T: Eq + Clone + Hash,                                       // Candidate
+  C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
   variant: Variant,
   condorcet: CondorcetTally<T, C>,
@@ -86,8 +101,8 @@ where
 
 impl<T, C> SchulzeTally<T, C>
 where
-  T: Eq + Clone + Hash,                             // Candidate
-  C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+  T: Eq + Clone + Hash,                                       // Candidate
+  C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
   /// Create a new `SchulzeTally` with the given number of winners.
   ///
@@ -108,142 +123,257 @@ where
     };
   }
 
+  /// Create a new `SchulzeTally` with the given number of winners, for an election between the given candidates.
+  ///
+  /// Standing candidates that are not ranked on a vote are counted as less preferred than every candidate ranked on it.
+  /// See [`CondorcetTally::with_candidates()`](../condorcet/struct.CondorcetTally.html#method.with_candidates) for details.
+  pub fn with_candidates(num_winners: u32, variant: Variant, candidates: Candidates<T>) -> Self {
+    return SchulzeTally {
+      variant: variant,
+      condorcet: CondorcetTally::with_candidates(num_winners, candidates),
+    };
+  }
+
   /// Add a new vote
-  pub fn add(&mut self, mut selection: Vec<T>) {
-    self.condorcet.add(selection);
+  ///
+  /// An error will only be returned if `selection` contains duplicate candidates.
+  pub fn add(&mut self, selection: Vec<T>) -> Result<(), TallyError> {
+    self.condorcet.add(selection)
   }
 
   /// Add a vote by reference.
-  pub fn add_ref(&mut self, selection: &[T]) {
-    self.condorcet.add_ref(selection);
+  pub fn add_ref(&mut self, selection: &[T]) -> Result<(), TallyError> {
+    self.condorcet.add_ref(selection)
   }
 
   /// Add a weighted vote.
   /// By default takes a weight as a `usize` integer, but can be customized by using `SchulzeTally` with a custom count type.
-  pub fn add_weighted(&mut self, mut selection: Vec<T>, weight: C) {
-    self.condorcet.add_weighted(selection, weight);
+  pub fn add_weighted(&mut self, selection: Vec<T>, weight: C) -> Result<(), TallyError> {
+    self.condorcet.add_weighted(selection, weight)
   }
 
   /// Add a weighted vote by reference.
-  pub fn add_weighted_ref(&mut self, selection: &[T], weight: C) {
-    self.condorcet.add_weighted_ref(selection, weight);
+  pub fn add_weighted_ref(&mut self, selection: &[T], weight: C) -> Result<(), TallyError> {
+    self.condorcet.add_weighted_ref(selection, weight)
+  }
+
+  /// Add a new ranked vote that may contain tied candidates.
+  ///
+  /// For each pair of tied candidates, both candidates are given half a point over the other.
+  /// If `vote` contains ties, a float or rational count type must be used, otherwise an error is returned.
+  pub fn add_ranked(&mut self, vote: RankedBallot<T>) -> Result<(), TallyError> {
+    self.condorcet.add_ranked(vote)
+  }
+
+  /// Add a ranked vote by reference.
+  pub fn add_ranked_ref(&mut self, vote: &RankedBallot<T>) -> Result<(), TallyError> {
+    self.condorcet.add_ranked_ref(vote)
+  }
+
+  /// Add a weighted ranked vote.
+  pub fn add_ranked_weighted(&mut self, vote: RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+    self.condorcet.add_ranked_weighted(vote, weight)
+  }
+
+  /// Add a weighted ranked vote by reference.
+  pub fn add_ranked_weighted_ref(&mut self, vote: &RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+    self.condorcet.add_ranked_weighted_ref(vote, weight)
+  }
+
+  /// Add all votes in a `BallotBox`.
+  ///
+  /// If the ballot box contains ties, a float or rational count type must be used, otherwise an error is returned.
+  pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+    self.condorcet.add_ballot_box(ballot_box)
+  }
+
+  /// Merge another tally into this one, as if all of its votes had been added to this tally.
+  ///
+  /// Tallies can be merged in any order. See [`CondorcetTally::merge()`](../condorcet/struct.CondorcetTally.html#method.merge) for details.
+  /// The variant and number of winners for `other` are ignored.
+  pub fn merge(&mut self, other: Self)
+  where
+    T: Ord,
+  {
+    self.condorcet.merge(other.condorcet);
   }
 
   /// Get a list of all candidates seen by this tally.
-  /// Candidates are returned in no particular order.
+  /// Candidates are returned in the order they were first seen.
   pub fn candidates(&self) -> Vec<T> {
     return self.condorcet.candidates();
   }
 
+  /// Sort candidates by their `Ord` order instead of the order they were first seen.
+  /// See [Determinism](../index.html#determinism) for details.
+  pub fn sort_candidates(&mut self)
+  where
+    T: Ord,
+  {
+    self.condorcet.sort_candidates();
+  }
+
+  /// Get total pairwise counts for this tally.
+  /// See [`CondorcetTally::totals()`](../condorcet/struct.CondorcetTally.html#method.totals) for details.
   pub fn totals(&self) -> Vec<((T, T), C)> {
     return self.condorcet.totals();
   }
 
+  /// Get the strength of the strongest path between each pair of candidates.
+  ///
+  /// For a pair `((T1, T2), C)`, `C` is the strength of the strongest path from candidate `T1` to candidate `T2`.
+  /// Pairs are ordered by `T1`, then by `T2`, in the order candidates were first seen.
   pub fn strongest_paths(&self) -> Vec<((T, T), C)> {
-    // See: https://en.wikipedia.org/wiki/Schulze_method#Implementations
-
-    let zero = C::zero();
-    let mut p = HashMap::<(usize, usize), C>::new();
-    for ((i, j), count) in self.condorcet.running_total.iter() {
-      let count_2 = self.condorcet.running_total.get(&(*j, *i)).unwrap_or(&zero);
-      if count > count_2 {
-        let strength = match self.variant {
-          Variant::Winning => *count,
-          Variant::Margin => *count - *count_2,
-          Variant::Ratio => *count / *count_2,
-          Variant::Losing => *count_2,
-        };
-        p.insert((*i, *j), strength);
-      } else {
-        p.insert((*i, *j), zero);
+    let p = self.strongest_path_matrix();
+    let n = self.condorcet.candidate_ids.len();
+
+    let mut strongest = Vec::<((T, T), C)>::with_capacity(n * n);
+    for i in 0..n {
+      for j in 0..n {
+        if i != j {
+          let candidate1 = self.condorcet.candidate_ids[i].clone();
+          let candidate2 = self.condorcet.candidate_ids[j].clone();
+          strongest.push(((candidate1, candidate2), p[i * n + j]));
+        }
       }
     }
 
-    for ((i, j), _) in self.condorcet.running_total.iter() {
-      for k in self.condorcet.candidates.values() {
-        if i != k && j != k {
-          // p[j,k] := max(p[j,k], min(p[j,i], p[i,k]))
-          let pji = p.get(&(*j, *i)).unwrap_or(&zero);
-          let pik = p.get(&(*i, *k)).unwrap_or(&zero);
-          let pjk = p.get(&(*j, *k)).unwrap_or(&zero);
-          let min;
-          if pji < pik {
-            min = pji;
-          } else {
-            min = pik;
-          }
-          let max;
-          if pjk > min {
-            max = pjk;
-          } else {
-            max = min;
+    return strongest;
+  }
+
+  // Compute the strongest paths as a dense `n * n` matrix, indexed by candidate id.
+  fn strongest_path_matrix(&self) -> Vec<C> {
+    // See: https://en.wikipedia.org/wiki/Schulze_method#Implementations
+
+    let zero = C::zero();
+    let matrix = &self.condorcet.running_total;
+    let n = matrix.len();
+
+    let mut p = vec![zero; n * n];
+    let mut unopposed = Vec::new();
+    let mut strongest = zero;
+    for i in 0..n {
+      for j in 0..n {
+        let count = matrix.get(i, j);
+        let count_2 = matrix.get(j, i);
+        if i != j && count > count_2 {
+          p[i * n + j] = match self.variant {
+            Variant::Winning => count,
+            Variant::Margin => count - count_2,
+            Variant::Ratio if count_2 == zero => {
+              unopposed.push(i * n + j);
+              zero
+            }
+            Variant::Ratio => count / count_2,
+            Variant::Losing => count_2,
+          };
+          if p[i * n + j] > strongest {
+            strongest = p[i * n + j];
           }
-          p.insert((*j, *k), *max);
         }
       }
     }
 
-    let mut strongest = Vec::<((T, T), C)>::with_capacity(self.condorcet.running_total.len());
-
-    // Invert the candidate map.
-    let mut candidates = HashMap::<usize, T>::with_capacity(self.condorcet.candidates.len());
-    for (candidate, i) in self.condorcet.candidates.iter() {
-      candidates.insert(*i, candidate.clone());
+    // An unopposed link has an infinite ratio, which integer and rational count types can't represent.
+    // Unopposed links are instead given the same strength, which is stronger than every other link.
+    for link in unopposed {
+      p[link] = strongest + C::one();
     }
 
-    for ((candidate1, candidate2), strength) in p.iter() {
-      // Ok to unwrap here since candidates must exist.
-      let candidate1 = candidates.get(candidate1).unwrap().clone();
-      let candidate2 = candidates.get(candidate2).unwrap().clone();
-      strongest.push(((candidate1, candidate2), *strength));
+    for i in 0..n {
+      for j in 0..n {
+        if i == j {
+          continue;
+        }
+        for k in 0..n {
+          if i != k && j != k {
+            // p[j,k] := max(p[j,k], min(p[j,i], p[i,k]))
+            let pji = p[j * n + i];
+            let pik = p[i * n + k];
+            let min = if pji < pik { pji } else { pik };
+            if p[j * n + k] < min {
+              p[j * n + k] = min;
+            }
+          }
+        }
+      }
     }
 
-    return strongest;
+    return p;
   }
 
-  crate fn get_counted(&self) -> CountedCandidates<T, C> {
-    let mut strongest = self.strongest_paths();
-
-    // Convert strongest to a hashmap
-    let mut strongest_hash = HashMap::<(T, T), C>::with_capacity(strongest.len());
-    for ((candidate_1, candidate_2), strength) in strongest.drain(..) {
-      strongest_hash.insert((candidate_1, candidate_2), strength);
-    }
+  pub(crate) fn get_counted(&self) -> CountedCandidates<T, C> {
+    let p = self.strongest_path_matrix();
+    let n = self.condorcet.candidate_ids.len();
 
     // Make a little plurality tally for counting up pairwise strength competition.
-    let mut running_total = PluralityTally::with_capacity(self.condorcet.num_winners, self.condorcet.candidates.len());
-
-    let zero = C::zero();
-    for ((candidate_1, candidate_2), strength_1) in strongest_hash.iter() {
-      // Cloning here is dumb, but unable to construct a key tuple otherwise
-      let strength_2 = strongest_hash.get(&(candidate_2.clone(), candidate_1.clone())).unwrap_or(&zero);
-      if strength_1 >= strength_2 {
-        running_total.add_ref(candidate_1);
-      } else {
-        // Add it with a weight of zero
-        running_total.add_weighted_ref(candidate_1, C::zero());
+    // Candidates are added in id order so that ties are listed in the order they were first seen.
+    let mut running_total = PluralityTally::with_capacity(self.condorcet.num_winners, n);
+    for i in 0..n {
+      let candidate = &self.condorcet.candidate_ids[i];
+      running_total.count(candidate, C::zero()).expect(COUNT_OVERFLOW_PANIC);
+      for j in 0..n {
+        if i != j && p[i * n + j] >= p[j * n + i] {
+          running_total.count(candidate, C::one()).expect(COUNT_OVERFLOW_PANIC);
+        }
       }
     }
 
     return running_total.get_counted();
   }
 
+  /// Get a ranked list of all candidates. Candidates with the same rank are tied, and are listed in the order they were first seen.
   pub fn ranked(&self) -> Vec<(T, u32)> {
     return self.get_counted().into_ranked(0).into_vec();
   }
 
-  /// Get a ranked list of winners. Winners with the same rank are tied.
+  /// Get a ranked list of winners. Winners with the same rank are tied, and are listed in the order they were first seen.
   /// The number of winners might be greater than the requested `num_winners` if there is a tie.
-  /// In approval voting, the winning candidate(s) is the one most approved by all voters.
   pub fn winners(&self) -> RankedWinners<T> {
     return self.get_counted().into_ranked(self.condorcet.num_winners);
   }
 
+  /// Build a graph representing all pairwise competitions between all candidates.
+  ///
+  /// See [`CondorcetTally::build_graph`](../condorcet/struct.CondorcetTally.html#method.build_graph) for details.
+  /// This method requires the `std` feature.
+  #[cfg(feature = "std")]
   pub fn build_graph(&self) -> Graph<T, (C, C)> {
     return self.condorcet.build_graph();
   }
 }
 
+#[cfg(feature = "parallel")]
+impl<T, C> SchulzeTally<T, C>
+where
+  T: Eq + Clone + Hash + Send + Sync,                                       // Candidate
+  C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send + Sync, // Vote count type
+{
+  /// Create a new `SchulzeTally` from a parallel iterator of votes.
+  ///
+  /// See [`CondorcetTally::from_par_iter()`](../condorcet/struct.CondorcetTally.html#method.from_par_iter) for details.
+  /// Requires the `parallel` feature.
+  pub fn from_par_iter<I>(num_winners: u32, variant: Variant, votes: I) -> Result<Self, TallyError>
+  where
+    I: IntoParallelIterator<Item = Vec<T>>,
+  {
+    let mut tally = Self::new(num_winners, variant);
+    tally.par_add_all(votes)?;
+    return Ok(tally);
+  }
+
+  /// Add many votes in parallel.
+  ///
+  /// See [`CondorcetTally::par_add_all()`](../condorcet/struct.CondorcetTally.html#method.par_add_all) for details.
+  /// Requires the `parallel` feature.
+  pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+  where
+    I: IntoParallelIterator<Item = Vec<T>>,
+  {
+    self.condorcet.par_add_all(votes)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -253,14 +383,14 @@ mod tests {
     // See: https://en.wikipedia.org/wiki/Schulze_method
 
     let mut tally = DefaultSchulzeTally::new(1, Variant::Winning);
-    tally.add_weighted(vec!["A", "C", "B", "E", "D"], 5);
-    tally.add_weighted(vec!["A", "D", "E", "C", "B"], 5);
-    tally.add_weighted(vec!["B", "E", "D", "A", "C"], 8);
-    tally.add_weighted(vec!["C", "A", "B", "E", "D"], 3);
-    tally.add_weighted(vec!["C", "A", "E", "B", "D"], 7);
-    tally.add_weighted(vec!["C", "B", "A", "D", "E"], 2);
-    tally.add_weighted(vec!["D", "C", "E", "B", "A"], 7);
-    tally.add_weighted(vec!["E", "B", "A", "D", "C"], 8);
+    tally.add_weighted(vec!["A", "C", "B", "E", "D"], 5).unwrap();
+    tally.add_weighted(vec!["A", "D", "E", "C", "B"], 5).unwrap();
+    tally.add_weighted(vec!["B", "E", "D", "A", "C"], 8).unwrap();
+    tally.add_weighted(vec!["C", "A", "B", "E", "D"], 3).unwrap();
+    tally.add_weighted(vec!["C", "A", "E", "B", "D"], 7).unwrap();
+    tally.add_weighted(vec!["C", "B", "A", "D", "E"], 2).unwrap();
+    tally.add_weighted(vec!["D", "C", "E", "B", "A"], 7).unwrap();
+    tally.add_weighted(vec!["E", "B", "A", "D", "C"], 8).unwrap();
 
     // Verify totals
     let totals = tally.totals();
@@ -333,19 +463,68 @@ mod tests {
     assert_eq!(ranked, vec![("E", 0), ("A", 1), ("C", 2), ("B", 3), ("D", 4)]);
   }
 
+  #[test]
+  fn schulze_path_widening() {
+    // No vote ranks B above A, but paths can still be widened through B.
+    let mut tally = DefaultSchulzeTally::new(1, Variant::Winning);
+    tally.add(vec!["A", "B"]).unwrap();
+    tally.add(vec!["B", "C"]).unwrap();
+
+    let strongest = tally.strongest_paths();
+    assert!(strongest.contains(&(("A", "C"), 1)));
+    assert!(strongest.contains(&(("C", "A"), 0)));
+    assert_eq!(tally.ranked(), vec![("A", 0), ("B", 1), ("C", 2)]);
+  }
+
   #[test]
   fn schulze_example_4() {
     // See Example 4: https://arxiv.org/pdf/1804.02973.pdf
 
     let mut tally = DefaultSchulzeTally::new(1, Variant::Winning);
-    tally.add_weighted(vec!["a", "b", "c", "d"], 12);
-    tally.add_weighted(vec!["a", "d", "b", "c"], 6);
-    tally.add_weighted(vec!["b", "c", "d", "a"], 9);
-    tally.add_weighted(vec!["c", "d", "a", "b"], 15);
-    tally.add_weighted(vec!["d", "b", "a", "c"], 21);
+    tally.add_weighted(vec!["a", "b", "c", "d"], 12).unwrap();
+    tally.add_weighted(vec!["a", "d", "b", "c"], 6).unwrap();
+    tally.add_weighted(vec!["b", "c", "d", "a"], 9).unwrap();
+    tally.add_weighted(vec!["c", "d", "a", "b"], 15).unwrap();
+    tally.add_weighted(vec!["d", "b", "a", "c"], 21).unwrap();
 
-    // Verify ranking - "a" and "b" are tied.
+    // Verify ranking - "a" and "b" are tied, and are listed in the order they were first seen.
     let ranked = tally.ranked();
-    assert_eq!(ranked, vec![("d", 0), ("b", 1), ("a", 1), ("c", 2)]);
+    assert_eq!(ranked, vec![("d", 0), ("a", 1), ("b", 1), ("c", 2)]);
+  }
+
+  #[cfg(feature = "rational")]
+  #[test]
+  fn schulze_rational() -> Result<(), TallyError> {
+    use num_rational::Rational64;
+
+    let r = |numer, denom| Rational64::new(numer, denom);
+    let mut tally = SchulzeTally::<&str, Rational64>::new(1, Variant::Ratio);
+    tally.add_weighted(vec!["A", "B", "C", "D"], r(3, 1))?;
+    tally.add_weighted(vec!["B", "C", "A", "D"], r(2, 1))?;
+    tally.add_weighted(vec!["C", "A", "B", "D"], r(2, 1))?;
+
+    // Links: A -> B is 5/2, B -> C is 5/2, C -> A is 4/3. Every candidate has an unopposed link to D,
+    // which is stronger than every other link.
+    let strongest = tally.strongest_paths();
+    for (pairwise, strength) in strongest.iter() {
+      match pairwise {
+        ("A", "B") => assert_eq!(*strength, r(5, 2)),
+        ("A", "C") => assert_eq!(*strength, r(5, 2)),
+        ("B", "A") => assert_eq!(*strength, r(4, 3)),
+        ("B", "C") => assert_eq!(*strength, r(5, 2)),
+        ("C", "A") => assert_eq!(*strength, r(4, 3)),
+        ("C", "B") => assert_eq!(*strength, r(4, 3)),
+        (_, "D") => assert_eq!(*strength, r(7, 2)),
+        (_, _) => assert_eq!(*strength, r(0, 1)),
+      }
+    }
+    assert_eq!(tally.ranked(), vec![("A", 0), ("B", 1), ("C", 2), ("D", 3)]);
+
+    // Unopposed links no longer cause a division by zero with an integer count type.
+    let mut tally = DefaultSchulzeTally::new(1, Variant::Ratio);
+    tally.add(vec!["A", "B"])?;
+    assert_eq!(tally.winners().into_unranked(), vec!["A"]);
+
+    Ok(())
   }
 }
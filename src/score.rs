@@ -1,10 +1,19 @@
-use num_traits::cast::NumCast;
-use num_traits::Num;
-use std::hash::Hash;
-use std::ops::AddAssign;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::check_duplicate;
 use super::plurality::PluralityTally;
 use super::result::RankedWinners;
+use super::Candidates;
+use super::Numeric;
+use super::TallyError;
 
 /// A score tally using `u64` integers to count votes.
 /// `DefaultScoreTally` is generally preferred over `ScoreTally`.
@@ -46,18 +55,19 @@ pub type DefaultScoreTally<T> = ScoreTally<T, u64>;
 ///    let winners = tally.winners().into_unranked();
 ///    assert!(winners[0] == "Notorious RBG");
 /// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ScoreTally<T, C = u64>
 where
-    T: Eq + Clone + Hash,                             // Candidate
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
     plurality: PluralityTally<T, C>,
 }
 
 impl<T, C> ScoreTally<T, C>
 where
-    T: Eq + Clone + Hash,                             // Candidate
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // Vote count type
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
 {
     /// Create a new `ScoreTally` with the given number of winners.
     ///
@@ -76,42 +86,81 @@ where
         };
     }
 
+    /// Create a new `ScoreTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// See [`PluralityTally::with_candidates()`](../plurality/struct.PluralityTally.html#method.with_candidates) for details.
+    pub fn with_candidates(num_winners: u32, candidates: Candidates<T>) -> Self {
+        return ScoreTally {
+            plurality: PluralityTally::with_candidates(num_winners, candidates),
+        };
+    }
+
     /// Add a new vote
-    pub fn add(&mut self, mut selection: Vec<(T, C)>) {
-        for (vote, score) in selection.drain(0..) {
-            self.plurality.add_weighted(vote, score);
-        }
+    ///
+    /// An error is returned if `selection` scores the same candidate more than once, or if the tally was created with
+    /// [`with_candidates()`](#method.with_candidates) and `selection` scores an unknown candidate.
+    pub fn add(&mut self, selection: Vec<(T, C)>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, C::one())
     }
 
     /// Add a vote by reference.
-    pub fn add_ref(&mut self, selection: &[(T, C)]) {
-        for (vote, score) in selection {
-            self.plurality.add_weighted_ref(vote, *score);
-        }
+    pub fn add_ref(&mut self, selection: &[(T, C)]) -> Result<(), TallyError> {
+        self.add_weighted_ref(selection, C::one())
     }
 
     /// Add a weighted vote.
     /// By default takes a weight as a `usize` integer, but can be customized by using `ApprovalTally` with a custom vote type.
-    pub fn add_weighted(&mut self, mut selection: Vec<(T, C)>, weight: C) {
-        for (vote, score) in selection.drain(0..) {
-            self.plurality.add_weighted(vote, weight * score);
-        }
+    pub fn add_weighted(&mut self, selection: Vec<(T, C)>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, weight)
     }
 
     /// Add a weighted vote by reference.
-    pub fn add_weighted_ref(&mut self, selection: &[(T, C)], weight: C) {
+    pub fn add_weighted_ref(&mut self, selection: &[(T, C)], weight: C) -> Result<(), TallyError> {
+        check_duplicate_scores(selection)?;
+        if let Some(registry) = &self.plurality.registry {
+            registry.filter_scores(selection)?;
+        }
         for (vote, score) in selection {
-            self.plurality.add_weighted_ref(vote, weight * *score);
+            let weighted_score = weight.checked_mul(*score).ok_or(TallyError::CountOverflow)?;
+            self.plurality.add_weighted_ref(vote, weighted_score)?;
         }
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Tallies can be merged in any order. See [`PluralityTally::merge()`](../plurality/struct.PluralityTally.html#method.merge) for details.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.plurality.merge(other.plurality);
+    }
+
+    // Create an empty tally that checks votes against the same registry, to count part of the votes separately.
+    #[cfg(feature = "parallel")]
+    fn fork(&self) -> Self {
+        return ScoreTally {
+            plurality: self.plurality.fork(),
+        };
     }
 
     /// Get a list of all candidates seen by this tally.
-    /// Candidates are returned in no particular order.
+    /// Candidates are returned in the order they were first seen.
     pub fn candidates(&self) -> Vec<T> {
         return self.plurality.candidates();
     }
 
-    /// Get a ranked list of winners. Winners with the same rank are tied.
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        self.plurality.sort_candidates();
+    }
+
+    /// Get a ranked list of winners. Winners with the same rank are tied, and are listed in the order they were first seen.
     /// The number of winners might be greater than the requested `num_winners` if there is a tie.
     /// In score voting, the winning candidate(s) is the one with the highest total score.
     pub fn winners(&self) -> RankedWinners<T> {
@@ -142,7 +191,7 @@ where
         return self.plurality.totals();
     }
 
-    /// Get a ranked list of all candidates. Candidates with the same rank are tied.
+    /// Get a ranked list of all candidates. Candidates with the same rank are tied, and are listed in the order they were first seen.
     /// Candidates are ranked in ascending order. The highest ranked candidate has a rank of `0`.
     ///
     /// # Example
@@ -166,6 +215,62 @@ where
     }
 }
 
+#[cfg(feature = "parallel")]
+impl<T, C> ScoreTally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send + Sync, // Vote count type
+{
+    /// Create a new `ScoreTally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and counted into per-thread tallies, which are then merged.
+    /// Requires the `parallel` feature.
+    pub fn from_par_iter<I>(num_winners: u32, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<(T, C)>>,
+    {
+        let mut tally = Self::new(num_winners);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// Votes are split across threads and counted into per-thread tallies, which are then merged into this tally.
+    /// If any vote scores the same candidate more than once or scores an unknown candidate, an error is returned
+    /// and this tally is left unchanged.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<(T, C)>>,
+    {
+        let tally = votes
+            .into_par_iter()
+            .try_fold(
+                || self.fork(),
+                |mut tally, vote| {
+                    tally.add(vote)?;
+                    Ok(tally)
+                },
+            )
+            .try_reduce(
+                || self.fork(),
+                |mut tally, other| {
+                    tally.plurality.merge_counts(other.plurality);
+                    Ok(tally)
+                },
+            )?;
+        self.plurality.merge_counts(tally.plurality);
+        Ok(())
+    }
+}
+
+// Check if a score vote scores the same candidate more than once.
+pub(crate) fn check_duplicate_scores<T: PartialEq, C>(selection: &[(T, C)]) -> Result<(), TallyError> {
+    let candidates: Vec<&T> = selection.iter().map(|(candidate, _)| candidate).collect();
+    check_duplicate(&candidates)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,9 +278,9 @@ mod tests {
     #[test]
     fn score_basic() {
         let mut tally = ScoreTally::new(1);
-        tally.add(vec![("Alice", 10), ("Bob", 4)]);
-        tally.add_ref(&vec![("Alice", 2), ("Bob", 2)]);
-        tally.add_weighted_ref(&vec![("Alice", 1), ("Bob", 1)], 5);
+        tally.add(vec![("Alice", 10), ("Bob", 4)]).unwrap();
+        tally.add_ref(&[("Alice", 2), ("Bob", 2)]).unwrap();
+        tally.add_weighted_ref(&[("Alice", 1), ("Bob", 1)], 5).unwrap();
 
         let candidates = tally.candidates();
         assert_eq!(candidates.len(), 2);
@@ -198,10 +303,10 @@ mod tests {
         // From: https://en.wikipedia.org/wiki/Score_voting
 
         let mut tally = ScoreTally::with_capacity(1, 4);
-        tally.add_weighted(vec![("Memphis", 10), ("Nashville", 4), ("Chattanooga", 2), ("Knoxville", 0)], 42);
-        tally.add_weighted(vec![("Memphis", 0), ("Nashville", 10), ("Chattanooga", 4), ("Knoxville", 2)], 26);
-        tally.add_weighted(vec![("Memphis", 0), ("Nashville", 6), ("Chattanooga", 10), ("Knoxville", 6)], 15);
-        tally.add_weighted(vec![("Memphis", 0), ("Nashville", 5), ("Chattanooga", 7), ("Knoxville", 10)], 17);
+        tally.add_weighted(vec![("Memphis", 10), ("Nashville", 4), ("Chattanooga", 2), ("Knoxville", 0)], 42).unwrap();
+        tally.add_weighted(vec![("Memphis", 0), ("Nashville", 10), ("Chattanooga", 4), ("Knoxville", 2)], 26).unwrap();
+        tally.add_weighted(vec![("Memphis", 0), ("Nashville", 6), ("Chattanooga", 10), ("Knoxville", 6)], 15).unwrap();
+        tally.add_weighted(vec![("Memphis", 0), ("Nashville", 5), ("Chattanooga", 7), ("Knoxville", 10)], 17).unwrap();
 
         let candidates = tally.candidates();
         assert_eq!(candidates.len(), 4);
@@ -221,4 +326,27 @@ mod tests {
         assert_eq!(winners.overflow(), Option::None);
         assert_eq!(winners.all(), vec!["Nashville"]);
     }
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn score_parallel() {
+        let mut votes = Vec::new();
+        for _ in 0..1_000 {
+            votes.push(vec![("Alice", 5), ("Bob", 2)]);
+            votes.push(vec![("Bob", 4), ("Carlos", 1)]);
+        }
+
+        let mut tally = DefaultScoreTally::new(1);
+        tally.add(vec![("Carlos", 10)]).unwrap();
+        tally.par_add_all(votes).unwrap();
+        assert_eq!(tally.totals(), vec![("Bob", 6_000), ("Alice", 5_000), ("Carlos", 1_010)]);
+    }
+
+    #[test]
+    fn score_overflow() {
+        let mut tally = ScoreTally::<&str, u8>::new(1);
+        tally.add_weighted(vec![("Alice", 10)], 20).unwrap();
+        assert!(matches!(tally.add_weighted(vec![("Alice", 10)], 30), Err(TallyError::CountOverflow)));
+        assert!(matches!(tally.add(vec![("Alice", 60)]), Err(TallyError::CountOverflow)));
+        assert_eq!(tally.totals(), vec![("Alice", 200)]);
+    }
 }
@@ -0,0 +1,505 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::result::RankedWinners;
+use super::score::check_duplicate_scores;
+use super::Candidates;
+use super::Numeric;
+use super::TallyError;
+use super::{add_count, sorted_order, COUNT_OVERFLOW_PANIC, C_FROM_PANIC};
+
+/// Specifies how votes are reweighted after each winner is elected.
+///
+/// Every variant elects one winner each round, choosing the candidate with the highest total of weighted scores.
+/// The variants differ in how much of a vote is used up by electing the candidates it scored.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Variant {
+    /// Reweighted Range Voting (RRV). A vote that gave a total of `sum` to the candidates elected so far has a weight of
+    ///
+    /// ```1 / (1 + sum / max-score)```
+    Reweighted,
+
+    /// Sequentially Spent Score (SSS). Each vote starts with a budget equal to the maximum score, and each score counts
+    /// for at most the vote's remaining budget. When a candidate is elected, the votes that scored them spend a quota,
+    /// `total-votes * max-score / total-seats`, in proportion to the scores they gave. If the candidate's total is less
+    /// than a quota, the votes spend all of the scores they gave.
+    SequentiallySpent,
+
+    /// Allocated Score, also known as STAR-PR. When a candidate is elected, a quota of votes, `total-votes / total-seats`,
+    /// is allocated to them and takes no further part in the count. The votes that gave the candidate the highest scores
+    /// are allocated first. If only part of the votes that gave a score are needed to fill the quota, all of those votes
+    /// are reweighted so that a quota is allocated.
+    Allocated,
+}
+
+/// A single round of a proportional score count.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Round<T, C> {
+    /// The candidates elected this round. This only contains more than one candidate if several candidates tie for the last seat.
+    pub elected: Vec<T>,
+
+    /// The total weighted score of every candidate that had not been elected before this round, in the order they were first seen.
+    pub totals: Vec<(T, C)>,
+}
+
+/// A proportional score tally using `f64` floats to count votes.
+/// `DefaultScorePrTally` is generally preferred over `ScorePrTally`.
+/// Since this is an alias, refer to [`ScorePrTally`](struct.ScorePrTally.html) for method documentation.
+///
+/// # Example
+/// ```
+///    use tallystick::score_pr::DefaultScorePrTally;
+///    use tallystick::score_pr::Variant;
+///
+///    // Scores are out of 5. Six voters like Alice and Bob, and four voters like Carlos.
+///    let mut tally = DefaultScorePrTally::new(2, 5.0, Variant::Reweighted);
+///    tally.add_weighted(vec![("Alice", 5.0), ("Bob", 5.0)], 6.0).unwrap();
+///    tally.add_weighted(vec![("Carlos", 5.0)], 4.0).unwrap();
+///
+///    let winners = tally.winners().into_unranked();
+///    assert_eq!(winners, vec!["Alice", "Carlos"]);
+/// ```
+pub type DefaultScorePrTally<T> = ScorePrTally<T, f64>;
+
+/// A generic proportional score tally, which elects several winners from score votes.
+///
+/// Unlike a [`ScoreTally`](../score/struct.ScoreTally.html) with several winners, which elects the candidates with the
+/// highest total scores, votes count for less once candidates they scored are elected, so that winners represent voters
+/// in proportion to their number. Votes are the same as for a `ScoreTally`.
+///
+/// Generics:
+/// - `T`: The candidate type.
+/// - `C`: The count and score type. Votes are reweighted by fractions, so a float, a [`rational`](https://rust-num.github.io/num/num_rational/index.html)
+///   (with the `rational` feature) or a [`FixedDecimal`](../struct.FixedDecimal.html) must be used.
+///
+/// # Example
+/// ```
+///    use tallystick::score_pr::ScorePrTally;
+///    use tallystick::score_pr::Variant;
+///
+///    let mut tally = ScorePrTally::<&str, f64>::new(2, 5.0, Variant::Allocated);
+///    tally.add_weighted(vec![("Alice", 5.0), ("Bob", 4.0)], 8.0).unwrap();
+///    tally.add_weighted(vec![("Carlos", 5.0)], 2.0).unwrap();
+///
+///    // Eight out of ten voters elect both winners.
+///    let winners = tally.winners().into_unranked();
+///    assert_eq!(winners, vec!["Alice", "Bob"]);
+///
+///    // A score above the maximum is rejected.
+///    assert!(tally.add(vec![("Alice", 6.0)]).is_err());
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScorePrTally<T, C = f64>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    candidates: Vec<T>,                 // Candidates in the order they were first seen
+    ballots: Vec<(Vec<(usize, C)>, C)>, // Votes as the score of each candidate, with their weight
+    totals: Vec<C>,                     // Total weighted score of each candidate, so that counting can't overflow
+    total: C,                           // Total weight of all votes
+    num_winners: u32,
+    max_score: C,
+    variant: Variant,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    registry: Option<Candidates<T>>, // The declared candidates, if any
+}
+
+impl<T, C> ScorePrTally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // Vote count type
+{
+    /// Create a new `ScorePrTally` with the given number of winners, maximum score, and variant.
+    ///
+    /// This method will panic if used with an integer based vote-count type.
+    pub fn new(num_winners: u32, max_score: C, variant: Variant) -> Self {
+        if !C::fraction() {
+            panic!("tallystick::score_pr::ScorePrTally cannot be used with an integer count type. Please use a float or a rational.")
+        }
+        return ScorePrTally {
+            candidates: Vec::new(),
+            ballots: Vec::new(),
+            totals: Vec::new(),
+            total: C::zero(),
+            num_winners: num_winners,
+            max_score: max_score,
+            variant: variant,
+            registry: None,
+        };
+    }
+
+    /// Create a new `ScorePrTally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// Every standing candidate can be elected, even if no votes score them. Withdrawn candidates are removed from votes,
+    /// and votes with unknown candidates are rejected unless write-ins are accepted.
+    /// See [`Candidates`](../struct.Candidates.html) for details.
+    pub fn with_candidates(num_winners: u32, max_score: C, variant: Variant, candidates: Candidates<T>) -> Self {
+        let mut tally = Self::new(num_winners, max_score, variant);
+        for candidate in candidates.standing() {
+            tally.intern(candidate);
+        }
+        tally.registry = Some(candidates);
+        return tally;
+    }
+
+    /// Add a new vote
+    ///
+    /// An error is returned if `selection` scores the same candidate more than once, gives a score below zero or above
+    /// the maximum score, or if the tally was created with [`with_candidates()`](#method.with_candidates) and `selection`
+    /// scores an unknown candidate.
+    pub fn add(&mut self, selection: Vec<(T, C)>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, C::one())
+    }
+
+    /// Add a vote by reference.
+    pub fn add_ref(&mut self, selection: &[(T, C)]) -> Result<(), TallyError> {
+        self.add_weighted_ref(selection, C::one())
+    }
+
+    /// Add a weighted vote.
+    pub fn add_weighted(&mut self, selection: Vec<(T, C)>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, weight)
+    }
+
+    /// Add a weighted vote by reference.
+    pub fn add_weighted_ref(&mut self, selection: &[(T, C)], weight: C) -> Result<(), TallyError> {
+        check_duplicate_scores(selection)?;
+        let filtered;
+        let selection = match &self.registry {
+            Some(registry) => {
+                filtered = registry.filter_scores(selection)?;
+                &filtered
+            }
+            None => selection,
+        };
+        if selection.iter().any(|(_, score)| *score < C::zero() || *score > self.max_score) {
+            return Err(TallyError::ScoreOutOfRange);
+        }
+
+        // Check the totals before counting the vote, so that the vote isn't partially counted.
+        let total = self.total.checked_add(weight).ok_or(TallyError::CountOverflow)?;
+        let mut totals = Vec::with_capacity(selection.len());
+        for (candidate, score) in selection.iter() {
+            let weighted = weight.checked_mul(*score).ok_or(TallyError::CountOverflow)?;
+            let candidate_total = match self.candidates.iter().position(|seen| seen == candidate) {
+                Some(id) => self.totals[id],
+                None => C::zero(),
+            };
+            totals.push(candidate_total.checked_add(weighted).ok_or(TallyError::CountOverflow)?);
+        }
+
+        let mut scores = Vec::with_capacity(selection.len());
+        for ((candidate, score), candidate_total) in selection.iter().zip(totals) {
+            let id = self.intern(candidate);
+            self.totals[id] = candidate_total;
+            scores.push((id, *score));
+        }
+        self.total = total;
+        self.ballots.push((scores, weight));
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Tallies can be merged in any order to get the same result. After merging, candidates are sorted as if by
+    /// [`sort_candidates()`](#method.sort_candidates), so their order doesn't depend on merge order either.
+    /// The variant, maximum score and number of winners for `other` are ignored.
+    ///
+    /// # Panics
+    /// This method will panic if the merged total weight of all votes, or the total score of a candidate, overflows the count type.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        let ids: Vec<usize> = other.candidates.iter().map(|candidate| self.intern(candidate)).collect();
+        for (id, candidate_total) in ids.iter().zip(other.totals) {
+            add_count(&mut self.totals[*id], candidate_total).expect(COUNT_OVERFLOW_PANIC);
+        }
+        add_count(&mut self.total, other.total).expect(COUNT_OVERFLOW_PANIC);
+        for (scores, weight) in other.ballots {
+            let scores = scores.into_iter().map(|(candidate, score)| (ids[candidate], score)).collect();
+            self.ballots.push((scores, weight));
+        }
+        self.sort_candidates();
+    }
+
+    /// Get a list of all candidates seen by this tally.
+    /// Candidates are returned in the order they were first seen.
+    pub fn candidates(&self) -> Vec<T> {
+        return self.candidates.clone();
+    }
+
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+        let order = sorted_order(&self.candidates, declared);
+        let mut ids = vec![0; order.len()];
+        for (id, old) in order.iter().enumerate() {
+            ids[*old] = id;
+        }
+        self.candidates = order.iter().map(|old| self.candidates[*old].clone()).collect();
+        self.totals = order.iter().map(|old| self.totals[*old]).collect();
+        for (scores, _) in self.ballots.iter_mut() {
+            for (candidate, _) in scores.iter_mut() {
+                *candidate = ids[*candidate];
+            }
+        }
+    }
+
+    /// Get a ranked list of winners, ranked in the order they were elected.
+    ///
+    /// If several candidates tie for the last seat, they are all returned with the same rank, and the number of winners
+    /// will be greater than `num_winners`. Other ties are broken in favour of the candidates that were seen first.
+    pub fn winners(&self) -> RankedWinners<T> {
+        let mut winners = RankedWinners::new(self.num_winners);
+        for (rank, round) in self.rounds().into_iter().enumerate() {
+            for candidate in round.elected {
+                winners.push(candidate, rank as u32);
+            }
+        }
+        return winners;
+    }
+
+    /// Get the rounds of the count, in order. One winner is elected in each round.
+    ///
+    /// # Example
+    /// ```
+    ///    use tallystick::score_pr::DefaultScorePrTally;
+    ///    use tallystick::score_pr::Variant;
+    ///
+    ///    let mut tally = DefaultScorePrTally::new(2, 5.0, Variant::Reweighted);
+    ///    tally.add_weighted(vec![("Alice", 5.0), ("Bob", 5.0)], 6.0).unwrap();
+    ///    tally.add_weighted(vec![("Carlos", 5.0)], 4.0).unwrap();
+    ///
+    ///    let rounds = tally.rounds();
+    ///    assert_eq!(rounds[0].totals, vec![("Alice", 30.0), ("Bob", 30.0), ("Carlos", 20.0)]);
+    ///    assert_eq!(rounds[0].elected, vec!["Alice"]);
+    ///
+    ///    // Votes for Alice now have half their weight.
+    ///    assert_eq!(rounds[1].totals, vec![("Bob", 15.0), ("Carlos", 20.0)]);
+    ///    assert_eq!(rounds[1].elected, vec!["Carlos"]);
+    /// ```
+    pub fn rounds(&self) -> Vec<Round<T, C>> {
+        let num_winners = self.num_winners as usize;
+        let mut rounds = Vec::with_capacity(num_winners);
+        let mut elected = vec![false; self.candidates.len()];
+
+        // The weight of each vote for Reweighted and Allocated, and its remaining budget for SequentiallySpent.
+        let mut strengths: Vec<C> = match self.variant {
+            Variant::SequentiallySpent => vec![self.max_score; self.ballots.len()],
+            _ => vec![C::one(); self.ballots.len()],
+        };
+        let seats = C::from_usize(num_winners.max(1)).expect(C_FROM_PANIC);
+        let quota = match self.variant {
+            Variant::SequentiallySpent => self.total * self.max_score / seats,
+            _ => self.total / seats,
+        };
+
+        for round in 0..num_winners {
+            let continuing: Vec<usize> = (0..self.candidates.len()).filter(|candidate| !elected[*candidate]).collect();
+            if continuing.is_empty() {
+                break;
+            }
+            let totals: Vec<C> = continuing.iter().map(|candidate| self.round_total(*candidate, &strengths)).collect();
+
+            // Find the candidates with the highest total. Candidates tied for the last seat are all elected.
+            let mut best: Vec<usize> = Vec::new();
+            for (i, total) in totals.iter().enumerate() {
+                match best.first().map(|first| total.partial_cmp(&totals[*first])) {
+                    None | Some(Some(Ordering::Greater)) => best = vec![i],
+                    Some(Some(Ordering::Equal)) => best.push(i),
+                    _ => {}
+                }
+            }
+            if round + 1 < num_winners {
+                best.truncate(1);
+            }
+            let winner = continuing[best[0]];
+            self.reweight(winner, totals[best[0]], quota, &mut strengths);
+            elected[winner] = true;
+
+            rounds.push(Round {
+                elected: best.iter().map(|i| self.candidates[continuing[*i]].clone()).collect(),
+                totals: continuing.iter().zip(totals).map(|(candidate, total)| (self.candidates[*candidate].clone(), total)).collect(),
+            });
+        }
+        return rounds;
+    }
+
+    // Get the total weighted score of a candidate, given the strength of each vote.
+    fn round_total(&self, candidate: usize, strengths: &[C]) -> C {
+        let mut total = C::zero();
+        for ((scores, weight), strength) in self.ballots.iter().zip(strengths) {
+            if let Some((_, score)) = scores.iter().find(|(id, _)| *id == candidate) {
+                let score = match self.variant {
+                    Variant::SequentiallySpent if *strength < *score => *strength,
+                    Variant::SequentiallySpent => *score,
+                    _ => *score * *strength,
+                };
+                total += *weight * score;
+            }
+        }
+        return total;
+    }
+
+    // Reweight the votes that scored a newly elected candidate.
+    fn reweight(&self, winner: usize, winner_total: C, quota: C, strengths: &mut [C]) {
+        let score = |scores: &Vec<(usize, C)>| scores.iter().find(|(id, _)| *id == winner).map_or(C::zero(), |(_, score)| *score);
+        match self.variant {
+            Variant::Reweighted => {
+                for ((scores, _), strength) in self.ballots.iter().zip(strengths.iter_mut()) {
+                    // Recover the sum of scores given to elected candidates from the current weight.
+                    let sum = C::one() / *strength - C::one() + score(scores) / self.max_score;
+                    *strength = C::one() / (C::one() + sum);
+                }
+            }
+            Variant::SequentiallySpent => {
+                for ((scores, _), strength) in self.ballots.iter().zip(strengths.iter_mut()) {
+                    let spent = if *strength < score(scores) { *strength } else { score(scores) };
+                    let spent = if winner_total > quota { spent * quota / winner_total } else { spent };
+                    *strength = *strength - spent;
+                }
+            }
+            Variant::Allocated => {
+                // Allocate the votes that gave the winner the highest scores first, until a quota is allocated.
+                let mut levels: Vec<C> = Vec::new();
+                for ((scores, _), strength) in self.ballots.iter().zip(strengths.iter()) {
+                    let score = score(scores);
+                    if score > C::zero() && *strength > C::zero() && !levels.contains(&score) {
+                        levels.push(score);
+                    }
+                }
+                levels.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+                let mut remaining = quota;
+                for level in levels {
+                    let mut level_weight = C::zero();
+                    for ((scores, weight), strength) in self.ballots.iter().zip(strengths.iter()) {
+                        if score(scores) == level {
+                            level_weight += *weight * *strength;
+                        }
+                    }
+                    let kept = if level_weight > remaining { C::one() - remaining / level_weight } else { C::zero() };
+                    for ((scores, _), strength) in self.ballots.iter().zip(strengths.iter_mut()) {
+                        if score(scores) == level {
+                            *strength = *strength * kept;
+                        }
+                    }
+                    if level_weight >= remaining {
+                        break;
+                    }
+                    remaining = remaining - level_weight;
+                }
+            }
+        }
+    }
+
+    // Get the id of a candidate, adding it if it hasn't been seen before.
+    fn intern(&mut self, candidate: &T) -> usize {
+        match self.candidates.iter().position(|seen| seen == candidate) {
+            Some(id) => return id,
+            None => {
+                self.candidates.push(candidate.clone());
+                self.totals.push(C::zero());
+                return self.candidates.len() - 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Scores are out of 5. Voters who like Alice and Bob are given by `majority`, and the rest of ten voters like Carlos.
+    fn tally(variant: Variant, majority: f64) -> DefaultScorePrTally<&'static str> {
+        let mut tally = DefaultScorePrTally::new(2, 5.0, variant);
+        tally.add_weighted(vec![("Alice", 5.0), ("Bob", 5.0)], majority).unwrap();
+        tally.add_weighted(vec![("Carlos", 5.0)], 10.0 - majority).unwrap();
+        return tally;
+    }
+
+    #[test]
+    fn score_pr_proportional() {
+        for variant in [Variant::Reweighted, Variant::SequentiallySpent, Variant::Allocated].iter() {
+            // Six out of ten voters get one of two seats.
+            let winners = tally(*variant, 6.0).winners();
+            assert_eq!(winners.into_vec(), vec![("Alice", 0), ("Carlos", 1)]);
+
+            // Eight out of ten voters get both seats.
+            let winners = tally(*variant, 8.0).winners();
+            assert_eq!(winners.into_vec(), vec![("Alice", 0), ("Bob", 1)]);
+        }
+    }
+
+    #[test]
+    fn score_pr_rounds() {
+        // Votes for Alice and Bob have a weight of 1/2 once Alice is elected.
+        let rounds = tally(Variant::Reweighted, 8.0).rounds();
+        assert_eq!(rounds[1].totals, vec![("Bob", 20.0), ("Carlos", 10.0)]);
+
+        // The quota is 25. Alice has 40, so each vote for her spends 5 * 25 / 40, leaving a budget of 1.875.
+        let rounds = tally(Variant::SequentiallySpent, 8.0).rounds();
+        assert_eq!(rounds[0].totals, vec![("Alice", 40.0), ("Bob", 40.0), ("Carlos", 10.0)]);
+        assert_eq!(rounds[1].totals, vec![("Bob", 15.0), ("Carlos", 10.0)]);
+
+        // The quota is 5 votes, so 5 of the 8 votes for Alice are allocated to her.
+        let rounds = tally(Variant::Allocated, 8.0).rounds();
+        assert_eq!(rounds[1].totals, vec![("Bob", 15.0), ("Carlos", 10.0)]);
+    }
+
+    #[test]
+    fn score_pr_allocated_levels() {
+        // The quota is 2 votes. Both votes that gave Alice a 5 are allocated to her, and the vote that gave her a 3 is not.
+        let mut tally = DefaultScorePrTally::new(2, 5.0, Variant::Allocated);
+        tally.add_weighted(vec![("Alice", 5.0), ("Bob", 5.0)], 2.0).unwrap();
+        tally.add(vec![("Alice", 3.0), ("Bob", 4.0)]).unwrap();
+        tally.add(vec![("Carlos", 5.0)]).unwrap();
+
+        let rounds = tally.rounds();
+        assert_eq!(rounds[0].totals, vec![("Alice", 13.0), ("Bob", 14.0), ("Carlos", 5.0)]);
+        assert_eq!(rounds[1].totals, vec![("Alice", 3.0), ("Carlos", 5.0)]);
+        assert_eq!(tally.winners().into_vec(), vec![("Bob", 0), ("Carlos", 1)]);
+    }
+
+    #[test]
+    fn score_pr_ties() -> Result<(), TallyError> {
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos"]);
+        let mut tally = DefaultScorePrTally::with_candidates(2, 10.0, Variant::Reweighted, candidates);
+        tally.add(vec![("Alice", 10.0)])?;
+        assert!(tally.add(vec![("Dave", 10.0)]).is_err());
+        assert!(tally.add(vec![("Bob", 11.0)]).is_err());
+        assert!(tally.add(vec![("Bob", -1.0)]).is_err());
+
+        // Bob and Carlos tie for the last seat.
+        let winners = tally.winners();
+        assert_eq!(winners.overflow(), Some(vec!["Bob", "Carlos"]));
+
+        let mut other = DefaultScorePrTally::new(2, 10.0, Variant::Reweighted);
+        other.add(vec![("Carlos", 1.0)])?;
+        tally.merge(other);
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0), ("Carlos", 1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn score_pr_integer() {
+        ScorePrTally::<&str, u64>::new(2, 5, Variant::Reweighted);
+    }
+}
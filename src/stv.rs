@@ -1,155 +1,302 @@
 // TODO: Remove this allow
 #![allow(missing_docs)]
 
-use hashbrown::HashMap;
-use num_traits::cast::NumCast;
-use num_traits::Num;
-use std::hash::Hash;
-use std::ops::AddAssign;
-
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::AddAssign;
+use num_traits::cast::FromPrimitive;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::check_duplicate;
+use super::BallotBox;
+use super::Candidates;
+use super::Numeric;
 use super::Quota;
+use super::RankedBallot;
+use super::runoff;
+use super::runoff::Elimination;
+use super::runoff::Round;
 use super::RankedWinners;
+use super::sorted_order;
+use super::TallyError;
 
+// A portion of a unique vote that is currently held by a candidate.
 #[derive(Debug)]
-struct WeightedVote<T, C>
-where
-    T: Eq + Clone + Hash,                             // Candidate
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // vote count type
-{
+struct WeightedVote<C> {
+    ballot: usize, // Index of the unique vote in the ballot box
+    rank: usize,   // Rank on the vote of the candidate currently holding it
     weight: C,
-    remaining: Vec<T>,
 }
 
 pub type DefaultTally<T> = Tally<T, f64>;
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Tally<T, C>
 where
-    T: Eq + Clone + Hash,                             // Candidate
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // vote count type
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // vote count type
 {
-    running_total: HashMap<T, Vec<WeightedVote<T, C>>>,
+    ballots: BallotBox<T, C>, // Identical votes are stored once, with their total weight
     num_winners: u32,
     quota: Quota<C>,
-    expected_votes: Option<usize>, // Expected votes *per candidate*.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    registry: Option<Candidates<T>>, // The declared candidates, if any
 }
 
 impl<T, C> Tally<T, C>
 where
-    T: Eq + Clone + Hash,                             // Candidate
-    C: Copy + PartialOrd + AddAssign + Num + NumCast, // vote count type
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // vote count type
 {
     pub fn new(num_winners: u32, quota: Quota<C>) -> Self {
         return Tally {
-            running_total: HashMap::new(),
+            ballots: BallotBox::new(),
             num_winners: num_winners,
             quota: quota,
-            expected_votes: None,
+            registry: None,
         };
     }
 
     pub fn with_capacity(num_winners: u32, quota: Quota<C>, expected_candidates: usize, expected_votes: usize) -> Self {
         return Tally {
-            running_total: HashMap::with_capacity(expected_candidates),
+            ballots: BallotBox::with_capacity(expected_candidates, expected_votes),
             num_winners: num_winners,
             quota: quota,
-            expected_votes: Some((expected_votes / expected_candidates) * 2),
+            registry: None,
         };
     }
 
-    pub fn add(&mut self, mut selection: Vec<T>) {
-        if selection.is_empty() {
-            return;
-        }
+    /// Create a new `Tally` with the given number of winners, for an election between the given candidates.
+    ///
+    /// Every standing candidate is in the count, even if they are not ranked on any vote.
+    /// See [`Candidates`](../struct.Candidates.html) for details.
+    pub fn with_candidates(num_winners: u32, quota: Quota<C>, candidates: Candidates<T>) -> Self {
+        let mut tally = Self::with_capacity(num_winners, quota, candidates.standing().len(), 0);
+        tally.ballots.declare(candidates.standing());
+        tally.registry = Some(candidates);
+        return tally;
+    }
+
+    /// Sort candidates by their `Ord` order instead of the order they were first seen.
+    /// See [Determinism](../index.html#determinism) for details.
+    pub fn sort_candidates(&mut self)
+    where
+        T: Ord,
+    {
+        let declared = self.registry.as_ref().map_or(0, |registry| registry.standing().len());
+        let order = sorted_order(self.ballots.candidate_ids(), declared);
+        self.ballots.reorder(&order);
+    }
+
+    pub fn add(&mut self, selection: Vec<T>) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, C::one())
+    }
 
-        let choice = selection.remove(0);
+    pub fn add_ref(&mut self, selection: &[T]) -> Result<(), TallyError> {
+        self.add_weighted_ref(selection, C::one())
+    }
+
+    /// Add a weighted vote.
+    pub fn add_weighted(&mut self, selection: Vec<T>, weight: C) -> Result<(), TallyError> {
+        self.add_weighted_ref(&selection, weight)
+    }
 
-        // Ensure that the running total contains all candidates
-        for candidate in selection.iter() {
-            if !self.running_total.contains_key(candidate) {
-                self.running_total.insert(candidate.clone(), vec![]);
+    /// Add a weighted vote by reference.
+    pub fn add_weighted_ref(&mut self, selection: &[T], weight: C) -> Result<(), TallyError> {
+        match &self.registry {
+            Some(registry) => {
+                check_duplicate(selection)?;
+                self.ballots.add_weighted_ref(&registry.filter(selection)?, weight)
             }
+            None => self.ballots.add_weighted_ref(selection, weight),
         }
+    }
 
-        let weighted_vote = WeightedVote {
-            weight: C::one(),
-            remaining: selection,
-        };
+    /// Add a ranked vote that may contain tied candidates.
+    ///
+    /// The vote is split evenly between tied candidates. If one of the tied candidates is elected or eliminated,
+    /// their part of the vote is transferred to the remaining tied candidates before moving on to lower ranks.
+    /// If `vote` contains ties, a float or rational count type must be used, otherwise an error is returned.
+    pub fn add_ranked(&mut self, vote: RankedBallot<T>) -> Result<(), TallyError> {
+        self.add_ranked_weighted(vote, C::one())
+    }
+
+    /// Add a ranked vote by reference.
+    pub fn add_ranked_ref(&mut self, vote: &RankedBallot<T>) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(vote, C::one())
+    }
+
+    /// Add a weighted ranked vote that may contain tied candidates.
+    pub fn add_ranked_weighted(&mut self, vote: RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+        self.add_ranked_weighted_ref(&vote, weight)
+    }
 
-        match self.expected_votes {
-            Some(expected_votes) => {
-                self.running_total
-                    .entry(choice)
-                    .or_insert_with(|| Vec::with_capacity(expected_votes))
-                    .push(weighted_vote);
+    /// Add a weighted ranked vote by reference.
+    pub fn add_ranked_weighted_ref(&mut self, vote: &RankedBallot<T>, weight: C) -> Result<(), TallyError> {
+        if vote.has_ties() && !C::fraction() {
+            return Err(TallyError::TiedVoteRequiresFractionalCount);
+        }
+        match &self.registry {
+            Some(registry) => {
+                vote.check_duplicate()?;
+                self.ballots.add_ranked_weighted_ref(&registry.filter_ranked(vote)?, weight)
             }
-            None => {
-                self.running_total.entry(choice).or_default().push(weighted_vote);
+            None => self.ballots.add_ranked_weighted_ref(vote, weight),
+        }
+    }
+
+    /// Add all votes in a `BallotBox`.
+    ///
+    /// If the ballot box contains ties, a float or rational count type must be used, otherwise an error is returned.
+    pub fn add_ballot_box(&mut self, ballot_box: &BallotBox<T, C>) -> Result<(), TallyError> {
+        if ballot_box.has_ties() && !C::fraction() {
+            return Err(TallyError::TiedVoteRequiresFractionalCount);
+        }
+        if let Some(registry) = &self.registry {
+            registry.check_all(ballot_box.candidate_ids())?;
+        }
+        for (vote, weight) in ballot_box.iter() {
+            match &self.registry {
+                Some(registry) => self.ballots.add_ranked_weighted_ref(&registry.filter_ranked(&vote)?, weight)?,
+                None => self.ballots.add_ranked_weighted(vote, weight)?,
             }
         }
+        Ok(())
+    }
+
+    /// Merge another tally into this one, as if all of its votes had been added to this tally.
+    ///
+    /// Tallies can be merged in any order to get the same result. After merging, candidates are sorted as if by
+    /// [`sort_candidates()`](#method.sort_candidates), so their order doesn't depend on merge order either.
+    /// The quota and number of winners for `other` are ignored.
+    ///
+    /// # Panics
+    /// This method will panic if the merged total weight of all votes overflows the count type.
+    pub fn merge(&mut self, other: Self)
+    where
+        T: Ord,
+    {
+        self.ballots.merge(other.ballots);
+        self.sort_candidates();
+    }
+
+    /// Get a ranked list of winners. Candidates elected in the same round share a rank, and are listed in the order they were first seen.
+    ///
+    /// With a single winner this is an instant runoff count. The candidates with the fewest votes are eliminated each round,
+    /// until the leading candidate reaches the quota or is the last candidate left. The quota is taken from every vote cast,
+    /// so with the Droop quota a candidate needs a majority of all votes, including votes that have been exhausted.
+    pub fn winners(&self) -> RankedWinners<T> {
+        if self.num_winners == 1 {
+            return runoff::winners(self.rounds(), self.num_winners);
+        }
+        return self.count(|piles, votes| self.transfer(piles, votes)).0;
     }
 
-    pub fn add_ref(&mut self, selection: &Vec<T>) {
-        // Regretably, we need to store the entire selection, so just clone it
-        self.add(selection.clone());
+    /// Get the rounds of the count, in order.
+    ///
+    /// Each round lists the votes held by every continuing candidate, followed by the candidates elected or eliminated
+    /// at the end of the round. Votes from elected and eliminated candidates are transferred before the next round.
+    pub fn rounds(&self) -> Vec<Round<T, C>> {
+        if self.num_winners == 1 {
+            return runoff::count(self.ballots.candidate_ids(), self);
+        }
+        return self.count(|piles, votes| self.transfer(piles, votes)).1;
     }
 
-    pub fn winners(&mut self) -> RankedWinners<T> {
+    // Run the count, using `distribute` to transfer votes to the next continuing candidates on each vote.
+    // Returns the winners along with the rounds of the count.
+    fn count<F>(&self, distribute: F) -> (RankedWinners<T>, Vec<Round<T, C>>)
+    where
+        F: Fn(&mut [Option<Vec<WeightedVote<C>>>], Vec<WeightedVote<C>>),
+    {
+        let candidates = self.ballots.candidate_ids();
         let threshold = self.threshold();
 
+        // Each continuing candidate holds a pile of votes, elected or eliminated candidates hold `None`.
+        let mut piles: Vec<Option<Vec<WeightedVote<C>>>> = (0..candidates.len()).map(|_| Some(Vec::new())).collect();
+        let mut votes = Vec::with_capacity(self.ballots.len());
+        for (ballot, (_, weight)) in self.ballots.interned().iter().enumerate() {
+            votes.push(WeightedVote {
+                ballot: ballot,
+                rank: 0,
+                weight: *weight,
+            });
+        }
+        distribute(&mut piles, votes);
+
         let mut winners = RankedWinners::new(self.num_winners);
+        let mut rounds = Vec::new();
 
         let mut rank: u32 = 0;
         loop {
+            let continuing: Vec<usize> = (0..piles.len()).filter(|id| piles[*id].is_some()).collect();
+            let votecounts: Vec<C> = continuing.iter().map(|id| Self::votecount(piles[*id].as_ref().unwrap())).collect();
+            let mut round = Round {
+                totals: continuing.iter().map(|id| candidates[*id].clone()).zip(votecounts.iter().cloned()).collect(),
+                elected: Vec::new(),
+                eliminated: Vec::new(),
+            };
+
             // Step 1. If we have less candidates left than there are spots to fill, they are all winners
-            if self.running_total.len() <= self.num_winners as usize - winners.len() {
-                for (candidate, _) in self.running_total.drain() {
-                    winners.push(candidate, rank);
+            if continuing.len() <= self.num_winners as usize - winners.len() {
+                for candidate in continuing {
+                    winners.push(candidates[candidate].clone(), rank);
+                    round.elected.push(candidates[candidate].clone());
                 }
-                return winners;
+                rounds.push(round);
+                return (winners, rounds);
             }
 
             // Step 2. Check if any candidates are over the threshold
-            let mut new_winners: Vec<T> = Vec::new();
-            for (candidate, votes) in self.running_total.iter() {
-                let mut votecount = C::zero();
-                for vote in votes.iter() {
-                    votecount += vote.weight;
-                }
-                if votecount >= threshold {
-                    new_winners.push(candidate.clone());
+            let mut new_winners: Vec<usize> = Vec::new();
+            for (candidate, votecount) in continuing.iter().zip(votecounts.iter()) {
+                if *votecount >= threshold {
+                    new_winners.push(*candidate);
                 }
             }
 
             // Step 3. If we have enough winners, end the tally and return results.
             if (winners.len() + new_winners.len()) as u32 >= self.num_winners {
                 for winner in new_winners.drain(0..) {
-                    winners.push(winner, rank);
+                    winners.push(candidates[winner].clone(), rank);
+                    round.elected.push(candidates[winner].clone());
                 }
-                return winners;
+                rounds.push(round);
+                return (winners, rounds);
             }
 
             // Step 4. If there's new winners, redistribute their excess vote.
             if !new_winners.is_empty() {
-                let mut winner_votes: HashMap<T, Vec<WeightedVote<T, C>>> = HashMap::new();
+                let mut winner_votes: Vec<(usize, Vec<WeightedVote<C>>)> = Vec::new();
                 for winner in new_winners.drain(0..) {
-                    let votes = self.running_total.remove(&winner).unwrap();
-                    winner_votes.insert(winner, votes);
+                    let votes = piles[winner].take().unwrap();
+                    winner_votes.push((winner, votes));
                 }
-                for (winner, mut votes) in winner_votes.drain() {
-                    let overvote = C::from(votes.len()).unwrap() - threshold;
-                    let weight = overvote / C::from(votes.len()).unwrap();
+                for (winner, votes) in winner_votes.drain(0..) {
+                    let votecount = Self::votecount(&votes);
+                    let overvote = votecount - threshold;
+                    let weight = overvote / votecount;
 
                     // Redistibute to next choice
-                    for vote in votes.drain(0..) {
-                        self.redistribute(vote, weight);
-                    }
-
-                    winners.push(winner, rank);
+                    let surplus = votes.into_iter().map(|vote| WeightedVote {
+                        weight: weight * vote.weight,
+                        ..vote
+                    });
+                    distribute(&mut piles, surplus.collect());
+
+                    winners.push(candidates[winner].clone(), rank);
+                    round.elected.push(candidates[winner].clone());
                 }
+                rounds.push(round);
 
                 // If we have enough winners, return it.
                 if winners.len() as u32 >= self.num_winners {
-                    return winners;
+                    return (winners, rounds);
                 }
 
                 // We've added winners, so increase the rank and continue to the next round.
@@ -157,57 +304,43 @@ where
                 continue;
             } else {
                 // Remove loosers and redistribute
-                let mut new_loosers: Vec<T> = Vec::new();
-                {
-                    let mut votecounts: HashMap<&T, C> = HashMap::new();
-                    let mut first = true;
-                    let mut least = C::zero();
-                    for (candidate, votes) in self.running_total.iter() {
-                        let mut votecount = C::zero();
-
-                        for vote in votes.iter() {
-                            votecount += vote.weight;
-                        }
-                        if first {
-                            least = votecount;
-                            first = false;
-                        } else if votecount < least {
-                            least = votecount;
-                        }
-
-                        votecounts.insert(&candidate, votecount);
+                let mut least = votecounts[0];
+                for votecount in votecounts.iter() {
+                    if *votecount < least {
+                        least = *votecount;
                     }
-                    for (candidate_ref, count) in votecounts.iter() {
-                        if *count <= least {
-                            let candidate = *candidate_ref;
-                            new_loosers.push(candidate.clone());
-                        }
+                }
+                let mut new_loosers: Vec<usize> = Vec::new();
+                for (candidate, votecount) in continuing.iter().zip(votecounts.iter()) {
+                    if *votecount <= least {
+                        new_loosers.push(*candidate);
                     }
-                };
+                }
 
                 // If the number of loosers to be removed would result in an underelection, then the loosers become winners.
                 let needed_winners = self.num_winners as usize - winners.len();
-                let available_winners = self.running_total.len() - new_loosers.len();
+                let available_winners = continuing.len() - new_loosers.len();
                 if available_winners < needed_winners {
                     for winning_loosers in new_loosers.drain(0..) {
-                        winners.push(winning_loosers, rank);
+                        winners.push(candidates[winning_loosers].clone(), rank);
+                        round.elected.push(candidates[winning_loosers].clone());
                     }
-                    return winners;
+                    rounds.push(round);
+                    return (winners, rounds);
                 }
 
-                // If there's new loosers, redistribute their excess vote.
+                // If there's new loosers, redistribute their votes.
                 if !new_loosers.is_empty() {
-                    let mut looser_votes: Vec<Vec<WeightedVote<T, C>>> = Vec::new();
+                    let mut looser_votes: Vec<Vec<WeightedVote<C>>> = Vec::new();
                     for looser in new_loosers.drain(0..) {
-                        let votes = self.running_total.remove(&looser).unwrap();
-                        looser_votes.push(votes);
+                        looser_votes.push(piles[looser].take().unwrap());
+                        round.eliminated.push(candidates[looser].clone());
                     }
-                    for mut votes in looser_votes.drain(0..) {
+                    for votes in looser_votes.drain(0..) {
                         // Redistibute to next choice
-                        for vote in votes.drain(0..) {
-                            self.redistribute(vote, C::one());
-                        }
+                        distribute(&mut piles, votes);
                     }
+                    rounds.push(round);
                 } else {
                     unreachable!();
                 }
@@ -215,45 +348,156 @@ where
         }
     }
 
-    fn redistribute(&mut self, vote: WeightedVote<T, C>, weight: C) {
-        if vote.remaining.is_empty() {
-            return;
+    // Transfer votes to the next continuing candidates on each vote.
+    fn transfer(&self, piles: &mut [Option<Vec<WeightedVote<C>>>], votes: Vec<WeightedVote<C>>) {
+        for vote in votes {
+            if let Some((rank, split)) = self.next_preference(piles, &vote) {
+                Self::assign(piles, self.ballots.interned()[vote.ballot].0.ranks(), vote.ballot, rank, split);
+            }
         }
+    }
 
-        let mut remaining = vote.remaining;
-        let next_choice = remaining.remove(0);
-        let weighted_vote = WeightedVote {
-            weight: weight * vote.weight,
-            remaining: remaining,
-        };
-        if self.running_total.contains_key(&next_choice) {
-            if let Some(x) = self.running_total.get_mut(&next_choice) {
-                x.push(weighted_vote);
+    // Find the next rank on a vote, starting at the rank currently holding it, that contains a continuing candidate.
+    // Returns that rank and the weight given to each continuing candidate in it, since the vote is split evenly if
+    // there is more than one. Returns `None` if no continuing candidates remain on the vote, in which case it is exhausted.
+    fn next_preference(&self, piles: &[Option<Vec<WeightedVote<C>>>], vote: &WeightedVote<C>) -> Option<(usize, C)> {
+        let ranks = self.ballots.interned()[vote.ballot].0.ranks();
+        for (next_rank, candidates) in ranks.iter().enumerate().skip(vote.rank) {
+            // Skip to the next choice in line if the preferred next-choices have already won or lost.
+            let continuing = candidates.iter().filter(|id| piles[**id].is_some()).count();
+            if continuing > 0 {
+                return Some((next_rank, vote.weight / C::from_usize(continuing).unwrap()));
             }
-        } else {
-            // Skip to the next choice in line if the preferred next-choice has already won or lost.
-            self.redistribute(weighted_vote, C::one());
         }
+        return None;
     }
 
-    fn total_votes(&self) -> usize {
-        let mut total: usize = 0;
-
-        for (_, candidate_votes) in self.running_total.iter() {
-            total += candidate_votes.len();
+    // Give part of a vote to every continuing candidate at the given rank.
+    fn assign(piles: &mut [Option<Vec<WeightedVote<C>>>], ranks: &[Vec<usize>], ballot: usize, rank: usize, split: C) {
+        for candidate in ranks[rank].iter() {
+            if let Some(pile) = piles[*candidate].as_mut() {
+                pile.push(WeightedVote {
+                    ballot: ballot,
+                    rank: rank,
+                    weight: split,
+                });
+            }
         }
+    }
 
-        return total;
+    fn votecount(votes: &[WeightedVote<C>]) -> C {
+        let mut votecount = C::zero();
+        for vote in votes.iter() {
+            votecount += vote.weight;
+        }
+        return votecount;
     }
 
     fn threshold(&self) -> C {
-        let total_votes = C::from(self.total_votes()).unwrap();
-        let num_winners = C::from(self.num_winners).unwrap();
+        let total_votes = self.ballots.total();
+        let num_winners = C::from_u32(self.num_winners).unwrap();
 
         return self.quota.threshold(total_votes, num_winners);
     }
 }
 
+// With a single winner, the count is an instant runoff, which eliminates the candidates with the fewest votes each round.
+impl<T, C> Elimination<C> for Tally<T, C>
+where
+    T: Eq + Clone + Hash,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive, // vote count type
+{
+    fn count(&self, continuing: &[bool]) -> Vec<C> {
+        let mut totals = vec![C::zero(); continuing.len()];
+        for (vote, weight) in self.ballots.interned() {
+            // A vote is split evenly between tied continuing candidates, like when it is transferred.
+            if let Some(rank) = vote.ranks().iter().find(|rank| rank.iter().any(|candidate| continuing[*candidate])) {
+                let tied = rank.iter().filter(|candidate| continuing[**candidate]).count();
+                let split = *weight / C::from_usize(tied).unwrap();
+                for candidate in rank.iter().filter(|candidate| continuing[**candidate]) {
+                    totals[*candidate] += split;
+                }
+            }
+        }
+        return totals;
+    }
+
+    fn eliminate(&self, continuing: &[bool], totals: &[C]) -> Vec<usize> {
+        let mut eliminated = Vec::new();
+        let mut least = C::zero();
+        for (candidate, votes) in totals.iter().enumerate().filter(|(candidate, _)| continuing[*candidate]) {
+            if eliminated.is_empty() || *votes < least {
+                eliminated = vec![candidate];
+                least = *votes;
+            } else if *votes == least {
+                eliminated.push(candidate);
+            }
+        }
+        return eliminated;
+    }
+
+    // The quota is the same as in a count with more than one winner, so exhausted votes still count towards it.
+    fn elected(&self, votes: C, _active: C) -> bool {
+        return votes >= self.threshold();
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T, C> Tally<T, C>
+where
+    T: Eq + Clone + Hash + Send + Sync,                                       // Candidate
+    C: Copy + PartialOrd + AddAssign + Numeric + FromPrimitive + Send + Sync, // vote count type
+{
+    /// Create a new `Tally` from a parallel iterator of votes.
+    ///
+    /// Votes are split across threads and collected into per-thread ballot boxes, which are then merged.
+    /// Requires the `parallel` feature.
+    pub fn from_par_iter<I>(num_winners: u32, quota: Quota<C>, votes: I) -> Result<Self, TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        let mut tally = Self::new(num_winners, quota);
+        tally.par_add_all(votes)?;
+        return Ok(tally);
+    }
+
+    /// Add many votes in parallel.
+    ///
+    /// See [`BallotBox::par_add_all()`](../struct.BallotBox.html#method.par_add_all) for details.
+    /// Requires the `parallel` feature.
+    pub fn par_add_all<I>(&mut self, votes: I) -> Result<(), TallyError>
+    where
+        I: IntoParallelIterator<Item = Vec<T>>,
+    {
+        self.ballots.par_add_filtered(votes, self.registry.as_ref())
+    }
+
+    /// Get a ranked list of winners, transferring votes between candidates in parallel.
+    ///
+    /// This gives the same result as [`winners()`](#method.winners), but each time a pile of votes is transferred
+    /// the next preference on every vote in the pile is found in parallel. Requires the `parallel` feature.
+    pub fn par_winners(&self) -> RankedWinners<T> {
+        if self.num_winners == 1 {
+            return self.winners();
+        }
+        return self.count(|piles, votes| self.par_transfer(piles, votes)).0;
+    }
+
+    // Transfer votes to the next continuing candidates on each vote, finding the next preferences in parallel.
+    // Candidates can't be elected or eliminated during a transfer, so every vote sees the same continuing candidates.
+    fn par_transfer(&self, piles: &mut [Option<Vec<WeightedVote<C>>>], votes: Vec<WeightedVote<C>>) {
+        let next: Vec<Option<(usize, C)>> = {
+            let piles: &[Option<Vec<WeightedVote<C>>>] = piles;
+            votes.par_iter().map(|vote| self.next_preference(piles, vote)).collect()
+        };
+        for (vote, next) in votes.iter().zip(next) {
+            if let Some((rank, split)) = next {
+                Self::assign(piles, self.ballots.interned()[vote.ballot].0.ranks(), vote.ballot, rank, split);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,32 +506,221 @@ mod tests {
     fn stv_test() {
         // Election between Alice, Bob, and Cir
         let mut tally = DefaultTally::new(2, Quota::Droop);
-        tally.add(vec!["Alice", "Bob", "Cir"]);
-        tally.add(vec!["Alice", "Bob", "Cir"]);
-        tally.add(vec!["Alice", "Bob", "Cir"]);
+        tally.add(vec!["Alice", "Bob", "Cir"]).unwrap();
+        tally.add(vec!["Alice", "Bob", "Cir"]).unwrap();
+        tally.add(vec!["Alice", "Bob", "Cir"]).unwrap();
 
         let winners = tally.winners();
         assert_eq!(winners.into_vec(), vec! {("Alice", 0), ("Bob", 1)});
     }
 
+    #[test]
+    fn stv_rounds() -> Result<(), TallyError> {
+        // From https://en.wikipedia.org/wiki/Single_transferable_vote#Counting_the_votes
+        let mut tally = DefaultTally::new(3, Quota::Droop);
+        tally.add_weighted(vec!["Orange"], 4.0)?;
+        tally.add_weighted(vec!["Pear", "Orange"], 2.0)?;
+        tally.add_weighted(vec!["Chocolate", "Strawberry"], 8.0)?;
+        tally.add_weighted(vec!["Chocolate", "Sweets"], 4.0)?;
+        tally.add(vec!["Strawberry"])?;
+        tally.add(vec!["Sweets"])?;
+
+        // The quota is 6. Chocolate's surplus of 6 is transferred at half weight, and Pear's votes go to Orange.
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 5);
+        assert_eq!(
+            rounds[0].totals,
+            vec![("Orange", 4.0), ("Pear", 2.0), ("Chocolate", 12.0), ("Strawberry", 1.0), ("Sweets", 1.0)]
+        );
+        assert_eq!(rounds[0].elected, vec!["Chocolate"]);
+        assert_eq!(rounds[1].totals, vec![("Orange", 4.0), ("Pear", 2.0), ("Strawberry", 5.0), ("Sweets", 3.0)]);
+        assert_eq!(rounds[1].eliminated, vec!["Pear"]);
+        assert_eq!(rounds[2].totals, vec![("Orange", 6.0), ("Strawberry", 5.0), ("Sweets", 3.0)]);
+        assert_eq!(rounds[2].elected, vec!["Orange"]);
+        assert_eq!(rounds[3].eliminated, vec!["Sweets"]);
+        assert_eq!(rounds[4].totals, vec![("Strawberry", 5.0)]);
+        assert_eq!(rounds[4].elected, vec!["Strawberry"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stv_instant_runoff_rounds() -> Result<(), TallyError> {
+        // With one winner, the Droop quota is 5 of the 9 votes. Carlos' votes are exhausted, so Alice never reaches it,
+        // and is elected once she is the last candidate left.
+        let mut tally = Tally::<&str, u64>::new(1, Quota::Droop);
+        tally.add_weighted(vec!["Alice"], 4)?;
+        tally.add_weighted(vec!["Bob"], 3)?;
+        tally.add_weighted(vec!["Carlos"], 2)?;
+
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 3);
+        assert_eq!(rounds[0].totals, vec![("Alice", 4), ("Bob", 3), ("Carlos", 2)]);
+        assert_eq!(rounds[0].eliminated, vec!["Carlos"]);
+        assert_eq!(rounds[1].totals, vec![("Alice", 4), ("Bob", 3)]);
+        assert_eq!(rounds[1].eliminated, vec!["Bob"]);
+        assert_eq!(rounds[2].totals, vec![("Alice", 4)]);
+        assert_eq!(rounds[2].elected, vec!["Alice"]);
+        assert_eq!(tally.winners().into_vec(), vec![("Alice", 0)]);
+
+        // Ranking Alice after Carlos gets her to the quota once Carlos is eliminated.
+        let mut tally = Tally::<&str, u64>::new(1, Quota::Droop);
+        tally.add_weighted(vec!["Alice"], 4)?;
+        tally.add_weighted(vec!["Bob"], 3)?;
+        tally.add_weighted(vec!["Carlos", "Alice"], 2)?;
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[1].totals, vec![("Alice", 6), ("Bob", 3)]);
+        assert_eq!(rounds[1].elected, vec!["Alice"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stv_instant_runoff_quota() -> Result<(), TallyError> {
+        // The Imperiali quota for one seat is a third of the votes, so Alice is elected in the first round.
+        let mut tally = Tally::<&str, u64>::new(1, Quota::Imperiali);
+        tally.add_weighted(vec!["Alice"], 4)?;
+        tally.add_weighted(vec!["Bob", "Alice"], 3)?;
+        tally.add_weighted(vec!["Carlos", "Bob"], 2)?;
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 1);
+        assert_eq!(rounds[0].elected, vec!["Alice"]);
+
+        // The Hare quota for one seat is every vote, so candidates are eliminated until only one is left.
+        let mut tally = Tally::<&str, u64>::new(1, Quota::Hare);
+        tally.add_weighted(vec!["Alice"], 4)?;
+        tally.add_weighted(vec!["Bob", "Alice"], 3)?;
+        tally.add_weighted(vec!["Carlos", "Bob"], 2)?;
+        let rounds = tally.rounds();
+        assert_eq!(rounds.len(), 3);
+        assert_eq!(rounds[1].totals, vec![("Alice", 4), ("Bob", 5)]);
+        assert_eq!(rounds[1].eliminated, vec!["Alice"]);
+        assert_eq!(rounds[2].totals, vec![("Bob", 5)]);
+        assert_eq!(tally.winners().into_vec(), vec![("Bob", 0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stv_ranked_ties() -> Result<(), TallyError> {
+        // Tied votes are split evenly, then flow to the other tied candidate before lower ranks.
+        let mut tally = DefaultTally::new(1, Quota::Droop);
+        for _ in 0..4 {
+            tally.add_ranked(RankedBallot::from(vec![vec!["Alice", "Bob"], vec!["Carlos"]]))?;
+        }
+        for _ in 0..3 {
+            tally.add(vec!["Carlos"]).unwrap();
+        }
+        tally.add(vec!["Bob"]).unwrap();
+
+        // Round 1: Alice 2, Bob 3, Carlos 3. Alice is eliminated and her half-votes go to Bob.
+        let winners = tally.winners();
+        assert_eq!(winners.into_vec(), vec! {("Bob", 0)});
+
+        // Ties require a fractional count type
+        let mut tally = Tally::<&str, u64>::new(1, Quota::Droop);
+        assert!(tally.add_ranked(RankedBallot::from(vec![vec!["Alice", "Bob"]])).is_err());
+        assert!(tally.add_ranked(RankedBallot::from(vec!["Alice", "Alice"])).is_err());
+        assert!(tally.add_ranked(RankedBallot::from(vec!["Alice", "Bob"])).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stv_ballot_box() -> Result<(), TallyError> {
+        // From https://en.wikipedia.org/wiki/Single_transferable_vote#Counting_the_votes
+        let mut ballot_box = BallotBox::new();
+        ballot_box.add_weighted(vec!["Orange"], 4.0)?;
+        ballot_box.add_weighted(vec!["Pear", "Orange"], 2.0)?;
+        ballot_box.add_weighted(vec!["Chocolate", "Strawberry"], 8.0)?;
+        ballot_box.add_weighted(vec!["Chocolate", "Sweets"], 4.0)?;
+        ballot_box.add(vec!["Strawberry"])?;
+        ballot_box.add(vec!["Sweets"])?;
+
+        let mut tally = DefaultTally::new(3, Quota::Droop);
+        tally.add_ballot_box(&ballot_box)?;
+        let winners = tally.winners();
+        assert_eq!(winners.into_vec(), vec! {("Chocolate", 0), ("Orange", 1), ("Strawberry", 2)});
+
+        let mut tally = DefaultTally::new(3, Quota::Droop);
+        tally.add_weighted(vec!["Orange"], 4.0).unwrap();
+        tally.add_weighted(vec!["Pear", "Orange"], 2.0).unwrap();
+        tally.add_weighted(vec!["Chocolate", "Strawberry"], 8.0).unwrap();
+        tally.add_weighted(vec!["Chocolate", "Sweets"], 4.0).unwrap();
+        tally.add_weighted_ref(&["Strawberry"], 1.0).unwrap();
+        tally.add_weighted_ref(&["Sweets"], 1.0).unwrap();
+        let winners = tally.winners();
+        assert_eq!(winners.into_vec(), vec! {("Chocolate", 0), ("Orange", 1), ("Strawberry", 2)});
+
+        Ok(())
+    }
+
+    #[test]
+    fn stv_candidates() -> Result<(), TallyError> {
+        let candidates = Candidates::new(vec!["Alice", "Bob", "Carlos", "Dave"]).withdrawn(vec!["Dave"]);
+        let mut tally = DefaultTally::with_candidates(1, Quota::Droop, candidates);
+
+        // Votes for Dave move to their next preference, and votes are never transferred to unranked candidates.
+        tally.add_weighted(vec!["Dave", "Alice"], 3.0)?;
+        tally.add_weighted(vec!["Bob"], 1.0)?;
+        tally.add_weighted(vec!["Carlos", "Bob"], 2.0)?;
+        assert!(tally.add(vec!["Erin"]).is_err());
+
+        let winners = tally.winners();
+        assert_eq!(winners.into_vec(), vec! {("Alice", 0)});
+
+        Ok(())
+    }
+
+    #[cfg(feature = "rational")]
+    #[test]
+    fn stv_rational() -> Result<(), TallyError> {
+        use num_rational::Rational64;
+
+        let votes = [
+            (vec!["Dave", "Alice"], 3),
+            (vec!["Alice", "Dave"], 2),
+            (vec!["Carlos"], 3),
+            (vec!["Alice", "Bob", "Carlos"], 2),
+        ];
+
+        // The Hagenbach-Bischoff quota is 10/3. Alice is elected with 4 votes, and her surplus of 2/3 is transferred
+        // at a value of 1/6 per vote. Dave receives 2/6, which brings him to exactly 10/3 and elects him.
+        let mut tally = Tally::<&str, Rational64>::new(2, Quota::Hagenbach);
+        for (vote, weight) in votes.iter() {
+            tally.add_weighted_ref(vote, Rational64::from_integer(*weight))?;
+        }
+        assert_eq!(tally.winners().into_vec(), vec! {("Alice", 0), ("Dave", 1)});
+
+        // With floats, Dave falls just short of the quota, and the count goes on to an incorrect result.
+        let mut tally = Tally::<&str, f64>::new(2, Quota::Hagenbach);
+        for (vote, weight) in votes.iter() {
+            tally.add_weighted_ref(vote, *weight as f64)?;
+        }
+        assert_ne!(tally.winners().into_vec(), vec! {("Alice", 0), ("Dave", 1)});
+
+        Ok(())
+    }
+
     #[test]
     fn stv_wikipedia_test() -> Result<(), ()> {
         // From https://en.wikipedia.org/wiki/Single_transferable_vote#Counting_the_votes
         let mut tally = DefaultTally::new(3, Quota::Droop);
         for _ in 0..4 {
-            tally.add(vec!["Orange"]);
+            tally.add(vec!["Orange"]).unwrap();
         }
         for _ in 0..2 {
-            tally.add(vec!["Pear", "Orange"]);
+            tally.add(vec!["Pear", "Orange"]).unwrap();
         }
         for _ in 0..8 {
-            tally.add(vec!["Chocolate", "Strawberry"]);
+            tally.add(vec!["Chocolate", "Strawberry"]).unwrap();
         }
         for _ in 0..4 {
-            tally.add(vec!["Chocolate", "Sweets"]);
+            tally.add(vec!["Chocolate", "Sweets"]).unwrap();
         }
-        tally.add(vec!["Strawberry"]);
-        tally.add(vec!["Sweets"]);
+        tally.add(vec!["Strawberry"]).unwrap();
+        tally.add(vec!["Sweets"]).unwrap();
 
         let winners = tally.winners();
         assert_eq!(winners.into_vec(), vec! {("Chocolate", 0), ("Orange", 1), ("Strawberry", 2)});
@@ -296,28 +729,28 @@ mod tests {
         let mut hare_tally = DefaultTally::new(5, Quota::Hare);
         let mut droop_tally = DefaultTally::new(5, Quota::Droop);
         for _ in 0..31 {
-            hare_tally.add(vec!["Andrea", "Carter", "Brad"]);
-            droop_tally.add(vec!["Andrea", "Carter", "Brad"]);
+            hare_tally.add(vec!["Andrea", "Carter", "Brad"]).unwrap();
+            droop_tally.add(vec!["Andrea", "Carter", "Brad"]).unwrap();
         }
         for _ in 0..30 {
-            hare_tally.add(vec!["Carter", "Andrea", "Brad"]);
-            droop_tally.add(vec!["Carter", "Andrea", "Brad"]);
+            hare_tally.add(vec!["Carter", "Andrea", "Brad"]).unwrap();
+            droop_tally.add(vec!["Carter", "Andrea", "Brad"]).unwrap();
         }
         for _ in 0..2 {
-            hare_tally.add(vec!["Brad", "Andrea", "Carter"]);
-            droop_tally.add(vec!["Brad", "Andrea", "Carter"]);
+            hare_tally.add(vec!["Brad", "Andrea", "Carter"]).unwrap();
+            droop_tally.add(vec!["Brad", "Andrea", "Carter"]).unwrap();
         }
         for _ in 0..20 {
-            hare_tally.add(vec!["Delilah", "Scott", "Jennifer"]);
-            droop_tally.add(vec!["Delilah", "Scott", "Jennifer"]);
+            hare_tally.add(vec!["Delilah", "Scott", "Jennifer"]).unwrap();
+            droop_tally.add(vec!["Delilah", "Scott", "Jennifer"]).unwrap();
         }
         for _ in 0..20 {
-            hare_tally.add(vec!["Scott", "Delilah", "Jennifer"]);
-            droop_tally.add(vec!["Scott", "Delilah", "Jennifer"]);
+            hare_tally.add(vec!["Scott", "Delilah", "Jennifer"]).unwrap();
+            droop_tally.add(vec!["Scott", "Delilah", "Jennifer"]).unwrap();
         }
         for _ in 0..17 {
-            hare_tally.add(vec!["Jennifer", "Delilah", "Scott"]);
-            droop_tally.add(vec!["Jennifer", "Delilah", "Scott"]);
+            hare_tally.add(vec!["Jennifer", "Delilah", "Scott"]).unwrap();
+            droop_tally.add(vec!["Jennifer", "Delilah", "Scott"]).unwrap();
         }
 
         let hare_winners = hare_tally.winners();
@@ -339,13 +772,13 @@ mod tests {
         // From https://en.wikipedia.org/wiki/Droop_quota
         let mut tally = DefaultTally::new(2, Quota::Droop);
         for _ in 0..45 {
-            tally.add(vec!["Andrea", "Carter"]);
+            tally.add(vec!["Andrea", "Carter"]).unwrap();
         }
         for _ in 0..25 {
-            tally.add(vec!["Carter"]);
+            tally.add(vec!["Carter"]).unwrap();
         }
         for _ in 0..30 {
-            tally.add(vec!["Brad"]);
+            tally.add(vec!["Brad"]).unwrap();
         }
 
         let winners = tally.winners();
@@ -354,13 +787,13 @@ mod tests {
         // From https://en.wikipedia.org/wiki/Hare_quota
         let mut tally = DefaultTally::new(2, Quota::Hare);
         for _ in 0..60 {
-            tally.add(vec!["Andrea", "Carter"]);
+            tally.add(vec!["Andrea", "Carter"]).unwrap();
         }
         for _ in 0..14 {
-            tally.add(vec!["Carter"]);
+            tally.add(vec!["Carter"]).unwrap();
         }
         for _ in 0..30 {
-            tally.add(vec!["Brad", "Andrea"]);
+            tally.add(vec!["Brad", "Andrea"]).unwrap();
         }
 
         let winners = tally.winners();
@@ -369,13 +802,13 @@ mod tests {
         // From https://en.wikipedia.org/wiki/Hagenbach-Bischoff_quota
         let mut tally = DefaultTally::new(2, Quota::Hagenbach);
         for _ in 0..45 {
-            tally.add(vec!["Andrea", "Carter"]);
+            tally.add(vec!["Andrea", "Carter"]).unwrap();
         }
         for _ in 0..25 {
-            tally.add(vec!["Carter"]);
+            tally.add(vec!["Carter"]).unwrap();
         }
         for _ in 0..30 {
-            tally.add(vec!["Brad"]);
+            tally.add(vec!["Brad"]).unwrap();
         }
 
         let winners = tally.winners();
@@ -385,36 +818,36 @@ mod tests {
         let mut hagen_tally = DefaultTally::new(7, Quota::Hagenbach);
         let mut droop_tally = DefaultTally::new(7, Quota::Droop);
         for _ in 0..14 {
-            hagen_tally.add(vec!["Andrea", "Carter", "Brad", "Delilah"]);
-            droop_tally.add(vec!["Andrea", "Carter", "Brad", "Delilah"]);
+            hagen_tally.add(vec!["Andrea", "Carter", "Brad", "Delilah"]).unwrap();
+            droop_tally.add(vec!["Andrea", "Carter", "Brad", "Delilah"]).unwrap();
         }
         for _ in 0..14 {
-            hagen_tally.add(vec!["Carter", "Andrea", "Brad", "Delilah"]);
-            droop_tally.add(vec!["Carter", "Andrea", "Brad", "Delilah"]);
+            hagen_tally.add(vec!["Carter", "Andrea", "Brad", "Delilah"]).unwrap();
+            droop_tally.add(vec!["Carter", "Andrea", "Brad", "Delilah"]).unwrap();
         }
         for _ in 0..14 {
-            hagen_tally.add(vec!["Brad", "Andrea", "Carter", "Delilah"]);
-            droop_tally.add(vec!["Brad", "Andrea", "Carter", "Delilah"]);
+            hagen_tally.add(vec!["Brad", "Andrea", "Carter", "Delilah"]).unwrap();
+            droop_tally.add(vec!["Brad", "Andrea", "Carter", "Delilah"]).unwrap();
         }
         for _ in 0..11 {
-            hagen_tally.add(vec!["Delilah", "Andrea", "Carter", "Brad"]);
-            droop_tally.add(vec!["Delilah", "Andrea", "Carter", "Brad"]);
+            hagen_tally.add(vec!["Delilah", "Andrea", "Carter", "Brad"]).unwrap();
+            droop_tally.add(vec!["Delilah", "Andrea", "Carter", "Brad"]).unwrap();
         }
         for _ in 0..13 {
-            hagen_tally.add(vec!["Scott", "Jennifer", "Matt", "Susan"]);
-            droop_tally.add(vec!["Scott", "Jennifer", "Matt", "Susan"]);
+            hagen_tally.add(vec!["Scott", "Jennifer", "Matt", "Susan"]).unwrap();
+            droop_tally.add(vec!["Scott", "Jennifer", "Matt", "Susan"]).unwrap();
         }
         for _ in 0..13 {
-            hagen_tally.add(vec!["Jennifer", "Scott", "Matt", "Susan"]);
-            droop_tally.add(vec!["Jennifer", "Scott", "Matt", "Susan"]);
+            hagen_tally.add(vec!["Jennifer", "Scott", "Matt", "Susan"]).unwrap();
+            droop_tally.add(vec!["Jennifer", "Scott", "Matt", "Susan"]).unwrap();
         }
         for _ in 0..13 {
-            hagen_tally.add(vec!["Matt", "Scott", "Jennifer", "Susan"]);
-            droop_tally.add(vec!["Matt", "Scott", "Jennifer", "Susan"]);
+            hagen_tally.add(vec!["Matt", "Scott", "Jennifer", "Susan"]).unwrap();
+            droop_tally.add(vec!["Matt", "Scott", "Jennifer", "Susan"]).unwrap();
         }
         for _ in 0..12 {
-            hagen_tally.add(vec!["Susan", "Scott", "Jennifer", "Matt"]);
-            droop_tally.add(vec!["Susan", "Scott", "Jennifer", "Matt"]);
+            hagen_tally.add(vec!["Susan", "Scott", "Jennifer", "Matt"]).unwrap();
+            droop_tally.add(vec!["Susan", "Scott", "Jennifer", "Matt"]).unwrap();
         }
 
         let hagen_winners = hagen_tally.winners();
@@ -440,16 +873,16 @@ mod tests {
         // From https://en.wikipedia.org/wiki/Hagenbach-Bischoff_quota
         let mut tally = DefaultTally::new(2, Quota::Hagenbach);
         for _ in 0..50 {
-            tally.add(vec!["Andrea", "Brad"]);
+            tally.add(vec!["Andrea", "Brad"]).unwrap();
         }
         for _ in 0..150 {
-            tally.add(vec!["Andrea", "Carter"]);
+            tally.add(vec!["Andrea", "Carter"]).unwrap();
         }
         for _ in 0..75 {
-            tally.add(vec!["Brad", "Carter"]);
+            tally.add(vec!["Brad", "Carter"]).unwrap();
         }
         for _ in 0..25 {
-            tally.add(vec!["Carter", "Brad"]);
+            tally.add(vec!["Carter", "Brad"]).unwrap();
         }
 
         let winners = tally.winners();
@@ -461,4 +894,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stv_parallel() {
+        // From https://en.wikipedia.org/wiki/Single_transferable_vote#Counting_the_votes
+        let mut votes = Vec::new();
+        votes.extend(vec![vec!["Orange"]; 4]);
+        votes.extend(vec![vec!["Pear", "Orange"]; 2]);
+        votes.extend(vec![vec!["Chocolate", "Strawberry"]; 8]);
+        votes.extend(vec![vec!["Chocolate", "Sweets"]; 4]);
+        votes.push(vec!["Strawberry"]);
+        votes.push(vec!["Sweets"]);
+
+        let tally = DefaultTally::from_par_iter(3, Quota::Droop, votes).unwrap();
+        assert_eq!(tally.winners(), tally.par_winners());
+        assert_eq!(
+            tally.par_winners().into_vec(),
+            vec! {("Chocolate", 0), ("Orange", 1), ("Strawberry", 2)}
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn stv_serde() {
+        let mut tally = DefaultTally::new(1, Quota::Droop);
+        tally.add_weighted(vec!["Alice", "Bob"], 3.0).unwrap();
+        tally.add_weighted(vec!["Bob"], 2.0).unwrap();
+
+        let json = serde_json::to_string(&tally).unwrap();
+        assert_eq!(
+            json,
+            r#"{"ballots":{"candidate_ids":["Alice","Bob"],"ballots":[[[[0],[1]],3.0],[[[1]],2.0]]},"num_winners":1,"quota":"Droop"}"#
+        );
+
+        let mut resumed: DefaultTally<String> = serde_json::from_str(&json).unwrap();
+        resumed.add_weighted(vec!["Bob".to_string()], 2.0).unwrap();
+        assert_eq!(resumed.winners().into_vec(), vec![("Bob".to_string(), 0)]);
+    }
 }
@@ -1,45 +1,138 @@
-use num_traits::real::Real;
+use num_traits::float::FloatCore;
 use num_traits::Num;
 
+#[cfg(feature = "rational")]
+use num_integer::Integer;
+#[cfg(feature = "rational")]
+use num_rational::Ratio;
+#[cfg(feature = "rational")]
+use num_traits::{CheckedAdd, CheckedMul};
+
 /// A trait for numeric types used to count votes.
 ///
-/// Generally seen as the generic `C` in this codebase, this type should be automatically implemented for all numeric types you wish to use.
-/// It is used to provide trait specialization so differnetial logic can applied to integer or fractional (float) based vote counting.
+/// Generally seen as the generic `C` in this codebase, it is used so that differential logic can be applied to integer
+/// or fractional (float) based vote counting.
 ///
-/// You should pobably not implement this trait. If you have a numeric type that does not implement `Numeric`,
-/// you should instead implement [`num_traits::Num`](/num-traits/latest/num_traits/trait.Num.html)
-/// (and optionally [`num_traits::real::Real`](/num-traits/latest/num_traits/real/trait.Real.html) for types that support fractions.)
-pub trait Numeric {
+/// `Numeric` is implemented for the primitive integer and float types, for the fixed-point
+/// [`FixedDecimal`](struct.FixedDecimal.html), and (with the `rational` feature) for
+/// [`num_rational::Ratio`](/num-rational/latest/num_rational/struct.Ratio.html).
+/// If you have a custom numeric type, implement [`num_traits::Num`](/num-traits/latest/num_traits/trait.Num.html)
+/// and this trait for it.
+pub trait Numeric: Num {
   /// Get the floor for this numeric type.
   /// For non-fractional types, this just returns self.
   fn floor(self) -> Self;
 
   /// Does this numeric type support fractional values?
   /// Integer-based types will return false.
-  /// Float, or num_rational::Ratio based types should return true.
+  /// Float, fixed-point, or num_rational::Ratio based types should return true.
   fn fraction() -> bool;
+
+  /// Add two numbers, returning `None` if the result overflows.
+  /// Tallies use this to count votes, and return `TallyError::CountOverflow` instead of wrapping.
+  /// Types that can't overflow, like floats, always return `Some`.
+  fn checked_add(self, other: Self) -> Option<Self>;
+
+  /// Multiply two numbers, returning `None` if the result overflows.
+  fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+// Integers can't hold fractions, so their floor is always themselves.
+macro_rules! integer_numeric {
+  ($($t:ty)*) => {$(
+    impl Numeric for $t {
+      fn floor(self) -> Self {
+        self
+      }
+      fn fraction() -> bool {
+        false
+      }
+      fn checked_add(self, other: Self) -> Option<Self> {
+        <$t>::checked_add(self, other)
+      }
+      fn checked_mul(self, other: Self) -> Option<Self> {
+        <$t>::checked_mul(self, other)
+      }
+    }
+  )*};
 }
 
-// Default implemention of numeric, assumes everything is an integer (non-fraction).
-impl<T: Num> Numeric for T {
-  default fn floor(self) -> Self {
-    self
+integer_numeric!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+// Floats use FloatCore so that floor() works without std. They become infinite instead of overflowing.
+impl Numeric for f32 {
+  fn floor(self) -> Self {
+    FloatCore::floor(self)
   }
-  default fn fraction() -> bool {
-    false
+  fn fraction() -> bool {
+    true
+  }
+  fn checked_add(self, other: Self) -> Option<Self> {
+    Some(self + other)
+  }
+  fn checked_mul(self, other: Self) -> Option<Self> {
+    Some(self * other)
   }
 }
 
-// Specialize Numeric using Real.
-// Real covers all floats, as well as things like num_rational::Ratio and num_rational::BigRational.
-impl<T: Num + Real> Numeric for T {
+impl Numeric for f64 {
   fn floor(self) -> Self {
-    self.floor()
+    FloatCore::floor(self)
   }
   fn fraction() -> bool {
     true
   }
+  fn checked_add(self, other: Self) -> Option<Self> {
+    Some(self + other)
+  }
+  fn checked_mul(self, other: Self) -> Option<Self> {
+    Some(self * other)
+  }
 }
 
-// TODO: no_std: should swap Real for num_traits::float::FloatCore
-// TODO: rational: Check that Ratio implements Real
+// Rationals, including num_rational::Rational64 and num_rational::BigRational.
+#[cfg(feature = "rational")]
+impl<T: Clone + Integer + CheckedAdd + CheckedMul> Numeric for Ratio<T> {
+  fn floor(self) -> Self {
+    Ratio::floor(&self)
+  }
+  fn fraction() -> bool {
+    true
+  }
+  fn checked_add(self, other: Self) -> Option<Self> {
+    CheckedAdd::checked_add(&self, &other)
+  }
+  fn checked_mul(self, other: Self) -> Option<Self> {
+    CheckedMul::checked_mul(&self, &other)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn numeric_test() {
+    assert_eq!(Numeric::floor(7u64), 7);
+    assert!(!u64::fraction());
+    assert_eq!(Numeric::floor(7.5f64), 7.0);
+    assert!(f64::fraction());
+    assert!(f32::fraction());
+
+    assert_eq!(Numeric::checked_add(250u8, 5), Some(255));
+    assert_eq!(Numeric::checked_add(250u8, 6), None);
+    assert_eq!(Numeric::checked_mul(u64::MAX, 2), None);
+    assert_eq!(Numeric::checked_add(f64::MAX, f64::MAX), Some(f64::INFINITY));
+  }
+
+  #[cfg(feature = "rational")]
+  #[test]
+  fn numeric_rational() {
+    use num_rational::{BigRational, Rational64};
+
+    assert_eq!(Numeric::floor(Rational64::new(15, 2)), Rational64::from_integer(7));
+    assert!(Rational64::fraction());
+    assert!(BigRational::fraction());
+    assert_eq!(Numeric::checked_add(Rational64::new(i64::MAX, 1), Rational64::new(1, 2)), None);
+  }
+}
@@ -0,0 +1,136 @@
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::approval::ApprovalTally;
+use crate::borda::{BordaTally, Variant as BordaVariant};
+use crate::condorcet::CondorcetTally;
+use crate::plurality::PluralityTally;
+use crate::schulze::{SchulzeTally, Variant as SchulzeVariant};
+use crate::stv;
+use crate::BallotBox;
+use crate::Quota;
+use crate::RankedBallot;
+use crate::RankedWinners;
+
+/// Tally ranked ballots and return the winners.
+///
+/// `method` is one of `plurality`, `approval`, `borda`, `condorcet`, `schulze` or `stv`. `ballots` is a JSON array of
+/// ballots, each an array of candidates in order of preference, where an array of candidates ranks them equally.
+/// The winners are returned as a JSON array of `{"candidate": <candidate>, "rank": <rank>}` objects, where the first
+/// rank is `1`. Votes are counted with JS numbers, and an invalid method or ballot throws an error.
+///
+/// ```js
+/// import { tally } from "tallystick";
+///
+/// const winners = JSON.parse(tally("stv", JSON.stringify([["Alice", "Bob"], ["Bob"], [["Alice", "Carlos"]]]), 1));
+/// ```
+#[wasm_bindgen]
+pub fn tally(method: &str, ballots: &str, winners: u32) -> Result<String, JsValue> {
+    return tally_json(method, ballots, winners).map_err(|e| JsValue::from_str(&e));
+}
+
+// Tally a JSON array of ranked ballots, returning the winners as JSON.
+fn tally_json(method: &str, ballots: &str, winners: u32) -> Result<String, String> {
+    let ballots = parse_ballots(ballots)?;
+    let winners = match method {
+        "plurality" => {
+            let mut tally = PluralityTally::<String, f64>::new(winners);
+            tally.add_ballot_box(&ballots).map_err(|e| e.to_string())?;
+            tally.winners()
+        }
+        "approval" => {
+            let mut tally = ApprovalTally::<String, f64>::new(winners);
+            tally.add_ballot_box(&ballots).map_err(|e| e.to_string())?;
+            tally.winners()
+        }
+        "borda" => {
+            let mut tally = BordaTally::<String, f64>::new(winners, BordaVariant::Borda);
+            tally.add_ballot_box(&ballots).map_err(|e| e.to_string())?;
+            tally.winners()
+        }
+        "condorcet" => {
+            let mut tally = CondorcetTally::<String, f64>::new(winners);
+            tally.add_ballot_box(&ballots).map_err(|e| e.to_string())?;
+            tally.winners()
+        }
+        "schulze" => {
+            let mut tally = SchulzeTally::<String, f64>::new(winners, SchulzeVariant::Winning);
+            tally.add_ballot_box(&ballots).map_err(|e| e.to_string())?;
+            tally.winners()
+        }
+        "stv" => {
+            let mut tally = stv::Tally::<String, f64>::new(winners, Quota::Droop);
+            tally.add_ballot_box(&ballots).map_err(|e| e.to_string())?;
+            tally.winners()
+        }
+        method => return Err(format!("unknown method '{}'", method)),
+    };
+    return Ok(winners_json(winners));
+}
+
+// Parse a JSON array of ranked ballots.
+fn parse_ballots(input: &str) -> Result<BallotBox<String, f64>, String> {
+    let invalid = || "ballots must be an array of arrays of candidates".to_string();
+    let input: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+    let mut ballots = BallotBox::new();
+    for ballot in input.as_array().ok_or_else(invalid)? {
+        let mut vote = RankedBallot::new();
+        for rank in ballot.as_array().ok_or_else(invalid)? {
+            match rank {
+                Value::String(candidate) => vote.push(candidate.clone()),
+                Value::Array(tied) => {
+                    let tied = tied
+                        .iter()
+                        .map(|candidate| candidate.as_str().map(String::from).ok_or_else(invalid));
+                    vote.push_tied(tied.collect::<Result<Vec<String>, String>>()?);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        ballots.add_ranked(vote).map_err(|e| e.to_string())?;
+    }
+    return Ok(ballots);
+}
+
+// Write the winners as a JSON array, ranked from 1.
+fn winners_json(winners: RankedWinners<String>) -> String {
+    let winners: Vec<Value> = winners
+        .iter()
+        .map(|(winner, rank)| json!({"candidate": winner, "rank": rank + 1}))
+        .collect();
+    return Value::Array(winners).to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wasm_tally() {
+        let ballots = r#"[["Alice", "Bob"], ["Alice"], ["Bob", "Alice"], [["Carlos", "Bob"]]]"#;
+        let winners = tally_json("plurality", ballots, 1).unwrap();
+        assert_eq!(winners, r#"[{"candidate":"Alice","rank":1}]"#);
+        let winners = tally_json("stv", ballots, 2).unwrap();
+        assert_eq!(winners, r#"[{"candidate":"Alice","rank":1},{"candidate":"Bob","rank":2}]"#);
+
+        assert!(tally_json("dictator", ballots, 1).is_err());
+        assert!(tally_json("plurality", r#"[["Alice", 1]]"#, 1).is_err());
+        assert!(tally_json("plurality", r#"{"Alice": 1}"#, 1).is_err());
+    }
+}
+
+// Run with `wasm-pack test --node -- --features wasm-bindgen`.
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn wasm_tally_js() {
+        let ballots = r#"[["Alice", "Bob"], ["Alice"], ["Bob", "Alice"]]"#;
+        assert_eq!(tally("condorcet", ballots, 1).unwrap(), r#"[{"candidate":"Alice","rank":1}]"#);
+
+        let error = tally("dictator", ballots, 1).unwrap_err();
+        assert_eq!(error.as_string().unwrap(), "unknown method 'dictator'");
+    }
+}